@@ -91,24 +91,143 @@ pub struct SearchCodeParams {
     pub top_k: usize,
     #[serde(default, alias = "repoFilter")]
     pub repo_filter: Option<String>,
+    #[serde(default, alias = "maxTokens")]
+    pub max_tokens: Option<usize>,
+    #[serde(default)]
+    pub explain: bool,
+    #[serde(default, alias = "groupByFile")]
+    pub group_by_file: bool,
+    #[serde(default, alias = "preferTests")]
+    pub prefer_tests: bool,
+    #[serde(default, alias = "contextPath")]
+    pub context_path: Option<String>,
+    #[serde(default, alias = "contextSymbol")]
+    pub context_symbol: Option<String>,
+    #[serde(default, alias = "deadlineMs")]
+    pub deadline_ms: Option<u64>,
+}
+
+impl Default for SearchCodeParams {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            top_k: default_top_k(),
+            repo_filter: None,
+            max_tokens: None,
+            explain: false,
+            group_by_file: false,
+            prefer_tests: false,
+            context_path: None,
+            context_symbol: None,
+            deadline_ms: None,
+        }
+    }
 }
 
 fn default_top_k() -> usize {
     5
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct SearchResultItem {
     pub file: String,
     pub function: String,
     pub start_line: usize,
     pub end_line: usize,
     pub code_block: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<SearchHighlight>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<SnippetDetail>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explain: Option<ResultExplanation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ResultExplanation {
+    pub chunk_id: String,
+    pub fused_score: f32,
+    pub lexical_rank: Option<usize>,
+    pub semantic_rank: Option<usize>,
+    pub exact_symbol: bool,
+    pub feedback_boost: f32,
+    #[serde(default)]
+    pub diversity_penalty: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub priors: Vec<PriorContribution>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct PriorContribution {
+    pub name: String,
+    pub delta: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SnippetDetail {
+    Full,
+    Matched,
+    Signature,
+    Truncated,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct SearchHighlight {
+    pub start_line: usize,
+    pub end_line: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub terms: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct SearchCodeResult {
     pub items: Vec<SearchResultItem>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<TokenBudgetReport>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<FileResultGroup>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub degraded: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct FileResultGroup {
+    pub file: String,
+    pub ranges: Vec<FileResultRange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct FileResultRange {
+    pub function: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub code_block: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<SearchHighlight>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<SnippetDetail>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explain: Option<ResultExplanation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct TokenBudgetReport {
+    pub max_tokens: usize,
+    pub used_tokens: usize,
+    pub elided_tokens: usize,
+    pub elided_items: usize,
+    pub estimator: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub over_budget: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
@@ -127,6 +246,65 @@ pub struct OpenLocationResult {
     pub line_end: usize,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct FindSimilarParams {
+    #[serde(default, alias = "chunkId")]
+    pub chunk_id: Option<String>,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default, alias = "lineStart")]
+    pub line_start: Option<usize>,
+    #[serde(default, alias = "lineEnd")]
+    pub line_end: Option<usize>,
+    #[serde(default = "default_top_k", alias = "topK")]
+    pub top_k: usize,
+    #[serde(default, alias = "repoFilter")]
+    pub repo_filter: Option<String>,
+    #[serde(default = "default_more_like_this", alias = "moreLikeThis")]
+    pub more_like_this: bool,
+    #[serde(default)]
+    pub explain: bool,
+    #[serde(default, alias = "deadlineMs")]
+    pub deadline_ms: Option<u64>,
+}
+
+impl Default for FindSimilarParams {
+    fn default() -> Self {
+        Self {
+            chunk_id: None,
+            path: None,
+            line_start: None,
+            line_end: None,
+            top_k: default_top_k(),
+            repo_filter: None,
+            more_like_this: default_more_like_this(),
+            explain: false,
+            deadline_ms: None,
+        }
+    }
+}
+
+fn default_more_like_this() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct FindSimilarResult {
+    pub source: SimilarSource,
+    pub items: Vec<SearchResultItem>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub degraded: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct SimilarSource {
+    pub chunk_id: String,
+    pub file: String,
+    pub function: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct CodeChunk {
     pub id: String,
@@ -155,6 +333,8 @@ pub struct SchemaBundle {
     pub search_code_result: Schema,
     pub open_location_params: Schema,
     pub open_location_result: Schema,
+    pub find_similar_params: Schema,
+    pub find_similar_result: Schema,
 }
 
 pub fn schema_bundle() -> SchemaBundle {
@@ -163,6 +343,8 @@ pub fn schema_bundle() -> SchemaBundle {
         search_code_result: schemars::schema_for!(SearchCodeResult),
         open_location_params: schemars::schema_for!(OpenLocationParams),
         open_location_result: schemars::schema_for!(OpenLocationResult),
+        find_similar_params: schemars::schema_for!(FindSimilarParams),
+        find_similar_result: schemars::schema_for!(FindSimilarResult),
     }
 }
 
@@ -172,7 +354,15 @@ mod tests {
 
     #[test]
     fn rpc_response_ok_sets_fields() {
-        let response = RpcResponse::ok(RpcId::Number(1), SearchCodeResult { items: Vec::new() });
+        let response = RpcResponse::ok(
+            RpcId::Number(1),
+            SearchCodeResult {
+                items: Vec::new(),
+                budget: None,
+                groups: Vec::new(),
+                degraded: false,
+            },
+        );
         assert_eq!(response.jsonrpc, "2.0");
         assert!(response.result.is_some());
         assert!(response.error.is_none());
@@ -21,10 +21,10 @@ pub fn resolve_runtime_ports(
     preferred_metrics: Option<u16>,
 ) -> Result<RuntimePorts> {
     let state_path = runtime_ports_path(cwd);
-    if let Some(existing) = load_ports(&state_path)? {
-        if ports_available(&existing) {
-            return Ok(existing);
-        }
+    if let Some(existing) = load_ports(&state_path)?
+        && ports_available(&existing)
+    {
+        return Ok(existing);
     }
 
     let mut reserved = Vec::new();
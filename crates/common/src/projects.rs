@@ -1,8 +1,14 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+const FEEDBACK_LOG_MAX_BYTES: u64 = 2 * 1024 * 1024;
+const FEEDBACK_LOG_KEEP_EVENTS: usize = 5_000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexedChunk {
     pub file: String,
@@ -19,6 +25,113 @@ pub struct IndexedProject {
     pub chunks_extracted: usize,
     pub indexed_at_unix: u64,
     pub chunks: Vec<IndexedChunk>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_template_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetrievalProfile {
+    pub rrf_k: usize,
+    pub lexical_weight: f32,
+    pub vector_weight: f32,
+    pub exact_symbol_weight: f32,
+    #[serde(default)]
+    pub dataset_version: String,
+    #[serde(default)]
+    pub mrr_at_10: f64,
+    #[serde(default)]
+    pub baseline_mrr_at_10: f64,
+    #[serde(default)]
+    pub tuned_at_unix: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FeedbackEvent {
+    pub query: String,
+    pub chunk_id: String,
+    pub file: String,
+    pub rank: usize,
+    pub unix_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PinnedResult {
+    pub id: String,
+    pub query_pattern: String,
+    pub file: String,
+    #[serde(default)]
+    pub symbol: Option<String>,
+    pub start_line: usize,
+    pub end_line: usize,
+    #[serde(default)]
+    pub created_at_unix: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct PinSet {
+    pins: Vec<PinnedResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct StateManifest {
+    #[serde(default)]
+    pub projects: BTreeMap<String, ProjectGeneration>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ProjectGeneration {
+    pub generation: u64,
+    pub updated_at_unix_ms: u64,
+    #[serde(default)]
+    pub feedback_generation: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VectorCollectionState {
+    pub collection: String,
+    #[serde(default)]
+    pub version: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub migration: Option<VectorMigration>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VectorMigration {
+    pub target: String,
+    pub version: u64,
+    pub model_id: String,
+    pub started_at_unix: u64,
+}
+
+impl VectorCollectionState {
+    pub fn begin_migration(
+        &mut self,
+        project_path: &str,
+        model_id: &str,
+        now_unix: u64,
+    ) -> VectorMigration {
+        let version = self
+            .migration
+            .as_ref()
+            .map_or(self.version, |migration| migration.version)
+            + 1;
+        let migration = VectorMigration {
+            target: versioned_vector_collection(project_path, version),
+            version,
+            model_id: model_id.to_string(),
+            started_at_unix: now_unix,
+        };
+        self.migration = Some(migration.clone());
+        migration
+    }
+
+    pub fn finish_migration(&mut self) -> Option<String> {
+        let migration = self.migration.take()?;
+        self.version = migration.version;
+        Some(std::mem::replace(&mut self.collection, migration.target))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -57,6 +170,7 @@ pub fn save_project_index(cwd: &Path, indexed: &IndexedProject) -> anyhow::Resul
     std::fs::create_dir_all(project_indexes_dir(cwd))?;
     std::fs::write(target, serde_json::to_string_pretty(indexed)?)?;
     upsert_catalog_entry(cwd, indexed)?;
+    bump_index_generation(cwd, &indexed.project_path)?;
     Ok(())
 }
 
@@ -67,6 +181,32 @@ pub fn load_project_index(cwd: &Path, project_path: &str) -> Option<IndexedProje
         .and_then(|raw| serde_json::from_str::<IndexedProject>(&raw).ok())
 }
 
+pub fn project_index_version(
+    cwd: &Path,
+    project_path: &str,
+) -> Option<(std::time::SystemTime, u64)> {
+    let meta = std::fs::metadata(project_index_file(cwd, project_path)).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+pub fn record_embedding_metadata(
+    cwd: &Path,
+    project_path: &str,
+    model_id: &str,
+    template_version: &str,
+) -> anyhow::Result<()> {
+    let mut indexed = load_project_index(cwd, project_path)
+        .ok_or_else(|| anyhow::anyhow!("project not indexed: {project_path}"))?;
+    if indexed.embedding_model.as_deref() == Some(model_id)
+        && indexed.embedding_template_version.as_deref() == Some(template_version)
+    {
+        return Ok(());
+    }
+    indexed.embedding_model = Some(model_id.to_string());
+    indexed.embedding_template_version = Some(template_version.to_string());
+    save_project_index(cwd, &indexed)
+}
+
 pub fn remove_project_index(cwd: &Path, project_path: &str) -> anyhow::Result<()> {
     let index_file = project_index_file(cwd, project_path);
     let storage_dir = project_storage_dir(cwd, project_path);
@@ -83,6 +223,242 @@ pub fn remove_project_index(cwd: &Path, project_path: &str) -> anyhow::Result<()
         project_catalog_file(cwd),
         serde_json::to_string_pretty(&catalog)?,
     )?;
+    bump_index_generation(cwd, project_path)?;
+    Ok(())
+}
+
+pub fn read_state_manifest(cwd: &Path) -> StateManifest {
+    std::fs::read_to_string(state_manifest_file(cwd))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<StateManifest>(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn index_generation(cwd: &Path, project_path: &str) -> u64 {
+    read_state_manifest(cwd)
+        .projects
+        .get(project_path)
+        .map(|entry| entry.generation)
+        .unwrap_or(0)
+}
+
+pub fn feedback_generation(cwd: &Path, project_path: &str) -> u64 {
+    read_state_manifest(cwd)
+        .projects
+        .get(project_path)
+        .map(|entry| entry.feedback_generation)
+        .unwrap_or(0)
+}
+
+pub fn bump_index_generation(cwd: &Path, project_path: &str) -> anyhow::Result<u64> {
+    let _lock = lock_project_state(cwd, project_path)?;
+    let entry = update_state_manifest(cwd, project_path, |entry| entry.generation += 1)?;
+    Ok(entry.generation)
+}
+
+// The watcher, CLI and servers all write project state, so read-modify-writes run under an
+// exclusive lock on a sibling of the state manifest that the atomic rename never replaces.
+fn lock_project_state(cwd: &Path, project_path: &str) -> anyhow::Result<std::fs::File> {
+    let target = state_manifest_file(cwd);
+    assert_state_write_target(cwd, project_path, &target)?;
+    std::fs::create_dir_all(codivex_dir(cwd))?;
+    let lock = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(target.with_extension("lock"))?;
+    lock.lock()?;
+    Ok(lock)
+}
+
+fn update_state_manifest(
+    cwd: &Path,
+    project_path: &str,
+    update: impl FnOnce(&mut ProjectGeneration),
+) -> anyhow::Result<ProjectGeneration> {
+    let target = state_manifest_file(cwd);
+    let mut manifest = read_state_manifest(cwd);
+    let entry = manifest
+        .projects
+        .entry(project_path.to_string())
+        .or_default();
+    update(entry);
+    entry.updated_at_unix_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let updated = *entry;
+    let staging = target.with_extension(format!("json.{}.tmp", std::process::id()));
+    std::fs::write(&staging, serde_json::to_string_pretty(&manifest)?)?;
+    std::fs::rename(staging, target)?;
+    Ok(updated)
+}
+
+pub fn save_retrieval_profile(
+    cwd: &Path,
+    project_path: &str,
+    profile: &RetrievalProfile,
+) -> anyhow::Result<()> {
+    let target = project_retrieval_profile_file(cwd, project_path);
+    assert_state_write_target(cwd, project_path, &target)?;
+    std::fs::create_dir_all(project_storage_dir(cwd, project_path))?;
+    std::fs::write(target, serde_json::to_string_pretty(profile)?)?;
+    bump_index_generation(cwd, project_path)?;
+    Ok(())
+}
+
+pub fn load_retrieval_profile(cwd: &Path, project_path: &str) -> Option<RetrievalProfile> {
+    std::fs::read_to_string(project_retrieval_profile_file(cwd, project_path))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<RetrievalProfile>(&raw).ok())
+}
+
+pub fn save_vector_collection_state(
+    cwd: &Path,
+    project_path: &str,
+    state: &VectorCollectionState,
+) -> anyhow::Result<()> {
+    let target = project_vector_state_file(cwd, project_path);
+    assert_state_write_target(cwd, project_path, &target)?;
+    std::fs::create_dir_all(project_storage_dir(cwd, project_path))?;
+    std::fs::write(target, serde_json::to_string_pretty(state)?)?;
+    bump_index_generation(cwd, project_path)?;
+    Ok(())
+}
+
+pub fn load_vector_collection_state(cwd: &Path, project_path: &str) -> VectorCollectionState {
+    std::fs::read_to_string(project_vector_state_file(cwd, project_path))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<VectorCollectionState>(&raw).ok())
+        .unwrap_or_else(|| VectorCollectionState {
+            collection: project_vector_collection(project_path),
+            version: 0,
+            migration: None,
+        })
+}
+
+pub fn active_vector_collection(cwd: &Path, project_path: &str) -> String {
+    load_vector_collection_state(cwd, project_path).collection
+}
+
+pub fn writable_vector_collection(cwd: &Path, project_path: &str) -> String {
+    let state = load_vector_collection_state(cwd, project_path);
+    state
+        .migration
+        .map_or(state.collection, |migration| migration.target)
+}
+
+pub fn vector_migration_in_progress(cwd: &Path, project_path: &str) -> bool {
+    load_vector_collection_state(cwd, project_path)
+        .migration
+        .is_some()
+}
+
+pub fn append_feedback_event(
+    cwd: &Path,
+    project_path: &str,
+    event: &FeedbackEvent,
+) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let target = project_feedback_file(cwd, project_path);
+    assert_state_write_target(cwd, project_path, &target)?;
+    std::fs::create_dir_all(project_storage_dir(cwd, project_path))?;
+    let _lock = lock_project_state(cwd, project_path)?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&target)?;
+    writeln!(file, "{}", serde_json::to_string(event)?)?;
+    if file.metadata()?.len() > FEEDBACK_LOG_MAX_BYTES {
+        compact_feedback_log(&target, FEEDBACK_LOG_KEEP_EVENTS)?;
+    }
+    update_state_manifest(cwd, project_path, |entry| entry.feedback_generation += 1)?;
+    Ok(())
+}
+
+fn compact_feedback_log(target: &Path, keep: usize) -> anyhow::Result<()> {
+    let raw = std::fs::read_to_string(target)?;
+    let lines = raw.lines().collect::<Vec<_>>();
+    let mut kept = lines[lines.len().saturating_sub(keep)..].join("\n");
+    kept.push('\n');
+    let staging = target.with_extension(format!("jsonl.{}.tmp", std::process::id()));
+    std::fs::write(&staging, kept)?;
+    std::fs::rename(staging, target)?;
+    Ok(())
+}
+
+pub fn load_feedback_events(cwd: &Path, project_path: &str) -> Vec<FeedbackEvent> {
+    std::fs::read_to_string(project_feedback_file(cwd, project_path))
+        .map(|raw| {
+            raw.lines()
+                .filter_map(|line| serde_json::from_str::<FeedbackEvent>(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn reset_feedback(cwd: &Path, project_path: &str) -> anyhow::Result<usize> {
+    let target = project_feedback_file(cwd, project_path);
+    assert_state_write_target(cwd, project_path, &target)?;
+    let _lock = lock_project_state(cwd, project_path)?;
+    let removed = load_feedback_events(cwd, project_path).len();
+    if target.exists() {
+        std::fs::remove_file(target)?;
+        update_state_manifest(cwd, project_path, |entry| entry.feedback_generation += 1)?;
+    }
+    Ok(removed)
+}
+
+pub fn load_pins(cwd: &Path, project_path: &str) -> Vec<PinnedResult> {
+    std::fs::read_to_string(project_pins_file(cwd, project_path))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<PinSet>(&raw).ok())
+        .map(|set| set.pins)
+        .unwrap_or_default()
+}
+
+pub fn add_pin(
+    cwd: &Path,
+    project_path: &str,
+    mut pin: PinnedResult,
+) -> anyhow::Result<PinnedResult> {
+    let _lock = lock_project_state(cwd, project_path)?;
+    let mut pins = load_pins(cwd, project_path);
+    let next = pins
+        .iter()
+        .filter_map(|p| p.id.strip_prefix('p').and_then(|n| n.parse::<u64>().ok()))
+        .max()
+        .unwrap_or(0)
+        + 1;
+    pin.id = format!("p{next}");
+    pins.push(pin.clone());
+    save_pins(cwd, project_path, pins)?;
+    Ok(pin)
+}
+
+pub fn remove_pin(cwd: &Path, project_path: &str, id: &str) -> anyhow::Result<bool> {
+    let _lock = lock_project_state(cwd, project_path)?;
+    let mut pins = load_pins(cwd, project_path);
+    let before = pins.len();
+    pins.retain(|p| p.id != id);
+    if pins.len() == before {
+        return Ok(false);
+    }
+    save_pins(cwd, project_path, pins)?;
+    Ok(true)
+}
+
+// Callers hold `lock_project_state`, so the generation is bumped in place rather than through
+// `bump_index_generation`, which would take the lock again.
+fn save_pins(cwd: &Path, project_path: &str, pins: Vec<PinnedResult>) -> anyhow::Result<()> {
+    let target = project_pins_file(cwd, project_path);
+    assert_state_write_target(cwd, project_path, &target)?;
+    std::fs::create_dir_all(project_storage_dir(cwd, project_path))?;
+    let staging = target.with_extension(format!("json.{}.tmp", std::process::id()));
+    std::fs::write(&staging, serde_json::to_string_pretty(&PinSet { pins })?)?;
+    std::fs::rename(staging, target)?;
+    update_state_manifest(cwd, project_path, |entry| entry.generation += 1)?;
     Ok(())
 }
 
@@ -146,6 +522,13 @@ pub fn project_vector_collection(project_path: &str) -> String {
     format!("code_chunks_{}", project_key(project_path))
 }
 
+pub fn versioned_vector_collection(project_path: &str, version: u64) -> String {
+    match version {
+        0 => project_vector_collection(project_path),
+        version => format!("{}_v{version}", project_vector_collection(project_path)),
+    }
+}
+
 fn codivex_dir(cwd: &Path) -> PathBuf {
     cwd.join(".codivex")
 }
@@ -158,6 +541,22 @@ fn project_index_file(cwd: &Path, project_path: &str) -> PathBuf {
     project_indexes_dir(cwd).join(format!("{}.json", project_key(project_path)))
 }
 
+fn project_retrieval_profile_file(cwd: &Path, project_path: &str) -> PathBuf {
+    project_storage_dir(cwd, project_path).join("retrieval-profile.json")
+}
+
+fn project_vector_state_file(cwd: &Path, project_path: &str) -> PathBuf {
+    project_storage_dir(cwd, project_path).join("vector-collection.json")
+}
+
+fn project_feedback_file(cwd: &Path, project_path: &str) -> PathBuf {
+    project_storage_dir(cwd, project_path).join("feedback.jsonl")
+}
+
+fn project_pins_file(cwd: &Path, project_path: &str) -> PathBuf {
+    project_storage_dir(cwd, project_path).join("pins.json")
+}
+
 fn selected_project_file(cwd: &Path) -> PathBuf {
     codivex_dir(cwd).join("selected-project.txt")
 }
@@ -166,6 +565,10 @@ fn project_catalog_file(cwd: &Path) -> PathBuf {
     codivex_dir(cwd).join("project-catalog.json")
 }
 
+fn state_manifest_file(cwd: &Path) -> PathBuf {
+    codivex_dir(cwd).join("state-manifest.json")
+}
+
 fn assert_state_write_target(cwd: &Path, project_path: &str, target: &Path) -> anyhow::Result<()> {
     let state_root = codivex_dir(cwd);
     if !target.starts_with(&state_root) {
@@ -187,7 +590,10 @@ fn assert_state_write_target(cwd: &Path, project_path: &str, target: &Path) -> a
 #[cfg(test)]
 mod tests {
     use super::{
-        IndexedProject, assert_state_write_target, project_lexical_index_dir, project_storage_dir,
+        FeedbackEvent, IndexedProject, PinnedResult, RetrievalProfile, add_pin,
+        append_feedback_event, assert_state_write_target, index_generation, load_feedback_events,
+        load_pins, load_retrieval_profile, project_lexical_index_dir, project_storage_dir,
+        remove_pin, reset_feedback, save_retrieval_profile,
     };
 
     #[test]
@@ -219,7 +625,251 @@ mod tests {
             chunks_extracted: 0,
             indexed_at_unix: 1,
             chunks: Vec::new(),
+            embedding_template_version: None,
+            embedding_model: None,
+        };
+        super::save_project_index(&cwd, &project).expect("save index");
+    }
+
+    #[test]
+    fn retrieval_profile_roundtrips_through_storage_dir() {
+        let cwd = std::env::temp_dir().join(format!("codivex-profile-{}", std::process::id()));
+        std::fs::create_dir_all(&cwd).expect("cwd");
+        let repo = "/tmp/repo-d";
+        assert!(load_retrieval_profile(&cwd, repo).is_none());
+        let profile = RetrievalProfile {
+            rrf_k: 20,
+            lexical_weight: 1.2,
+            vector_weight: 0.4,
+            exact_symbol_weight: 2.0,
+            dataset_version: "v1".to_string(),
+            mrr_at_10: 0.9,
+            baseline_mrr_at_10: 0.8,
+            tuned_at_unix: 1,
+        };
+        save_retrieval_profile(&cwd, repo, &profile).expect("save profile");
+        assert_eq!(load_retrieval_profile(&cwd, repo), Some(profile));
+    }
+
+    #[test]
+    fn feedback_events_append_load_and_reset() {
+        let cwd = std::env::temp_dir().join(format!("codivex-feedback-{}", std::process::id()));
+        std::fs::create_dir_all(&cwd).expect("cwd");
+        let repo = "/tmp/repo-e";
+        let _ = reset_feedback(&cwd, repo);
+        let event = FeedbackEvent {
+            query: "iso to date".to_string(),
+            chunk_id: "src/date.rs:1:3:iso_to_date".to_string(),
+            file: "src/date.rs".to_string(),
+            rank: 3,
+            unix_ms: 1,
+        };
+        let before = index_generation(&cwd, repo);
+        let feedback_before = super::feedback_generation(&cwd, repo);
+        append_feedback_event(&cwd, repo, &event).expect("append");
+        append_feedback_event(&cwd, repo, &event).expect("append");
+        assert_eq!(load_feedback_events(&cwd, repo).len(), 2);
+        assert_eq!(index_generation(&cwd, repo), before);
+        assert_eq!(super::feedback_generation(&cwd, repo), feedback_before + 2);
+        for _ in 0..3 {
+            append_feedback_event(&cwd, repo, &event).expect("append");
+        }
+        super::compact_feedback_log(&super::project_feedback_file(&cwd, repo), 4).expect("compact");
+        assert_eq!(load_feedback_events(&cwd, repo).len(), 4);
+        assert_eq!(reset_feedback(&cwd, repo).expect("reset"), 4);
+        assert!(load_feedback_events(&cwd, repo).is_empty());
+    }
+
+    #[test]
+    fn pins_add_list_and_remove() {
+        let cwd = std::env::temp_dir().join(format!("codivex-pins-{}", std::process::id()));
+        std::fs::create_dir_all(&cwd).expect("cwd");
+        let repo = "/tmp/repo-f";
+        for pin in load_pins(&cwd, repo) {
+            let _ = remove_pin(&cwd, repo, &pin.id);
+        }
+        let pin = |pattern: &str| PinnedResult {
+            id: String::new(),
+            query_pattern: pattern.to_string(),
+            file: "src/auth.rs".to_string(),
+            symbol: Some("check_auth".to_string()),
+            start_line: 10,
+            end_line: 20,
+            created_at_unix: 1,
+        };
+        let first = add_pin(&cwd, repo, pin("auth checked")).expect("add");
+        let second = add_pin(&cwd, repo, pin("build router")).expect("add");
+        assert_eq!((first.id.as_str(), second.id.as_str()), ("p1", "p2"));
+        assert!(remove_pin(&cwd, repo, "p1").expect("remove"));
+        assert!(!remove_pin(&cwd, repo, "p1").expect("remove"));
+        assert_eq!(load_pins(&cwd, repo), vec![second]);
+    }
+
+    #[test]
+    fn index_mutations_bump_project_generation() {
+        let cwd = std::env::temp_dir().join(format!("codivex-generation-{}", std::process::id()));
+        std::fs::create_dir_all(&cwd).expect("cwd");
+        let repo = "/tmp/repo-g";
+        let project = IndexedProject {
+            project_path: repo.to_string(),
+            files_scanned: 0,
+            chunks_extracted: 0,
+            indexed_at_unix: 1,
+            chunks: Vec::new(),
+            embedding_template_version: None,
+            embedding_model: None,
+        };
+        let before = index_generation(&cwd, repo);
+        super::save_project_index(&cwd, &project).expect("save index");
+        let saved = index_generation(&cwd, repo);
+        assert_eq!(saved, before + 1);
+        assert_eq!(index_generation(&cwd, "/tmp/other-repo"), 0);
+        super::remove_project_index(&cwd, repo).expect("remove index");
+        assert_eq!(index_generation(&cwd, repo), saved + 1);
+    }
+
+    #[test]
+    fn concurrent_generation_bumps_are_not_lost() {
+        let cwd = std::env::temp_dir().join(format!("codivex-bumps-{}", std::process::id()));
+        std::fs::create_dir_all(&cwd).expect("cwd");
+        let repo = "/tmp/repo-bumps";
+        let before = index_generation(&cwd, repo);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..10 {
+                        super::bump_index_generation(&cwd, repo).expect("bump");
+                    }
+                });
+            }
+        });
+        assert_eq!(index_generation(&cwd, repo), before + 40);
+    }
+
+    #[test]
+    fn concurrent_feedback_appends_are_not_lost() {
+        let cwd = std::env::temp_dir().join(format!("codivex-appends-{}", std::process::id()));
+        std::fs::create_dir_all(&cwd).expect("cwd");
+        let repo = "/tmp/repo-appends";
+        let _ = reset_feedback(&cwd, repo);
+        let before = super::feedback_generation(&cwd, repo);
+        let event = FeedbackEvent {
+            query: "q".to_string(),
+            chunk_id: "src/a.rs:1:2:a".to_string(),
+            file: "src/a.rs".to_string(),
+            rank: 1,
+            unix_ms: 1,
+        };
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..10 {
+                        append_feedback_event(&cwd, repo, &event).expect("append");
+                    }
+                });
+            }
+        });
+        assert_eq!(load_feedback_events(&cwd, repo).len(), 40);
+        assert_eq!(super::feedback_generation(&cwd, repo), before + 40);
+    }
+
+    #[test]
+    fn concurrent_pin_edits_are_not_lost() {
+        let cwd = std::env::temp_dir().join(format!("codivex-pin-race-{}", std::process::id()));
+        std::fs::create_dir_all(&cwd).expect("cwd");
+        let repo = "/tmp/repo-pin-race";
+        for pin in load_pins(&cwd, repo) {
+            let _ = remove_pin(&cwd, repo, &pin.id);
+        }
+        let before = index_generation(&cwd, repo);
+        let pin = PinnedResult {
+            id: String::new(),
+            query_pattern: "q".to_string(),
+            file: "src/a.rs".to_string(),
+            symbol: None,
+            start_line: 1,
+            end_line: 2,
+            created_at_unix: 1,
+        };
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..5 {
+                        add_pin(&cwd, repo, pin.clone()).expect("add");
+                    }
+                });
+            }
+        });
+        let pins = load_pins(&cwd, repo);
+        assert_eq!(pins.len(), 20);
+        let ids = pins
+            .iter()
+            .map(|p| p.id.as_str())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(ids.len(), 20);
+        assert_eq!(index_generation(&cwd, repo), before + 20);
+    }
+
+    #[test]
+    fn embedding_metadata_is_recorded_once_per_change() {
+        let cwd = std::env::temp_dir().join(format!("codivex-template-{}", std::process::id()));
+        std::fs::create_dir_all(&cwd).expect("cwd");
+        let repo = "/tmp/repo-h";
+        assert!(super::record_embedding_metadata(&cwd, repo, "model-a", "ctx-v1").is_err());
+        let project = IndexedProject {
+            project_path: repo.to_string(),
+            files_scanned: 0,
+            chunks_extracted: 0,
+            indexed_at_unix: 1,
+            chunks: Vec::new(),
+            embedding_template_version: None,
+            embedding_model: None,
         };
         super::save_project_index(&cwd, &project).expect("save index");
+        super::record_embedding_metadata(&cwd, repo, "model-a", "ctx-v1").expect("record");
+        let recorded = index_generation(&cwd, repo);
+        super::record_embedding_metadata(&cwd, repo, "model-a", "ctx-v1").expect("record");
+        assert_eq!(index_generation(&cwd, repo), recorded);
+        super::record_embedding_metadata(&cwd, repo, "model-b", "ctx-v1").expect("record");
+        assert_eq!(index_generation(&cwd, repo), recorded + 1);
+        let stored = super::load_project_index(&cwd, repo).expect("stored");
+        assert_eq!(stored.embedding_template_version.as_deref(), Some("ctx-v1"));
+        assert_eq!(stored.embedding_model.as_deref(), Some("model-b"));
+    }
+
+    #[test]
+    fn vector_migration_switches_to_versioned_collection() {
+        let cwd = std::env::temp_dir().join(format!("codivex-migration-{}", std::process::id()));
+        std::fs::create_dir_all(&cwd).expect("cwd");
+        let repo = "/tmp/repo-i";
+        let mut state = super::load_vector_collection_state(&cwd, repo);
+        assert_eq!(state.collection, super::project_vector_collection(repo));
+        assert!(!super::vector_migration_in_progress(&cwd, repo));
+
+        let migration = state.begin_migration(repo, "model-b", 7);
+        assert_eq!(migration.target, format!("{}_v1", state.collection));
+        super::save_vector_collection_state(&cwd, repo, &state).expect("save");
+        assert!(super::vector_migration_in_progress(&cwd, repo));
+        assert_eq!(
+            super::active_vector_collection(&cwd, repo),
+            super::project_vector_collection(repo)
+        );
+        assert_eq!(
+            super::writable_vector_collection(&cwd, repo),
+            migration.target
+        );
+
+        let retried = state.begin_migration(repo, "model-c", 8);
+        assert_eq!(retried.version, 2);
+        let old = state.finish_migration().expect("old collection");
+        assert_eq!(old, super::project_vector_collection(repo));
+        super::save_vector_collection_state(&cwd, repo, &state).expect("save");
+        assert!(!super::vector_migration_in_progress(&cwd, repo));
+        assert_eq!(
+            super::active_vector_collection(&cwd, repo),
+            super::versioned_vector_collection(repo, 2)
+        );
+        assert_eq!(state.finish_migration(), None);
+        let _ = std::fs::remove_dir_all(&cwd);
     }
 }
@@ -9,7 +9,7 @@ fn bench_embedding(c: &mut Criterion) {
 
     c.bench_function("embedding_batch_128", |b| {
         b.iter(|| {
-            let _ = engine.embed_batch(&batch).ok();
+            let _ = engine.embed_documents("bench", &batch).ok();
         })
     });
 }
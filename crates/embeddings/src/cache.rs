@@ -0,0 +1,458 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::Result;
+use lru::LruCache;
+use parking_lot::Mutex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+const MAGIC: &[u8; 8] = b"CVXEMB2\n";
+const RECORD_MARK: [u8; 4] = *b"CVXR";
+const LOG_FILE: &str = "embeddings.log";
+const LOCK_FILE: &str = "embeddings.lock";
+const AUTO_FLUSH_ENTRIES: usize = 4096;
+const MIN_COMPACT_BYTES: u64 = 8 * 1024 * 1024;
+
+type LogRecords = Vec<(String, Vec<f32>)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddingCacheLimits {
+    pub max_entries: usize,
+    pub max_bytes: u64,
+}
+
+impl Default for EmbeddingCacheLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 200_000,
+            max_bytes: 256 * 1024 * 1024,
+        }
+    }
+}
+
+impl EmbeddingCacheLimits {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_entries: std::env::var("CODEVIX_EMBED_CACHE_MAX_ENTRIES")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(defaults.max_entries),
+            max_bytes: std::env::var("CODEVIX_EMBED_CACHE_MAX_MB")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(|mb| mb * 1024 * 1024)
+                .unwrap_or(defaults.max_bytes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct EmbeddingCacheStats {
+    pub entries: usize,
+    pub bytes: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+pub struct EmbeddingCache {
+    path: PathBuf,
+    limits: EmbeddingCacheLimits,
+    state: Mutex<CacheState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct CacheState {
+    entries: LruCache<String, Vec<f32>>,
+    bytes: u64,
+    pending: Vec<String>,
+    log_bytes: u64,
+}
+
+impl EmbeddingCache {
+    pub fn open(dir: &Path, limits: EmbeddingCacheLimits) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let cache = Self {
+            path: dir.join(LOG_FILE),
+            limits,
+            state: Mutex::new(CacheState {
+                entries: LruCache::unbounded(),
+                bytes: 0,
+                pending: Vec::new(),
+                log_bytes: 0,
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        };
+        cache.load()?;
+        Ok(cache)
+    }
+
+    pub fn from_env(state_root: &Path) -> Option<Self> {
+        let limits = EmbeddingCacheLimits::from_env();
+        if limits.max_entries == 0 || limits.max_bytes == 0 {
+            return None;
+        }
+        let dir = std::env::var("CODEVIX_EMBED_CACHE_DIR")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| state_root.join("embedding-cache"));
+        match Self::open(&dir, limits) {
+            Ok(cache) => Some(cache),
+            Err(err) => {
+                tracing::warn!(dir = %dir.display(), error = %err, "embedding cache disabled");
+                None
+            }
+        }
+    }
+
+    pub fn key(model_id: &str, text: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(text.as_bytes());
+        format!("{model_id}/{:x}", hasher.finalize())
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<f32>> {
+        let hit = self.state.lock().entries.get(key).cloned();
+        let counter = if hit.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        hit
+    }
+
+    pub fn put(&self, key: String, vector: Vec<f32>) {
+        let mut state = self.state.lock();
+        state.pending.push(key.clone());
+        self.insert(&mut state, key, vector);
+        if state.pending.len() >= AUTO_FLUSH_ENTRIES
+            && let Err(err) = self.flush_locked(&mut state)
+        {
+            tracing::warn!(error = %err, "embedding cache flush failed");
+        }
+    }
+
+    pub fn flush(&self) -> Result<()> {
+        let mut state = self.state.lock();
+        self.flush_locked(&mut state)
+    }
+
+    pub fn stats(&self) -> EmbeddingCacheStats {
+        let state = self.state.lock();
+        EmbeddingCacheStats {
+            entries: state.entries.len(),
+            bytes: state.bytes,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn insert(&self, state: &mut CacheState, key: String, vector: Vec<f32>) {
+        let added = entry_bytes(&key, &vector);
+        if let Some(old) = state.entries.put(key.clone(), vector) {
+            state.bytes -= entry_bytes(&key, &old);
+        }
+        state.bytes += added;
+        while state.entries.len() > self.limits.max_entries || state.bytes > self.limits.max_bytes {
+            let Some((key, vector)) = state.entries.pop_lru() else {
+                break;
+            };
+            state.bytes -= entry_bytes(&key, &vector);
+        }
+    }
+
+    // The MCP server and the admin UI share one log, so every read-modify-write of
+    // it happens under an exclusive lock on a sibling lock file.
+    fn lock_log(&self) -> Result<File> {
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_file_name(LOCK_FILE))?;
+        lock.lock()?;
+        Ok(lock)
+    }
+
+    fn load(&self) -> Result<()> {
+        let _lock = self.lock_log()?;
+        let Some((records, skipped)) = self.read_log()? else {
+            return Ok(());
+        };
+        let mut state = self.state.lock();
+        for (key, vector) in records {
+            self.insert(&mut state, key, vector);
+        }
+        state.log_bytes = std::fs::metadata(&self.path)?.len();
+        if skipped > 0 {
+            tracing::warn!(
+                path = %self.path.display(),
+                skipped_bytes = skipped,
+                "skipped torn embedding cache records"
+            );
+            self.compact(&mut state)?;
+        }
+        Ok(())
+    }
+
+    fn read_log(&self) -> Result<Option<(LogRecords, u64)>> {
+        let Ok(file) = File::open(&self.path) else {
+            return Ok(None);
+        };
+        let mut raw = Vec::new();
+        BufReader::new(file).read_to_end(&mut raw)?;
+        if !raw.starts_with(MAGIC) {
+            tracing::warn!(path = %self.path.display(), "discarding unreadable embedding cache");
+            let _ = std::fs::remove_file(&self.path);
+            return Ok(None);
+        }
+        Ok(Some(parse_records(&raw[MAGIC.len()..])))
+    }
+
+    fn flush_locked(&self, state: &mut CacheState) -> Result<()> {
+        if state.pending.is_empty() {
+            return Ok(());
+        }
+        let pending = std::mem::take(&mut state.pending);
+        let _lock = self.lock_log()?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        state.log_bytes = file.metadata()?.len();
+        if state.log_bytes > MIN_COMPACT_BYTES.max(state.bytes * 2) {
+            drop(file);
+            self.merge_log(state)?;
+            return self.compact(state);
+        }
+
+        let mut writer = BufWriter::new(file);
+        if state.log_bytes == 0 {
+            writer.write_all(MAGIC)?;
+            state.log_bytes = MAGIC.len() as u64;
+        }
+        for key in pending {
+            if let Some(vector) = state.entries.peek(&key) {
+                write_record(&mut writer, &key, vector)?;
+                state.log_bytes += entry_bytes(&key, vector);
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    // Keeps entries another process appended since this one loaded the log.
+    fn merge_log(&self, state: &mut CacheState) -> Result<()> {
+        let Some((records, _)) = self.read_log()? else {
+            return Ok(());
+        };
+        for (key, vector) in records {
+            if !state.entries.contains(&key) {
+                self.insert(state, key, vector);
+            }
+        }
+        Ok(())
+    }
+
+    fn compact(&self, state: &mut CacheState) -> Result<()> {
+        let tmp = self
+            .path
+            .with_extension(format!("log.{}.tmp", std::process::id()));
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        writer.write_all(MAGIC)?;
+        for (key, vector) in state.entries.iter().rev() {
+            write_record(&mut writer, key, vector)?;
+        }
+        writer.flush()?;
+        drop(writer);
+        std::fs::rename(&tmp, &self.path)?;
+        state.log_bytes = MAGIC.len() as u64 + state.bytes;
+        Ok(())
+    }
+}
+
+fn entry_bytes(key: &str, vector: &[f32]) -> u64 {
+    (RECORD_MARK.len() + 2 + key.len() + 4 + vector.len() * 4 + 4) as u64
+}
+
+fn write_record(writer: &mut impl Write, key: &str, vector: &[f32]) -> Result<()> {
+    let mut body = Vec::with_capacity(2 + key.len() + 4 + vector.len() * 4);
+    body.extend_from_slice(&(key.len() as u16).to_le_bytes());
+    body.extend_from_slice(key.as_bytes());
+    body.extend_from_slice(&(vector.len() as u32).to_le_bytes());
+    for value in vector {
+        body.extend_from_slice(&value.to_le_bytes());
+    }
+    writer.write_all(&RECORD_MARK)?;
+    writer.write_all(&body)?;
+    writer.write_all(&checksum(&body).to_le_bytes())?;
+    Ok(())
+}
+
+// Records are framed by a marker and checksum so a torn write only loses itself:
+// parsing skips forward to the next valid marker.
+fn parse_records(raw: &[u8]) -> (LogRecords, u64) {
+    let mut records = Vec::new();
+    let mut skipped = 0u64;
+    let mut pos = 0;
+    while pos < raw.len() {
+        match parse_record(&raw[pos..]) {
+            Some((key, vector, len)) => {
+                records.push((key, vector));
+                pos += len;
+            }
+            None => {
+                pos += 1;
+                skipped += 1;
+            }
+        }
+    }
+    (records, skipped)
+}
+
+fn parse_record(raw: &[u8]) -> Option<(String, Vec<f32>, usize)> {
+    let body = raw.strip_prefix(&RECORD_MARK)?;
+    let key_len = u16::from_le_bytes(body.get(..2)?.try_into().ok()?) as usize;
+    let key = body.get(2..2 + key_len)?;
+    let dim_at = 2 + key_len;
+    let dim = u32::from_le_bytes(body.get(dim_at..dim_at + 4)?.try_into().ok()?) as usize;
+    let body_len = dim_at + 4 + dim.checked_mul(4)?;
+    let sum = u32::from_le_bytes(body.get(body_len..body_len + 4)?.try_into().ok()?);
+    if checksum(&body[..body_len]) != sum {
+        return None;
+    }
+    let vector = body[dim_at + 4..body_len]
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    let key = String::from_utf8(key.to_vec()).ok()?;
+    Some((key, vector, RECORD_MARK.len() + body_len + 4))
+}
+
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5u32, |hash, byte| {
+        (hash ^ u32::from(*byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+impl std::fmt::Debug for EmbeddingCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddingCache")
+            .field("path", &self.path)
+            .field("limits", &self.limits)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EmbeddingCache, EmbeddingCacheLimits};
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("codivex-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn persists_across_reopen_and_evicts_least_recently_used() {
+        let dir = temp_dir("embed-cache");
+        let limits = EmbeddingCacheLimits {
+            max_entries: 2,
+            max_bytes: u64::MAX,
+        };
+        let cache = EmbeddingCache::open(&dir, limits).expect("open");
+        let a = EmbeddingCache::key("model", "a");
+        let b = EmbeddingCache::key("model", "b");
+        let c = EmbeddingCache::key("model", "c");
+        cache.put(a.clone(), vec![1.0, 2.0]);
+        cache.put(b.clone(), vec![3.0]);
+        assert_eq!(cache.get(&a), Some(vec![1.0, 2.0]));
+        cache.put(c.clone(), vec![4.0]);
+        assert_eq!(cache.get(&b), None);
+        cache.flush().expect("flush");
+
+        let reopened = EmbeddingCache::open(&dir, limits).expect("reopen");
+        assert_eq!(reopened.get(&c), Some(vec![4.0]));
+        assert_eq!(reopened.stats().entries, 2);
+        assert_ne!(
+            EmbeddingCache::key("model", "a"),
+            EmbeddingCache::key("other-model", "a")
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn byte_limit_bounds_cache_size() {
+        let dir = temp_dir("embed-cache-bytes");
+        let cache = EmbeddingCache::open(
+            &dir,
+            EmbeddingCacheLimits {
+                max_entries: 100,
+                max_bytes: 200,
+            },
+        )
+        .expect("open");
+        for i in 0..10 {
+            cache.put(EmbeddingCache::key("m", &i.to_string()), vec![0.0; 16]);
+        }
+        let stats = cache.stats();
+        assert!(stats.bytes <= 200);
+        assert!(stats.entries < 10);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn torn_records_only_lose_themselves() {
+        let dir = temp_dir("embed-cache-torn");
+        let limits = EmbeddingCacheLimits::default();
+        let a = EmbeddingCache::key("m", "a");
+        let b = EmbeddingCache::key("m", "b");
+        let cache = EmbeddingCache::open(&dir, limits).expect("open");
+        cache.put(a.clone(), vec![1.0; 8]);
+        cache.flush().expect("flush");
+        let log = dir.join("embeddings.log");
+        let mut raw = std::fs::read(&log).expect("log");
+        let whole = raw.len();
+        raw.extend_from_within(8..whole - 10);
+        std::fs::write(&log, raw).expect("torn write");
+        cache.put(b.clone(), vec![2.0; 8]);
+        cache.flush().expect("flush");
+
+        let reopened = EmbeddingCache::open(&dir, limits).expect("reopen");
+        assert_eq!(reopened.get(&a), Some(vec![1.0; 8]));
+        assert_eq!(reopened.get(&b), Some(vec![2.0; 8]));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn compaction_keeps_entries_appended_by_another_process() {
+        let dir = temp_dir("embed-cache-shared");
+        let limits = EmbeddingCacheLimits::default();
+        let server = EmbeddingCache::open(&dir, limits).expect("open");
+        let ui = EmbeddingCache::open(&dir, limits).expect("open");
+        let a = EmbeddingCache::key("m", "a");
+        let b = EmbeddingCache::key("m", "b");
+        server.put(a.clone(), vec![1.0; 4]);
+        server.flush().expect("flush");
+        ui.put(b.clone(), vec![2.0; 4]);
+        ui.flush().expect("flush");
+        let mut state = server.state.lock();
+        server.merge_log(&mut state).expect("merge");
+        server.compact(&mut state).expect("compact");
+        drop(state);
+
+        let reopened = EmbeddingCache::open(&dir, limits).expect("reopen");
+        assert_eq!(reopened.get(&a), Some(vec![1.0; 4]));
+        assert_eq!(reopened.get(&b), Some(vec![2.0; 4]));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::manifest::Pooling;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum QuantizationMode {
     None,
@@ -11,16 +13,60 @@ pub enum ExecutionDevice {
     GpuPreferred,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WindowAggregation {
+    #[default]
+    Mean,
+    Max,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashedBackendMode {
+    #[default]
+    Disabled,
+    Fallback,
+    Always,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteProvider {
+    OpenAi,
+    Ollama,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteEmbeddingConfig {
+    pub provider: RemoteProvider,
+    pub url: String,
+    pub model: String,
+    pub api_key: Option<String>,
+    pub auth_header: String,
+    pub timeout_ms: u64,
+    pub max_retries: usize,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EmbeddingConfig {
     pub model_path: String,
     pub tokenizer_path: Option<String>,
+    pub manifest_path: Option<String>,
     pub vector_dim: usize,
     pub max_sequence_length: usize,
     pub batch_size: usize,
     pub quantization: QuantizationMode,
     pub execution_device: ExecutionDevice,
     pub allow_pseudo_fallback: bool,
+    pub session_pool_size: usize,
+    pub intra_op_threads: Option<usize>,
+    pub pooling: Option<Pooling>,
+    pub normalize: Option<bool>,
+    pub query_prefix: Option<String>,
+    pub passage_prefix: Option<String>,
+    pub window_overlap: usize,
+    pub max_windows: usize,
+    pub window_aggregation: WindowAggregation,
+    pub remote: Option<RemoteEmbeddingConfig>,
+    pub hashed_backend: HashedBackendMode,
 }
 
 impl Default for EmbeddingConfig {
@@ -35,9 +81,17 @@ impl Default for EmbeddingConfig {
                 None
             }
         });
+        let manifest_path = std::env::var("CODEVIX_MODEL_MANIFEST")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .or_else(|| {
+                let candidate = crate::ModelManifest::sidecar_path(&model_path);
+                candidate.exists().then(|| candidate.display().to_string())
+            });
         Self {
             model_path,
             tokenizer_path,
+            manifest_path,
             vector_dim: 384,
             max_sequence_length: 256,
             batch_size: 128,
@@ -46,7 +100,104 @@ impl Default for EmbeddingConfig {
             allow_pseudo_fallback: std::env::var("CODEVIX_ALLOW_PSEUDO_EMBED")
                 .map(|v| v.eq_ignore_ascii_case("true"))
                 .unwrap_or(cfg!(test)),
+            session_pool_size: std::env::var("CODEVIX_EMBED_SESSIONS")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(2)
+                .max(1),
+            intra_op_threads: std::env::var("CODEVIX_EMBED_INTRA_THREADS")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|v| *v > 0),
+            pooling: std::env::var("CODEVIX_EMBED_POOLING")
+                .ok()
+                .and_then(|v| Pooling::parse(&v)),
+            normalize: std::env::var("CODEVIX_EMBED_NORMALIZE")
+                .ok()
+                .map(|v| v.eq_ignore_ascii_case("true")),
+            query_prefix: std::env::var("CODEVIX_EMBED_QUERY_PREFIX").ok(),
+            passage_prefix: std::env::var("CODEVIX_EMBED_PASSAGE_PREFIX").ok(),
+            window_overlap: std::env::var("CODEVIX_EMBED_WINDOW_OVERLAP")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(32),
+            max_windows: std::env::var("CODEVIX_EMBED_MAX_WINDOWS")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(8)
+                .max(1),
+            window_aggregation: std::env::var("CODEVIX_EMBED_WINDOW_AGG")
+                .map(|v| {
+                    if v.eq_ignore_ascii_case("max") {
+                        WindowAggregation::Max
+                    } else {
+                        WindowAggregation::Mean
+                    }
+                })
+                .unwrap_or_default(),
+            remote: RemoteEmbeddingConfig::from_env(),
+            hashed_backend: HashedBackendMode::from_env(),
+        }
+    }
+}
+
+impl HashedBackendMode {
+    pub fn from_env() -> Self {
+        let backend = std::env::var("CODEVIX_EMBED_BACKEND").unwrap_or_default();
+        if backend.eq_ignore_ascii_case("hashed") {
+            return Self::Always;
+        }
+        match std::env::var("CODEVIX_EMBED_HASHED_FALLBACK") {
+            Ok(v) if v.eq_ignore_ascii_case("true") => Self::Fallback,
+            _ => Self::Disabled,
+        }
+    }
+}
+
+impl RemoteEmbeddingConfig {
+    pub fn new(provider: RemoteProvider, url: &str, model: &str) -> Self {
+        Self {
+            provider,
+            url: url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            api_key: None,
+            auth_header: "Authorization".to_string(),
+            timeout_ms: 30_000,
+            max_retries: 3,
+        }
+    }
+
+    pub fn from_env() -> Option<Self> {
+        let provider = match std::env::var("CODEVIX_EMBED_BACKEND")
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "openai" => RemoteProvider::OpenAi,
+            "ollama" => RemoteProvider::Ollama,
+            _ => return None,
+        };
+        let (default_url, default_model) = match provider {
+            RemoteProvider::OpenAi => ("http://localhost:8080", "text-embedding-3-small"),
+            RemoteProvider::Ollama => ("http://localhost:11434", "nomic-embed-text"),
+        };
+        let env = |key: &str| std::env::var(key).ok().filter(|v| !v.trim().is_empty());
+        let mut out = Self::new(
+            provider,
+            &env("CODEVIX_EMBED_URL").unwrap_or_else(|| default_url.to_string()),
+            &env("CODEVIX_EMBED_MODEL").unwrap_or_else(|| default_model.to_string()),
+        );
+        out.api_key = env("CODEVIX_EMBED_API_KEY");
+        if let Some(header) = env("CODEVIX_EMBED_AUTH_HEADER") {
+            out.auth_header = header;
+        }
+        if let Some(timeout) = env("CODEVIX_EMBED_TIMEOUT_MS").and_then(|v| v.parse().ok()) {
+            out.timeout_ms = timeout;
+        }
+        if let Some(retries) = env("CODEVIX_EMBED_MAX_RETRIES").and_then(|v| v.parse().ok()) {
+            out.max_retries = retries;
         }
+        Some(out)
     }
 }
 
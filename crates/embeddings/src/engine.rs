@@ -1,29 +1,86 @@
 use std::{
+    borrow::Cow,
     collections::HashMap,
-    path::Path,
-    sync::{Arc, Mutex},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, MutexGuard, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Instant, SystemTime},
 };
 
 use anyhow::{Context, Result, anyhow};
+use dashmap::DashMap;
 use ort::{session::Session, value::Tensor};
+use serde::Serialize;
 use tokenizers::{EncodeInput, Tokenizer};
 
-use crate::config::{EmbeddingConfig, ExecutionDevice};
+use crate::{
+    cache::{EmbeddingCache, EmbeddingCacheStats},
+    config::{EmbeddingConfig, ExecutionDevice, HashedBackendMode, WindowAggregation},
+    hashed::{CorpusStats, HASHED_MODEL_ID, corpus_model_id, hashed_embed},
+    manifest::{ModelManifest, ModelProbe, Pooling},
+    remote::RemoteBackend,
+};
+
+const WARM_UP_TEXT: &str = "fn warm_up() {}";
 
 pub struct EmbeddingEngine {
     config: EmbeddingConfig,
     device_used: ExecutionDevice,
     backend: EmbeddingBackend,
+    identity: OnceLock<ModelIdentity>,
+    cache: Option<EmbeddingCache>,
+    corpus_dir: Option<PathBuf>,
+    corpora: DashMap<String, LoadedCorpus>,
+    warm_up: OnceLock<Result<u64, String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EmbeddingReadiness {
+    pub ready: bool,
+    pub warmed_up: bool,
+    pub backend: &'static str,
+    pub sessions: usize,
+    pub warm_up_ms: Option<u64>,
+    pub error: Option<String>,
+    pub cache: Option<EmbeddingCacheStats>,
 }
 
 enum EmbeddingBackend {
     Onnx(OnnxBackend),
+    Remote(RemoteBackend),
+    Hashed,
     Pseudo,
     Unavailable(String),
 }
 
+#[derive(Clone, Default)]
+struct LoadedCorpus {
+    modified: Option<SystemTime>,
+    stats: Option<Arc<CorpusStats>>,
+    digest: Option<String>,
+}
+
+impl LoadedCorpus {
+    fn new(modified: Option<SystemTime>, stats: Option<CorpusStats>) -> Self {
+        Self {
+            modified,
+            digest: stats.as_ref().map(CorpusStats::digest),
+            stats: stats.map(Arc::new),
+        }
+    }
+}
+
+struct ModelIdentity {
+    manifest: ModelManifest,
+    model_id: String,
+    error: Option<String>,
+}
+
 struct OnnxBackend {
-    session: Mutex<Session>,
+    sessions: Vec<Mutex<Session>>,
+    next_session: AtomicUsize,
     tokenizer: Option<Arc<Tokenizer>>,
 }
 
@@ -32,24 +89,241 @@ struct EncodedBatch {
     attention_mask: Vec<i64>,
     batch_size: usize,
     seq_len: usize,
+    owners: Vec<usize>,
 }
 
 impl EmbeddingEngine {
-    pub fn new(config: EmbeddingConfig) -> Self {
+    pub fn new(mut config: EmbeddingConfig) -> Self {
         let device_used = resolve_device(config.execution_device);
-        let backend = match build_backend(&config) {
-            Ok(backend) => backend,
-            Err(err) => EmbeddingBackend::Unavailable(err.to_string()),
+        let identity = OnceLock::new();
+        // Remote backends resolve their manifest on first use, because probing the vector
+        // dimension is a network round trip that must not block construction.
+        let backend = match build_backend(&config).and_then(|backend| match backend {
+            EmbeddingBackend::Remote(_) => Ok((backend, None)),
+            _ => {
+                let manifest = backend.resolve_manifest(&config)?;
+                Ok((backend, Some(manifest)))
+            }
+        }) {
+            Ok((backend, Some(manifest))) => {
+                config.vector_dim = manifest.dim;
+                config.max_sequence_length = manifest.max_length_or(config.max_sequence_length);
+                let _ = identity.set(ModelIdentity::resolve(&config, Ok(manifest)));
+                backend
+            }
+            Ok((backend, None)) => backend,
+            Err(err) => {
+                let message = err.to_string();
+                let _ = identity.set(ModelIdentity::resolve(&config, Err(err)));
+                EmbeddingBackend::Unavailable(message)
+            }
         };
         Self {
             config,
             device_used,
             backend,
+            identity,
+            cache: None,
+            corpus_dir: None,
+            corpora: DashMap::new(),
+            warm_up: OnceLock::new(),
+        }
+    }
+
+    pub fn with_cache(mut self, cache: Option<EmbeddingCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn with_corpus_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.corpus_dir = dir;
+        self
+    }
+
+    pub fn needs_corpus(&self, corpus: &str) -> bool {
+        matches!(self.backend, EmbeddingBackend::Hashed)
+            && self.loaded_corpus(corpus).stats.is_none()
+    }
+
+    pub fn fit_corpus(&self, corpus: &str, texts: &[String]) {
+        if !matches!(self.backend, EmbeddingBackend::Hashed) {
+            return;
+        }
+        let stats = CorpusStats::fit(texts.iter().map(String::as_str));
+        if let Some(path) = self.corpus_path(corpus)
+            && let Err(err) = stats.save(&path)
+        {
+            tracing::warn!(corpus, error = %err, "failed persisting corpus statistics");
+        }
+        let modified = self.corpus_path(corpus).and_then(|path| modified_at(&path));
+        self.corpora
+            .insert(corpus.to_string(), LoadedCorpus::new(modified, Some(stats)));
+    }
+
+    // Another process (the watcher, CLI or admin UI) may refit and rewrite the statistics,
+    // so the cached copy is only trusted while the file's mtime is unchanged.
+    fn loaded_corpus(&self, corpus: &str) -> LoadedCorpus {
+        let path = self.corpus_path(corpus);
+        let modified = path.as_deref().and_then(modified_at);
+        if let Some(cached) = self.corpora.get(corpus)
+            && (path.is_none() || cached.modified == modified)
+        {
+            return cached.clone();
+        }
+        let loaded = LoadedCorpus::new(modified, path.as_deref().and_then(CorpusStats::load));
+        self.corpora.insert(corpus.to_string(), loaded.clone());
+        loaded
+    }
+
+    fn corpus_path(&self, corpus: &str) -> Option<PathBuf> {
+        let dir = self.corpus_dir.as_ref()?;
+        let key = EmbeddingCache::key(HASHED_MODEL_ID, corpus);
+        let digest = key.rsplit('/').next().unwrap_or(&key);
+        Some(dir.join(format!("{}.json", &digest[..24])))
+    }
+
+    fn embed_hashed(&self, corpus: &str, inputs: &[String]) -> Vec<Vec<f32>> {
+        let stats = self.loaded_corpus(corpus).stats;
+        inputs
+            .iter()
+            .map(|text| hashed_embed(text, self.config.vector_dim, stats.as_deref()))
+            .collect()
+    }
+
+    fn identity(&self) -> &ModelIdentity {
+        self.identity.get_or_init(|| {
+            ModelIdentity::resolve(&self.config, self.backend.resolve_manifest(&self.config))
+        })
+    }
+
+    pub fn model_id(&self) -> &str {
+        &self.identity().model_id
+    }
+
+    // Hashed vectors depend on the IDF statistics fitted for the corpus, so vectors stored for
+    // a project are tagged with this id rather than the bare model id.
+    pub fn corpus_model_id(&self, corpus: &str) -> String {
+        match self.loaded_corpus(corpus).digest {
+            Some(digest) if matches!(self.backend, EmbeddingBackend::Hashed) => {
+                corpus_model_id(self.model_id(), &digest)
+            }
+            _ => self.model_id().to_string(),
+        }
+    }
+
+    pub fn manifest(&self) -> &ModelManifest {
+        &self.identity().manifest
+    }
+
+    pub fn vector_dim(&self) -> usize {
+        self.identity().manifest.dim
+    }
+
+    pub fn embed_queries(&self, corpus: &str, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        if matches!(self.backend, EmbeddingBackend::Hashed) {
+            return Ok(self.embed_hashed(corpus, inputs));
+        }
+        self.embed_batch(&with_prefix(&self.manifest().query_prefix, inputs))
+    }
+
+    pub fn embed_documents(&self, corpus: &str, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        if matches!(self.backend, EmbeddingBackend::Hashed) {
+            return Ok(self.embed_hashed(corpus, inputs));
+        }
+        let inputs = with_prefix(&self.manifest().passage_prefix, inputs);
+        let Some(cache) = &self.cache else {
+            return self.embed_batch(&inputs);
+        };
+        let keys = inputs
+            .iter()
+            .map(|text| EmbeddingCache::key(self.model_id(), text))
+            .collect::<Vec<_>>();
+        let mut out = keys.iter().map(|key| cache.get(key)).collect::<Vec<_>>();
+        let missing = out
+            .iter()
+            .enumerate()
+            .filter(|(_, vector)| vector.is_none())
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            let texts = missing
+                .iter()
+                .map(|idx| inputs[*idx].clone())
+                .collect::<Vec<_>>();
+            for (idx, vector) in missing.into_iter().zip(self.embed_batch(&texts)?) {
+                cache.put(keys[idx].clone(), vector.clone());
+                out[idx] = Some(vector);
+            }
+        }
+        Ok(out.into_iter().flatten().collect())
+    }
+
+    pub fn flush_cache(&self) {
+        if let Some(cache) = &self.cache
+            && let Err(err) = cache.flush()
+        {
+            tracing::warn!(error = %err, "embedding cache flush failed");
+        }
+    }
+
+    pub fn warm_up(&self) -> &Result<u64, String> {
+        self.warm_up.get_or_init(|| {
+            let started = Instant::now();
+            let texts = [WARM_UP_TEXT.to_string()];
+            let result = match &self.backend {
+                EmbeddingBackend::Onnx(backend) => backend
+                    .sessions
+                    .iter()
+                    .try_for_each(|session| {
+                        let session = session
+                            .lock()
+                            .map_err(|_| anyhow!("embedding session lock poisoned"))?;
+                        backend
+                            .run(session, &texts, &self.config, self.manifest().pooling)
+                            .map(|_| ())
+                    })
+                    .map_err(|err| err.to_string()),
+                EmbeddingBackend::Remote(_) => self
+                    .embed_batch(&texts)
+                    .map(|_| ())
+                    .map_err(|err| err.to_string()),
+                EmbeddingBackend::Hashed | EmbeddingBackend::Pseudo => Ok(()),
+                EmbeddingBackend::Unavailable(msg) => Err(msg.clone()),
+            };
+            result.map(|()| started.elapsed().as_millis() as u64)
+        })
+    }
+
+    pub fn readiness(&self) -> EmbeddingReadiness {
+        let (backend, sessions) = match &self.backend {
+            EmbeddingBackend::Onnx(backend) => ("onnx", backend.sessions.len()),
+            EmbeddingBackend::Remote(backend) => (backend.name(), 0),
+            EmbeddingBackend::Hashed => ("hashed", 0),
+            EmbeddingBackend::Pseudo => ("pseudo", 0),
+            EmbeddingBackend::Unavailable(_) => ("unavailable", 0),
+        };
+        let warm_up = self.warm_up.get();
+        EmbeddingReadiness {
+            ready: matches!(warm_up, Some(Ok(_))),
+            warmed_up: warm_up.is_some(),
+            backend,
+            sessions,
+            warm_up_ms: warm_up.and_then(|r| r.as_ref().ok().copied()),
+            error: match (&self.backend, warm_up) {
+                (EmbeddingBackend::Unavailable(msg), _) => Some(msg.clone()),
+                (_, Some(Err(err))) => Some(err.clone()),
+                _ => self.identity.get().and_then(|i| i.error.clone()),
+            },
+            cache: self.cache.as_ref().map(EmbeddingCache::stats),
         }
     }
 
     pub fn runtime_name(&self) -> &'static str {
-        "ort"
+        match self.backend {
+            EmbeddingBackend::Remote(_) => "http",
+            EmbeddingBackend::Hashed => "hashed",
+            _ => "ort",
+        }
     }
 
     pub fn device_mode(&self) -> &'static str {
@@ -63,26 +337,107 @@ impl EmbeddingEngine {
         if inputs.is_empty() {
             return Ok(Vec::new());
         }
-        match &self.backend {
-            EmbeddingBackend::Onnx(backend) => backend.embed_batch(inputs, &self.config),
-            EmbeddingBackend::Pseudo => Ok(inputs
+        let mut vectors = match &self.backend {
+            EmbeddingBackend::Onnx(backend) => {
+                backend.embed_batch(inputs, &self.config, self.manifest().pooling)?
+            }
+            EmbeddingBackend::Remote(backend) => {
+                if let Some(err) = &self.identity().error {
+                    return Err(anyhow!("embedding unavailable: {err}"));
+                }
+                let vectors = backend.embed_batch(inputs)?;
+                let dim = self.vector_dim();
+                if let Some(vector) = vectors.iter().find(|v| v.len() != dim) {
+                    return Err(anyhow!(
+                        "embedding server returned {}-dim vectors but the model manifest declares {dim}; fix the model manifest and reindex",
+                        vector.len(),
+                    ));
+                }
+                vectors
+            }
+            EmbeddingBackend::Hashed => {
+                return Err(anyhow!(
+                    "hashed embeddings are weighted per corpus; use embed_queries or embed_documents"
+                ));
+            }
+            EmbeddingBackend::Pseudo => inputs
                 .iter()
                 .map(|input| pseudo_embed(input, self.config.vector_dim))
-                .collect::<Vec<_>>()),
-            EmbeddingBackend::Unavailable(msg) => Err(anyhow!(
-                "embedding unavailable: {msg}. set CODEVIX_ALLOW_PSEUDO_EMBED=true only for local test scaffolding"
-            )),
+                .collect::<Vec<_>>(),
+            EmbeddingBackend::Unavailable(msg) => {
+                return Err(anyhow!(
+                    "embedding unavailable: {msg}. set CODEVIX_ALLOW_PSEUDO_EMBED=true only for local test scaffolding"
+                ));
+            }
+        };
+        if self.manifest().normalize {
+            vectors.iter_mut().for_each(|v| l2_normalize(v));
+        }
+        Ok(vectors)
+    }
+}
+
+impl ModelIdentity {
+    fn resolve(config: &EmbeddingConfig, manifest: Result<ModelManifest>) -> Self {
+        match manifest {
+            Ok(manifest) => {
+                let model_id = format!(
+                    "{}:w{}x{}{}",
+                    manifest.fingerprint(manifest.max_length_or(config.max_sequence_length)),
+                    config.window_overlap,
+                    config.max_windows,
+                    match config.window_aggregation {
+                        WindowAggregation::Mean => "mean",
+                        WindowAggregation::Max => "max",
+                    }
+                );
+                Self {
+                    manifest,
+                    model_id,
+                    error: None,
+                }
+            }
+            Err(err) => Self {
+                manifest: ModelManifest::fallback("unavailable".to_string(), config.vector_dim),
+                model_id: "unavailable".to_string(),
+                error: Some(err.to_string()),
+            },
         }
     }
 }
 
 impl OnnxBackend {
-    fn embed_batch(&self, inputs: &[String], cfg: &EmbeddingConfig) -> Result<Vec<Vec<f32>>> {
-        let encoded = encode_inputs(inputs, cfg, self.tokenizer.as_ref())?;
-        let mut session = self
-            .session
+    fn embed_batch(
+        &self,
+        inputs: &[String],
+        cfg: &EmbeddingConfig,
+        pooling: Pooling,
+    ) -> Result<Vec<Vec<f32>>> {
+        let session = self.acquire_session()?;
+        self.run(session, inputs, cfg, pooling)
+    }
+
+    fn acquire_session(&self) -> Result<MutexGuard<'_, Session>> {
+        let start = self.next_session.fetch_add(1, Ordering::Relaxed);
+        let len = self.sessions.len();
+        for offset in 0..len {
+            if let Ok(guard) = self.sessions[(start + offset) % len].try_lock() {
+                return Ok(guard);
+            }
+        }
+        self.sessions[start % len]
             .lock()
-            .map_err(|_| anyhow!("embedding session lock poisoned"))?;
+            .map_err(|_| anyhow!("embedding session lock poisoned"))
+    }
+
+    fn run(
+        &self,
+        mut session: MutexGuard<'_, Session>,
+        inputs: &[String],
+        cfg: &EmbeddingConfig,
+        pooling: Pooling,
+    ) -> Result<Vec<Vec<f32>>> {
+        let encoded = encode_inputs(inputs, cfg, self.tokenizer.as_ref())?;
 
         let ids_tensor = Tensor::<i64>::from_array((
             vec![encoded.batch_size as i64, encoded.seq_len as i64],
@@ -123,14 +478,53 @@ impl OnnxBackend {
             .try_extract_tensor::<f32>()
             .map_err(|err| anyhow!("embedding output decode failed: {err}"))?;
 
-        decode_output_vectors(
+        let windows = decode_output_vectors(
             shape,
             values,
             encoded.batch_size,
             encoded.seq_len,
             &encoded.attention_mask,
             cfg.vector_dim,
-        )
+            pooling,
+        )?;
+        Ok(aggregate_windows(
+            windows,
+            &encoded.owners,
+            inputs.len(),
+            cfg.window_aggregation,
+        ))
+    }
+}
+
+impl EmbeddingBackend {
+    fn resolve_manifest(&self, config: &EmbeddingConfig) -> Result<ModelManifest> {
+        match self {
+            Self::Onnx(backend) => {
+                let session = backend.sessions[0]
+                    .lock()
+                    .map_err(|_| anyhow!("embedding session lock poisoned"))?;
+                ModelManifest::resolve(config, ModelProbe::onnx(&config.model_path, &session))
+            }
+            Self::Remote(backend) => ModelManifest::resolve(
+                config,
+                ModelProbe {
+                    id: format!("{}:{}", backend.name(), backend.model()),
+                    name: Some(backend.model().to_string()),
+                    dim: backend.probe_dim(),
+                },
+            ),
+            Self::Hashed => ModelManifest::resolve(
+                config,
+                ModelProbe {
+                    id: HASHED_MODEL_ID.to_string(),
+                    name: None,
+                    dim: None,
+                },
+            ),
+            Self::Pseudo | Self::Unavailable(_) => {
+                ModelManifest::resolve(config, ModelProbe::pseudo())
+            }
+        }
     }
 }
 
@@ -141,6 +535,7 @@ fn decode_output_vectors(
     seq_len: usize,
     attention_mask: &[i64],
     target_dim: usize,
+    pooling: Pooling,
 ) -> Result<Vec<Vec<f32>>> {
     if shape.len() < 2 {
         return Err(anyhow!(
@@ -157,9 +552,7 @@ fn decode_output_vectors(
 
     if shape.len() == 2 {
         let hidden = usize::try_from(shape[1]).unwrap_or(0);
-        if hidden == 0 {
-            return Err(anyhow!("embedding output hidden dimension is invalid"));
-        }
+        check_output_dim(hidden, target_dim)?;
         if values.len() < batch_size * hidden {
             return Err(anyhow!(
                 "embedding output tensor too small for expected shape {}x{}",
@@ -172,15 +565,13 @@ fn decode_output_vectors(
         for batch in 0..batch_size {
             let start = batch * hidden;
             let end = start + hidden;
-            out.push(fit_vector_dim(&values[start..end], target_dim));
+            out.push(values[start..end].to_vec());
         }
         return Ok(out);
     }
 
     let hidden = usize::try_from(shape[shape.len() - 1]).unwrap_or(0);
-    if hidden == 0 {
-        return Err(anyhow!("embedding output hidden dimension is invalid"));
-    }
+    check_output_dim(hidden, target_dim)?;
     let model_seq_len = usize::try_from(shape[shape.len() - 2]).unwrap_or(seq_len);
     if values.len() < batch_size * model_seq_len * hidden {
         return Err(anyhow!(
@@ -190,48 +581,101 @@ fn decode_output_vectors(
 
     let mut out = Vec::with_capacity(batch_size);
     for batch in 0..batch_size {
-        let mut pooled = vec![0.0f32; hidden];
-        let mut denom = 0.0f32;
-        for token in 0..model_seq_len {
-            let mask_index = batch * seq_len + token.min(seq_len.saturating_sub(1));
-            if attention_mask.get(mask_index).copied().unwrap_or(0) == 0 {
-                continue;
-            }
-            denom += 1.0;
+        let row = |token: usize| {
             let base = (batch * model_seq_len + token) * hidden;
-            for hidden_idx in 0..hidden {
-                pooled[hidden_idx] += values[base + hidden_idx];
-            }
-        }
-        if denom > 0.0 {
-            for value in &mut pooled {
-                *value /= denom;
+            &values[base..base + hidden]
+        };
+        let mut active = (0..model_seq_len).filter(|token| {
+            let mask_index = batch * seq_len + (*token).min(seq_len.saturating_sub(1));
+            attention_mask.get(mask_index).copied().unwrap_or(0) != 0
+        });
+        let pooled = match pooling {
+            Pooling::Cls => row(0).to_vec(),
+            Pooling::LastToken => row(active.next_back().unwrap_or(0)).to_vec(),
+            Pooling::Mean => {
+                let mut pooled = vec![0.0f32; hidden];
+                let mut denom = 0.0f32;
+                for token in active {
+                    denom += 1.0;
+                    for (sum, value) in pooled.iter_mut().zip(row(token)) {
+                        *sum += value;
+                    }
+                }
+                if denom > 0.0 {
+                    for value in &mut pooled {
+                        *value /= denom;
+                    }
+                }
+                pooled
             }
-        }
-        out.push(fit_vector_dim(&pooled, target_dim));
+        };
+        out.push(pooled);
     }
     Ok(out)
 }
 
-fn fit_vector_dim(values: &[f32], target_dim: usize) -> Vec<f32> {
-    if target_dim == 0 {
-        return Vec::new();
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+fn l2_normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > f32::EPSILON {
+        for value in vector {
+            *value /= norm;
+        }
     }
-    if values.len() == target_dim {
-        return values.to_vec();
+}
+
+fn with_prefix<'a>(prefix: &str, inputs: &'a [String]) -> Cow<'a, [String]> {
+    if prefix.is_empty() {
+        return Cow::Borrowed(inputs);
     }
-    if values.len() > target_dim {
-        return values[..target_dim].to_vec();
+    Cow::Owned(
+        inputs
+            .iter()
+            .map(|text| format!("{prefix}{text}"))
+            .collect(),
+    )
+}
+
+fn check_output_dim(hidden: usize, target_dim: usize) -> Result<()> {
+    if hidden == 0 {
+        return Err(anyhow!("embedding output hidden dimension is invalid"));
     }
-    let mut out = vec![0.0f32; target_dim];
-    out[..values.len()].copy_from_slice(values);
-    out
+    if hidden != target_dim {
+        return Err(anyhow!(
+            "embedding model outputs {hidden}-dim vectors but its manifest declares {target_dim}; fix the model manifest and reindex"
+        ));
+    }
+    Ok(())
 }
 
 fn build_backend(config: &EmbeddingConfig) -> Result<EmbeddingBackend> {
+    if let Some(remote) = &config.remote {
+        return Ok(EmbeddingBackend::Remote(RemoteBackend::new(
+            remote.clone(),
+            config.batch_size,
+        )));
+    }
+    if config.hashed_backend == HashedBackendMode::Always {
+        return Ok(EmbeddingBackend::Hashed);
+    }
     if config.allow_pseudo_fallback {
         return Ok(EmbeddingBackend::Pseudo);
     }
+    match build_onnx_backend(config) {
+        Err(err) if config.hashed_backend == HashedBackendMode::Fallback => {
+            tracing::warn!(error = %err, "embedding model unavailable, using hashed lexical embeddings");
+            Ok(EmbeddingBackend::Hashed)
+        }
+        other => other,
+    }
+}
+
+fn build_onnx_backend(config: &EmbeddingConfig) -> Result<EmbeddingBackend> {
     let model_path = Path::new(&config.model_path);
     if !model_path.exists() {
         return Err(anyhow!(
@@ -240,14 +684,24 @@ fn build_backend(config: &EmbeddingConfig) -> Result<EmbeddingBackend> {
         ));
     }
 
-    let session = Session::builder()
-        .context("failed to create ONNX session builder")?
-        .commit_from_file(model_path)
-        .with_context(|| format!("failed to load ONNX model {}", model_path.display()))?;
+    let mut sessions = Vec::with_capacity(config.session_pool_size.max(1));
+    for _ in 0..config.session_pool_size.max(1) {
+        let mut builder = Session::builder().context("failed to create ONNX session builder")?;
+        if let Some(threads) = config.intra_op_threads {
+            builder = builder
+                .with_intra_threads(threads)
+                .context("failed to set ONNX intra-op threads")?;
+        }
+        let session = builder
+            .commit_from_file(model_path)
+            .with_context(|| format!("failed to load ONNX model {}", model_path.display()))?;
+        sessions.push(Mutex::new(session));
+    }
     let tokenizer = load_tokenizer(config)?;
 
     Ok(EmbeddingBackend::Onnx(OnnxBackend {
-        session: Mutex::new(session),
+        sessions,
+        next_session: AtomicUsize::new(0),
         tokenizer,
     }))
 }
@@ -256,8 +710,12 @@ fn load_tokenizer(config: &EmbeddingConfig) -> Result<Option<Arc<Tokenizer>>> {
     let Some(path) = config.tokenizer_path.as_ref() else {
         return Ok(None);
     };
-    let tokenizer = Tokenizer::from_file(path)
+    let mut tokenizer = Tokenizer::from_file(path)
         .map_err(|err| anyhow!("failed loading tokenizer from {}: {err}", path))?;
+    tokenizer
+        .with_truncation(None)
+        .map_err(|err| anyhow!("failed disabling tokenizer truncation: {err}"))?;
+    tokenizer.with_padding(None);
     Ok(Some(Arc::new(tokenizer)))
 }
 
@@ -266,6 +724,8 @@ fn encode_inputs(
     config: &EmbeddingConfig,
     tokenizer: Option<&Arc<Tokenizer>>,
 ) -> Result<EncodedBatch> {
+    let seq_len = config.max_sequence_length.max(1);
+    let mut rows = Vec::with_capacity(inputs.len());
     if let Some(tokenizer) = tokenizer {
         let encoded_inputs = inputs
             .iter()
@@ -274,42 +734,126 @@ fn encode_inputs(
         let encodings = tokenizer
             .encode_batch(encoded_inputs, true)
             .map_err(|err| anyhow!("tokenization failed: {err}"))?;
-
-        let seq_len = config.max_sequence_length.max(1);
-        let mut input_ids = vec![0i64; inputs.len() * seq_len];
-        let mut attention_mask = vec![0i64; inputs.len() * seq_len];
-        for (row, encoding) in encodings.iter().enumerate() {
-            for (col, token_id) in encoding.get_ids().iter().take(seq_len).enumerate() {
-                input_ids[row * seq_len + col] = i64::from(*token_id);
-                attention_mask[row * seq_len + col] = 1;
-            }
+        for (owner, encoding) in encodings.iter().enumerate() {
+            let ids = encoding
+                .get_ids()
+                .iter()
+                .map(|id| i64::from(*id))
+                .collect::<Vec<_>>();
+            let special = encoding.get_special_tokens_mask();
+            let leading = special.iter().take_while(|s| **s == 1).count();
+            let trailing = special[leading..]
+                .iter()
+                .rev()
+                .take_while(|s| **s == 1)
+                .count();
+            rows.extend(
+                token_windows(&ids, leading, trailing, seq_len, config)
+                    .into_iter()
+                    .map(|window| (owner, window)),
+            );
+        }
+    } else {
+        for (owner, text) in inputs.iter().enumerate() {
+            let ids = text
+                .as_bytes()
+                .iter()
+                .map(|byte| i64::from(*byte) + 1)
+                .collect::<Vec<_>>();
+            rows.extend(
+                token_windows(&ids, 0, 0, seq_len, config)
+                    .into_iter()
+                    .map(|window| (owner, window)),
+            );
         }
-
-        return Ok(EncodedBatch {
-            input_ids,
-            attention_mask,
-            batch_size: inputs.len(),
-            seq_len,
-        });
     }
 
-    let seq_len = config.max_sequence_length.max(1);
-    let mut input_ids = vec![0i64; inputs.len() * seq_len];
-    let mut attention_mask = vec![0i64; inputs.len() * seq_len];
-    for (row, text) in inputs.iter().enumerate() {
-        for (col, byte) in text.as_bytes().iter().take(seq_len).enumerate() {
-            input_ids[row * seq_len + col] = i64::from(*byte) + 1;
+    let mut input_ids = vec![0i64; rows.len() * seq_len];
+    let mut attention_mask = vec![0i64; rows.len() * seq_len];
+    let mut owners = Vec::with_capacity(rows.len());
+    for (row, (owner, window)) in rows.iter().enumerate() {
+        for (col, token_id) in window.iter().enumerate() {
+            input_ids[row * seq_len + col] = *token_id;
             attention_mask[row * seq_len + col] = 1;
         }
+        owners.push(*owner);
     }
     Ok(EncodedBatch {
         input_ids,
         attention_mask,
-        batch_size: inputs.len(),
+        batch_size: rows.len(),
         seq_len,
+        owners,
     })
 }
 
+fn token_windows(
+    ids: &[i64],
+    leading: usize,
+    trailing: usize,
+    seq_len: usize,
+    config: &EmbeddingConfig,
+) -> Vec<Vec<i64>> {
+    let span = seq_len.saturating_sub(leading + trailing);
+    if ids.len() <= seq_len || span == 0 || leading + trailing > ids.len() {
+        return vec![ids[..ids.len().min(seq_len)].to_vec()];
+    }
+    let (prefix, rest) = ids.split_at(leading);
+    let (body, suffix) = rest.split_at(rest.len() - trailing);
+    let stride = span - config.window_overlap.min(span - 1);
+
+    let mut windows = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = (start + span).min(body.len());
+        windows.push([prefix, &body[start..end], suffix].concat());
+        if end == body.len() || windows.len() >= config.max_windows.max(1) {
+            break;
+        }
+        start += stride;
+    }
+    windows
+}
+
+fn aggregate_windows(
+    windows: Vec<Vec<f32>>,
+    owners: &[usize],
+    inputs: usize,
+    aggregation: WindowAggregation,
+) -> Vec<Vec<f32>> {
+    if windows.len() == inputs {
+        return windows;
+    }
+    let mut out: Vec<Option<Vec<f32>>> = vec![None; inputs];
+    let mut counts = vec![0usize; inputs];
+    for (vector, owner) in windows.into_iter().zip(owners) {
+        counts[*owner] += 1;
+        match &mut out[*owner] {
+            None => out[*owner] = Some(vector),
+            Some(acc) => {
+                for (a, v) in acc.iter_mut().zip(vector) {
+                    *a = match aggregation {
+                        WindowAggregation::Mean => *a + v,
+                        WindowAggregation::Max => a.max(v),
+                    };
+                }
+            }
+        }
+    }
+    out.into_iter()
+        .zip(counts)
+        .map(|(vector, count)| {
+            let mut vector = vector.unwrap_or_default();
+            if aggregation == WindowAggregation::Mean && count > 1 {
+                for value in &mut vector {
+                    *value /= count as f32;
+                }
+            }
+            vector
+        })
+        .collect()
+}
+
 fn resolve_device(preferred: ExecutionDevice) -> ExecutionDevice {
     match preferred {
         ExecutionDevice::Cpu => ExecutionDevice::Cpu,
@@ -340,7 +884,10 @@ fn pseudo_embed(input: &str, dim: usize) -> Vec<f32> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{EmbeddingConfig, EmbeddingEngine, ExecutionDevice};
+    use crate::{
+        EmbeddingCache, EmbeddingCacheLimits, EmbeddingConfig, EmbeddingEngine, ExecutionDevice,
+        HashedBackendMode, Pooling, RemoteEmbeddingConfig, RemoteProvider, WindowAggregation,
+    };
 
     #[test]
     fn embeds_batch_with_expected_dimensions_in_pseudo_mode() {
@@ -376,5 +923,254 @@ mod tests {
             .embed_batch(&["hello".to_string()])
             .expect_err("missing model should be reported");
         assert!(err.to_string().contains("embedding unavailable"));
+        assert!(engine.warm_up().is_err());
+        let readiness = engine.readiness();
+        assert!(!readiness.ready);
+        assert_eq!(readiness.backend, "unavailable");
+        assert!(readiness.error.is_some());
+    }
+
+    #[test]
+    fn cached_embeddings_skip_inference_for_repeated_texts() {
+        let dir = std::env::temp_dir().join(format!("codivex-engine-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = EmbeddingCache::open(&dir, EmbeddingCacheLimits::default()).expect("cache");
+        let engine = EmbeddingEngine::new(EmbeddingConfig {
+            vector_dim: 4,
+            ..EmbeddingConfig::default()
+        })
+        .with_cache(Some(cache));
+        let texts = vec!["fn a() {}".to_string(), "fn b() {}".to_string()];
+        let first = engine.embed_documents("repo", &texts).expect("first");
+        let second = engine
+            .embed_documents("repo", &[texts[1].clone(), "fn c() {}".to_string()])
+            .expect("second");
+        assert_eq!(second[0], first[1]);
+        let stats = engine.readiness().cache.expect("cache stats");
+        assert_eq!((stats.entries, stats.hits, stats.misses), (3, 1, 3));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn decoding_rejects_outputs_that_do_not_match_manifest_dim() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let mask = [1, 1, 1];
+        let err = super::decode_output_vectors(&[1, 3, 2], &values, 1, 3, &mask, 4, Pooling::Mean)
+            .expect_err("dimension mismatch");
+        assert!(err.to_string().contains("manifest declares 4"));
+
+        let mean = super::decode_output_vectors(&[1, 3, 2], &values, 1, 3, &mask, 2, Pooling::Mean)
+            .expect("mean pooled");
+        assert_eq!(mean, vec![vec![3.0, 4.0]]);
+        let cls = super::decode_output_vectors(&[1, 3, 2], &values, 1, 3, &mask, 2, Pooling::Cls)
+            .expect("cls pooled");
+        assert_eq!(cls, vec![vec![1.0, 2.0]]);
+        let last = super::decode_output_vectors(
+            &[1, 3, 2],
+            &values,
+            1,
+            3,
+            &[1, 1, 0],
+            2,
+            Pooling::LastToken,
+        )
+        .expect("last-token pooled");
+        assert_eq!(last, vec![vec![3.0, 4.0]]);
+    }
+
+    #[test]
+    fn long_inputs_are_split_into_overlapping_windows() {
+        let config = EmbeddingConfig {
+            window_overlap: 2,
+            max_windows: 8,
+            ..EmbeddingConfig::default()
+        };
+        let ids = (0..12).collect::<Vec<i64>>();
+        let windows = super::token_windows(&ids, 1, 1, 6, &config);
+        assert_eq!(
+            windows,
+            vec![
+                vec![0, 1, 2, 3, 4, 11],
+                vec![0, 3, 4, 5, 6, 11],
+                vec![0, 5, 6, 7, 8, 11],
+                vec![0, 7, 8, 9, 10, 11],
+            ]
+        );
+        assert_eq!(super::token_windows(&ids[..5], 1, 1, 6, &config).len(), 1);
+        let capped = super::token_windows(
+            &ids,
+            1,
+            1,
+            6,
+            &EmbeddingConfig {
+                max_windows: 2,
+                ..config.clone()
+            },
+        );
+        assert_eq!(capped.len(), 2);
+
+        let encoded = super::encode_inputs(
+            &["ab".to_string(), "abcdefgh".to_string()],
+            &EmbeddingConfig {
+                max_sequence_length: 4,
+                ..config
+            },
+            None,
+        )
+        .expect("encoded");
+        assert_eq!(encoded.owners, vec![0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn window_vectors_aggregate_per_input() {
+        let windows = vec![vec![1.0, 4.0], vec![3.0, 2.0], vec![5.0, 5.0]];
+        let owners = [0, 0, 1];
+        assert_eq!(
+            super::aggregate_windows(windows.clone(), &owners, 2, WindowAggregation::Mean),
+            vec![vec![2.0, 3.0], vec![5.0, 5.0]]
+        );
+        assert_eq!(
+            super::aggregate_windows(windows, &owners, 2, WindowAggregation::Max),
+            vec![vec![3.0, 4.0], vec![5.0, 5.0]]
+        );
+    }
+
+    #[test]
+    fn queries_and_documents_get_their_own_prefixes() {
+        let engine = EmbeddingEngine::new(EmbeddingConfig {
+            vector_dim: 8,
+            normalize: Some(true),
+            query_prefix: Some("query: ".to_string()),
+            passage_prefix: Some("passage: ".to_string()),
+            ..EmbeddingConfig::default()
+        });
+        let text = vec!["parse config".to_string()];
+        let query = engine.embed_queries("repo", &text).expect("query");
+        let document = engine.embed_documents("repo", &text).expect("document");
+        assert_eq!(
+            query,
+            engine
+                .embed_batch(&["query: parse config".to_string()])
+                .expect("prefixed")
+        );
+        assert_ne!(query, document);
+        let norm = query[0].iter().map(|v| v * v).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn engine_adopts_manifest_dimension_and_model_id() {
+        let dir =
+            std::env::temp_dir().join(format!("codivex-engine-manifest-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("dir");
+        let path = dir.join("model.manifest.json");
+        std::fs::write(&path, r#"{"id":"wide-model","dim":12}"#).expect("manifest");
+        let engine = EmbeddingEngine::new(EmbeddingConfig {
+            manifest_path: Some(path.display().to_string()),
+            ..EmbeddingConfig::default()
+        });
+        assert_eq!(engine.vector_dim(), 12);
+        assert_eq!(engine.manifest().id, "wide-model");
+        assert!(engine.model_id().starts_with("wide-model:12:"));
+        let vectors = engine
+            .embed_batch(&["fn a() {}".to_string()])
+            .expect("embed");
+        assert_eq!(vectors[0].len(), 12);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn remote_backend_is_selected_from_config_and_probes_dimension() {
+        let server = crate::remote::tests::stub_server(vec![
+            (200, r#"{"embeddings":[[3.0,4.0,0.0]]}"#.to_string()),
+            (200, r#"{"embeddings":[[0.0,3.0,4.0]]}"#.to_string()),
+        ]);
+        let engine = EmbeddingEngine::new(EmbeddingConfig {
+            manifest_path: None,
+            normalize: Some(true),
+            remote: Some(RemoteEmbeddingConfig::new(
+                RemoteProvider::Ollama,
+                &server.url,
+                "nomic-embed-text",
+            )),
+            ..EmbeddingConfig::default()
+        });
+        assert!(server.requests.lock().expect("requests").is_empty());
+        assert_eq!(engine.vector_dim(), 3);
+        assert_eq!(engine.runtime_name(), "http");
+        assert_eq!(engine.readiness().backend, "ollama");
+        assert!(engine.model_id().starts_with("ollama:nomic-embed-text:3:"));
+        assert_eq!(engine.manifest().query_prefix, "search_query: ");
+        let vectors = engine
+            .embed_queries("repo", &["find".to_string()])
+            .expect("vectors");
+        assert_eq!(vectors, vec![vec![0.0, 0.6, 0.8]]);
+        let requests = server.requests.lock().expect("requests");
+        assert!(requests[1].1.contains("search_query: find"));
+    }
+
+    #[test]
+    fn missing_model_falls_back_to_hashed_embeddings_with_corpus_weights() {
+        let dir =
+            std::env::temp_dir().join(format!("codivex-engine-corpus-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = EmbeddingConfig {
+            model_path: "/tmp/does-not-exist.onnx".to_string(),
+            manifest_path: None,
+            allow_pseudo_fallback: false,
+            hashed_backend: HashedBackendMode::Fallback,
+            vector_dim: 64,
+            ..EmbeddingConfig::default()
+        };
+        let engine = EmbeddingEngine::new(config.clone()).with_corpus_dir(Some(dir.clone()));
+        assert_eq!(engine.readiness().backend, "hashed");
+        assert!(engine.model_id().starts_with("hashed-v1:64:"));
+        assert!(engine.needs_corpus("repo"));
+        assert_eq!(engine.corpus_model_id("repo"), engine.model_id());
+        assert!(engine.embed_batch(&["fn f() {}".to_string()]).is_err());
+
+        let docs = vec![
+            "fn load_user() {}".to_string(),
+            "fn save_user() {}".to_string(),
+            "fn load_config() {}".to_string(),
+        ];
+        let unweighted = engine.embed_documents("repo", &docs).expect("vectors");
+        engine.fit_corpus("repo", &docs);
+        assert!(!engine.needs_corpus("repo"));
+        let weighted = engine.embed_documents("repo", &docs).expect("vectors");
+        assert_ne!(unweighted, weighted);
+        let fitted_id = engine.corpus_model_id("repo");
+        assert_ne!(fitted_id, engine.model_id());
+        assert_eq!(crate::hashed::base_model_id(&fitted_id), engine.model_id());
+
+        let reopened = EmbeddingEngine::new(config).with_corpus_dir(Some(dir.clone()));
+        assert!(!reopened.needs_corpus("repo"));
+        assert_eq!(
+            reopened.embed_documents("repo", &docs).expect("vectors"),
+            weighted
+        );
+
+        let refit = vec!["fn load_user() {}".to_string(); 3];
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        engine.fit_corpus("repo", &refit);
+        let refit_weighted = engine.embed_documents("repo", &docs).expect("vectors");
+        assert_ne!(refit_weighted, weighted);
+        assert_eq!(
+            reopened.embed_documents("repo", &docs).expect("vectors"),
+            refit_weighted
+        );
+        assert_ne!(reopened.corpus_model_id("repo"), fitted_id);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn readiness_reports_warm_up_completion() {
+        let engine = EmbeddingEngine::new(EmbeddingConfig::default());
+        assert!(!engine.readiness().warmed_up);
+        assert!(engine.warm_up().is_ok());
+        let readiness = engine.readiness();
+        assert!(readiness.ready && readiness.warmed_up);
+        assert_eq!(readiness.backend, "pseudo");
+        assert!(readiness.warm_up_ms.is_some());
     }
 }
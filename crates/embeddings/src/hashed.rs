@@ -0,0 +1,237 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub const HASHED_MODEL_ID: &str = "hashed-v1";
+
+const CORPUS_ID_SEPARATOR: &str = "+idf.";
+
+const TRIGRAM_WEIGHT: f32 = 0.3;
+const BIGRAM_WEIGHT: f32 = 0.7;
+const MIN_TRIGRAM_WORD: usize = 4;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CorpusStats {
+    documents: u32,
+    df: HashMap<u64, u32>,
+}
+
+impl CorpusStats {
+    pub fn fit<'a>(texts: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut out = Self::default();
+        for text in texts {
+            out.documents += 1;
+            for feature in features(text).into_keys() {
+                *out.df.entry(feature).or_default() += 1;
+            }
+        }
+        out
+    }
+
+    pub fn documents(&self) -> u32 {
+        self.documents
+    }
+
+    pub fn digest(&self) -> String {
+        let mut df = self.df.iter().collect::<Vec<_>>();
+        df.sort_unstable();
+        let mut hasher = Sha256::new();
+        hasher.update(self.documents.to_le_bytes());
+        for (feature, count) in df {
+            hasher.update(feature.to_le_bytes());
+            hasher.update(count.to_le_bytes());
+        }
+        format!("{:x}", hasher.finalize())[..12].to_string()
+    }
+
+    pub fn load(path: &Path) -> Option<Self> {
+        std::fs::read(path)
+            .ok()
+            .and_then(|raw| serde_json::from_slice(&raw).ok())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec(self)?)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    fn idf(&self, feature: u64) -> f32 {
+        let df = self.df.get(&feature).copied().unwrap_or(0);
+        ((1.0 + self.documents as f32) / (1.0 + df as f32)).ln() + 1.0
+    }
+}
+
+pub fn corpus_model_id(model_id: &str, stats_digest: &str) -> String {
+    format!("{model_id}{CORPUS_ID_SEPARATOR}{stats_digest}")
+}
+
+pub fn base_model_id(model_id: &str) -> &str {
+    model_id
+        .split_once(CORPUS_ID_SEPARATOR)
+        .map_or(model_id, |(base, _)| base)
+}
+
+pub fn hashed_embed(text: &str, dim: usize, stats: Option<&CorpusStats>) -> Vec<f32> {
+    let mut out = vec![0.0f32; dim.max(1)];
+    for (feature, (tf, weight)) in weighted_features(text) {
+        let idf = stats.map_or(1.0, |stats| stats.idf(feature));
+        let value = (1.0 + (tf as f32).ln()) * idf * weight;
+        let bucket = (feature % out.len() as u64) as usize;
+        if feature & (1 << 63) == 0 {
+            out[bucket] += value;
+        } else {
+            out[bucket] -= value;
+        }
+    }
+    let norm = out.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > f32::EPSILON {
+        for value in &mut out {
+            *value /= norm;
+        }
+    }
+    out
+}
+
+fn features(text: &str) -> HashMap<u64, u32> {
+    weighted_features(text)
+        .into_iter()
+        .map(|(feature, (tf, _))| (feature, tf))
+        .collect()
+}
+
+fn weighted_features(text: &str) -> HashMap<u64, (u32, f32)> {
+    let mut out = HashMap::new();
+    let mut add = |kind: &str, value: &str, weight: f32| {
+        let entry = out.entry(feature_hash(kind, value)).or_insert((0, weight));
+        entry.0 += 1;
+    };
+    for token in text
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|token| !token.is_empty())
+    {
+        let parts = split_identifier(token);
+        if parts.len() > 1 {
+            add("i", &parts.join("_"), 1.0);
+        }
+        for pair in parts.windows(2) {
+            add("b", &format!("{} {}", pair[0], pair[1]), BIGRAM_WEIGHT);
+        }
+        for part in &parts {
+            add("w", part, 1.0);
+            if part.chars().count() >= MIN_TRIGRAM_WORD {
+                let padded = format!("^{part}$").chars().collect::<Vec<_>>();
+                for trigram in padded.windows(3) {
+                    add("c", &trigram.iter().collect::<String>(), TRIGRAM_WEIGHT);
+                }
+            }
+        }
+    }
+    out
+}
+
+pub fn split_identifier(token: &str) -> Vec<String> {
+    let chars = token.chars().collect::<Vec<_>>();
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    for (idx, c) in chars.iter().enumerate() {
+        if *c == '_' {
+            parts.push(std::mem::take(&mut current));
+            continue;
+        }
+        let prev = idx.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(idx + 1);
+        let boundary = match prev {
+            Some(p) if c.is_uppercase() && p.is_lowercase() => true,
+            Some(p) if c.is_uppercase() && p.is_uppercase() => {
+                next.is_some_and(|n| n.is_lowercase())
+            }
+            _ => false,
+        };
+        if boundary {
+            parts.push(std::mem::take(&mut current));
+        }
+        current.extend(c.to_lowercase());
+    }
+    parts.push(current);
+    parts.retain(|part| part.chars().count() >= 2);
+    parts
+}
+
+fn feature_hash(kind: &str, value: &str) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in kind.bytes().chain([b':']).chain(value.bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CorpusStats, base_model_id, corpus_model_id, hashed_embed, split_identifier};
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    #[test]
+    fn splits_camel_snake_and_acronym_identifiers() {
+        assert_eq!(
+            split_identifier("parseHTTPRequest_v2"),
+            vec!["parse", "http", "request", "v2"]
+        );
+        assert_eq!(split_identifier("load_user_id"), vec!["load", "user", "id"]);
+        assert_eq!(split_identifier("x"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn natural_language_queries_land_near_matching_identifiers() {
+        let docs = [
+            "fn parseConfigFile(path: &str) -> Config { read(path) }",
+            "fn render_user_avatar(user: &User) -> Html { html(user) }",
+            "fn open_database_pool(url: &str) -> Pool { connect(url) }",
+        ];
+        let stats = CorpusStats::fit(docs);
+        assert_eq!(stats.documents(), 3);
+        let vectors = docs
+            .iter()
+            .map(|doc| hashed_embed(doc, 256, Some(&stats)))
+            .collect::<Vec<_>>();
+        let query = hashed_embed("parse config", 256, Some(&stats));
+        let scores = vectors
+            .iter()
+            .map(|v| cosine(&query, v))
+            .collect::<Vec<_>>();
+        assert!(scores[0] > scores[1] && scores[0] > scores[2]);
+        assert_eq!(query, hashed_embed("parse config", 256, Some(&stats)));
+        let norm = cosine(&vectors[0], &vectors[0]);
+        assert!((norm - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn corpus_stats_round_trip_through_disk() {
+        let dir = std::env::temp_dir().join(format!("codivex-corpus-{}", std::process::id()));
+        let path = dir.join("stats.json");
+        let stats = CorpusStats::fit(["fn a_b() {}", "fn b_c() {}"]);
+        stats.save(&path).expect("save");
+        let loaded = CorpusStats::load(&path).expect("load");
+        assert_eq!(loaded.digest(), stats.digest());
+        assert_eq!(loaded, stats);
+        assert_ne!(CorpusStats::fit(["fn a_b() {}"]).digest(), stats.digest());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn corpus_model_ids_keep_the_base_model_id() {
+        let id = corpus_model_id("hashed-v1:64:abc", "0123456789ab");
+        assert_eq!(base_model_id(&id), "hashed-v1:64:abc");
+        assert_eq!(base_model_id("onnx:model.onnx:ff"), "onnx:model.onnx:ff");
+    }
+}
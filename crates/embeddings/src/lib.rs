@@ -1,12 +1,26 @@
+pub mod cache;
 pub mod config;
 pub mod engine;
+pub mod hashed;
+pub mod manifest;
 pub mod queue;
+mod remote;
+pub mod rerank;
+pub mod tokens;
 pub mod worker;
 
-pub use config::{EmbeddingConfig, ExecutionDevice, QuantizationMode};
-pub use engine::EmbeddingEngine;
+pub use cache::{EmbeddingCache, EmbeddingCacheLimits, EmbeddingCacheStats};
+pub use config::{
+    EmbeddingConfig, ExecutionDevice, HashedBackendMode, QuantizationMode, RemoteEmbeddingConfig,
+    RemoteProvider, WindowAggregation,
+};
+pub use engine::{EmbeddingEngine, EmbeddingReadiness};
+pub use hashed::CorpusStats;
+pub use manifest::{ModelManifest, ModelProbe, Pooling};
 pub use queue::{EmbeddingJob, EmbeddingQueue};
+pub use rerank::{CrossEncoderReranker, RerankOutcome, RerankerConfig};
+pub use tokens::TokenCounter;
 pub use worker::{
-    EmbeddingWorkerConfig, EmbeddingWorkerMetrics, EmbeddingWorkerMetricsSnapshot,
-    run_embedding_worker, run_embedding_worker_with_metrics,
+    DeadLetter, EmbeddingResult, EmbeddingSink, EmbeddingWorkerConfig, EmbeddingWorkerMetrics,
+    EmbeddingWorkerMetricsSnapshot, run_embedding_worker, run_embedding_worker_with_metrics,
 };
@@ -0,0 +1,332 @@
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use ort::session::Session;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::EmbeddingConfig;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pooling {
+    #[default]
+    Mean,
+    Cls,
+    LastToken,
+}
+
+impl Pooling {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "mean" => Some(Self::Mean),
+            "cls" => Some(Self::Cls),
+            "last" | "last_token" | "last-token" => Some(Self::LastToken),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Mean => "mean",
+            Self::Cls => "cls",
+            Self::LastToken => "last_token",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelProbe {
+    pub id: String,
+    pub name: Option<String>,
+    pub dim: Option<usize>,
+}
+
+impl ModelProbe {
+    pub fn pseudo() -> Self {
+        Self {
+            id: "pseudo".to_string(),
+            name: None,
+            dim: None,
+        }
+    }
+
+    pub fn onnx(model_path: &str, session: &Session) -> Self {
+        let path = Path::new(model_path);
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("model");
+        // Exported models are usually all called `model.onnx`, so the family is recognised
+        // from the directory that holds the file as well as the file name itself.
+        let name = match path
+            .parent()
+            .and_then(Path::file_name)
+            .and_then(|n| n.to_str())
+        {
+            Some(dir) => format!("{dir}/{file_name}"),
+            None => file_name.to_string(),
+        };
+        let digest = file_digest(path).unwrap_or_else(|err| {
+            tracing::warn!(path = model_path, error = %err, "failed hashing embedding model");
+            "unhashed".to_string()
+        });
+        Self {
+            id: format!("onnx:{file_name}:{digest}"),
+            name: Some(name),
+            dim: probe_output_dim(session),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelManifest {
+    pub id: String,
+    pub dim: usize,
+    #[serde(default)]
+    pub pooling: Pooling,
+    #[serde(default)]
+    pub normalize: bool,
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    #[serde(default)]
+    pub query_prefix: String,
+    #[serde(default)]
+    pub passage_prefix: String,
+}
+
+impl ModelManifest {
+    pub fn sidecar_path(model_path: &str) -> std::path::PathBuf {
+        Path::new(model_path).with_extension("manifest.json")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("failed reading model manifest {}", path.display()))?;
+        let manifest = serde_json::from_str::<Self>(&raw)
+            .with_context(|| format!("invalid model manifest {}", path.display()))?;
+        if manifest.id.trim().is_empty() || manifest.dim == 0 {
+            return Err(anyhow!(
+                "model manifest {} needs a non-empty id and a positive dim",
+                path.display()
+            ));
+        }
+        Ok(manifest)
+    }
+
+    pub fn resolve(config: &EmbeddingConfig, probe: ModelProbe) -> Result<Self> {
+        let mut manifest = Self::resolve_base(config, probe)?;
+        if let Some(pooling) = config.pooling {
+            manifest.pooling = pooling;
+        }
+        if let Some(normalize) = config.normalize {
+            manifest.normalize = normalize;
+        }
+        if let Some(prefix) = &config.query_prefix {
+            manifest.query_prefix = prefix.clone();
+        }
+        if let Some(prefix) = &config.passage_prefix {
+            manifest.passage_prefix = prefix.clone();
+        }
+        Ok(manifest)
+    }
+
+    fn resolve_base(config: &EmbeddingConfig, probe: ModelProbe) -> Result<Self> {
+        if let Some(path) = config.manifest_path.as_deref() {
+            let manifest = Self::load(Path::new(path))?;
+            if let Some(dim) = probe.dim.filter(|dim| *dim != manifest.dim) {
+                return Err(anyhow!(
+                    "model manifest {path} declares dim {} but the model outputs {dim}",
+                    manifest.dim
+                ));
+            }
+            return Ok(manifest);
+        }
+
+        let mut manifest = Self::fallback(probe.id, probe.dim.unwrap_or(config.vector_dim));
+        if let Some((pooling, query_prefix, passage_prefix)) =
+            probe.name.as_deref().and_then(known_family)
+        {
+            manifest.pooling = pooling;
+            manifest.normalize = true;
+            manifest.query_prefix = query_prefix.to_string();
+            manifest.passage_prefix = passage_prefix.to_string();
+        }
+        Ok(manifest)
+    }
+
+    pub fn fallback(id: String, dim: usize) -> Self {
+        Self {
+            id,
+            dim,
+            pooling: Pooling::default(),
+            normalize: false,
+            max_length: None,
+            query_prefix: String::new(),
+            passage_prefix: String::new(),
+        }
+    }
+
+    pub fn max_length_or(&self, fallback: usize) -> usize {
+        self.max_length.filter(|len| *len > 0).unwrap_or(fallback)
+    }
+
+    pub fn fingerprint(&self, max_length: usize) -> String {
+        let mut out = format!(
+            "{}:{}:{}:{}:{max_length}",
+            self.id,
+            self.dim,
+            self.pooling.as_str(),
+            if self.normalize { "l2" } else { "raw" },
+        );
+        if !self.query_prefix.is_empty() || !self.passage_prefix.is_empty() {
+            let mut hasher = Sha256::new();
+            hasher.update(self.query_prefix.as_bytes());
+            hasher.update([0]);
+            hasher.update(self.passage_prefix.as_bytes());
+            let digest = format!("{:x}", hasher.finalize());
+            out.push(':');
+            out.push_str(&digest[..8]);
+        }
+        out
+    }
+}
+
+fn file_digest(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    let digest = format!("{:x}", hasher.finalize());
+    Ok(digest[..16].to_string())
+}
+
+fn known_family(model_name: &str) -> Option<(Pooling, &'static str, &'static str)> {
+    let lowered = model_name.to_ascii_lowercase();
+    let tokens = lowered
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .collect::<Vec<_>>();
+    let has = |family: &str| tokens.contains(&family);
+    if has("e5") {
+        Some((Pooling::Mean, "query: ", "passage: "))
+    } else if has("bge") {
+        Some((
+            Pooling::Cls,
+            "Represent this sentence for searching relevant passages: ",
+            "",
+        ))
+    } else if has("nomic") {
+        Some((Pooling::Mean, "search_query: ", "search_document: "))
+    } else if has("jina") || has("gte") {
+        Some((Pooling::Mean, "", ""))
+    } else {
+        None
+    }
+}
+
+fn probe_output_dim(session: &Session) -> Option<usize> {
+    let shape = session.outputs().first()?.dtype().tensor_shape()?;
+    shape
+        .last()
+        .and_then(|dim| usize::try_from(*dim).ok())
+        .filter(|dim| *dim > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ModelManifest, ModelProbe, Pooling};
+    use crate::EmbeddingConfig;
+
+    #[test]
+    fn model_digest_follows_file_content() {
+        let dir = std::env::temp_dir().join(format!("codivex-model-digest-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("dir");
+        let path = dir.join("model.onnx");
+        std::fs::write(&path, b"weights-a").expect("write");
+        let first = super::file_digest(&path).expect("digest");
+        std::fs::write(&path, b"weights-b").expect("write");
+        let second = super::file_digest(&path).expect("digest");
+        assert_eq!(first.len(), 16);
+        assert_ne!(first, second);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn sidecar_manifest_overrides_configured_dim() {
+        let dir = std::env::temp_dir().join(format!("codivex-manifest-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("dir");
+        let path = dir.join("code-model.manifest.json");
+        std::fs::write(
+            &path,
+            r#"{"id":"bge-code","dim":768,"pooling":"cls","normalize":true,"query_prefix":"query: "}"#,
+        )
+        .expect("write manifest");
+        let config = EmbeddingConfig {
+            manifest_path: Some(path.display().to_string()),
+            ..EmbeddingConfig::default()
+        };
+        let manifest = ModelManifest::resolve(&config, ModelProbe::pseudo()).expect("manifest");
+        assert_eq!((manifest.dim, manifest.pooling), (768, Pooling::Cls));
+        assert_eq!(manifest.max_length_or(256), 256);
+        assert!(
+            manifest
+                .fingerprint(256)
+                .starts_with("bge-code:768:cls:l2:256:")
+        );
+
+        std::fs::write(&path, r#"{"id":"","dim":0}"#).expect("write manifest");
+        assert!(ModelManifest::resolve(&config, ModelProbe::pseudo()).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn config_overrides_apply_on_top_of_manifest() {
+        let manifest = ModelManifest::resolve(
+            &EmbeddingConfig {
+                manifest_path: None,
+                pooling: Some(Pooling::LastToken),
+                normalize: Some(true),
+                query_prefix: Some("query: ".to_string()),
+                ..EmbeddingConfig::default()
+            },
+            ModelProbe::pseudo(),
+        )
+        .expect("manifest");
+        assert_eq!(manifest.pooling, Pooling::LastToken);
+        assert!(manifest.normalize);
+        assert_eq!(manifest.query_prefix, "query: ");
+        assert_eq!(Pooling::parse("last-token"), Some(Pooling::LastToken));
+        assert_eq!(Pooling::parse("max"), None);
+        assert_eq!(
+            super::known_family("bge-small-en-v1.5.onnx").map(|f| f.0),
+            Some(Pooling::Cls)
+        );
+        assert_eq!(
+            super::known_family("multilingual-e5-small.onnx").map(|f| f.2),
+            Some("passage: ")
+        );
+        assert_eq!(super::known_family("all-minilm-l6-v2.onnx"), None);
+        assert_eq!(
+            super::known_family("nomic-embed-text-v1.5/model.onnx").map(|f| f.1),
+            Some("search_query: ")
+        );
+        assert_eq!(super::known_family("models/model.onnx"), None);
+        assert_eq!(super::known_family("codee5net/model.onnx"), None);
+    }
+
+    #[test]
+    fn fingerprint_changes_with_dimension() {
+        let config = EmbeddingConfig {
+            manifest_path: None,
+            vector_dim: 384,
+            ..EmbeddingConfig::default()
+        };
+        let small = ModelManifest::resolve(&config, ModelProbe::pseudo()).expect("manifest");
+        let large = ModelManifest::resolve(
+            &EmbeddingConfig {
+                vector_dim: 768,
+                ..config
+            },
+            ModelProbe::pseudo(),
+        )
+        .expect("manifest");
+        assert_eq!(small.fingerprint(256), "pseudo:384:mean:raw:256");
+        assert_ne!(small.fingerprint(256), large.fingerprint(256));
+    }
+}
@@ -4,9 +4,11 @@ use tokio::sync::mpsc;
 #[derive(Debug, Clone)]
 pub struct EmbeddingJob {
     pub chunk_id: String,
+    pub corpus: String,
     pub text: String,
 }
 
+#[derive(Clone)]
 pub struct EmbeddingQueue {
     tx: mpsc::Sender<EmbeddingJob>,
 }
@@ -33,6 +35,7 @@ mod tests {
         queue
             .enqueue(EmbeddingJob {
                 chunk_id: "1".to_string(),
+                corpus: "repo".to_string(),
                 text: "hello".to_string(),
             })
             .await
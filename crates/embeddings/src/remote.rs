@@ -0,0 +1,350 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::config::{RemoteEmbeddingConfig, RemoteProvider};
+
+const BASE_BACKOFF_MS: u64 = 200;
+const MAX_BACKOFF_MS: u64 = 5_000;
+const PROBE_TEXT: &str = "dimension probe";
+
+pub(crate) struct RemoteBackend {
+    config: RemoteEmbeddingConfig,
+    endpoint: String,
+    client: Result<Client, String>,
+    batch_size: usize,
+}
+
+enum RequestError {
+    Retryable(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+#[derive(Deserialize)]
+struct OpenAiResponse {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbedding {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: usize,
+}
+
+#[derive(Deserialize)]
+struct OllamaResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+impl RemoteBackend {
+    pub(crate) fn new(config: RemoteEmbeddingConfig, batch_size: usize) -> Self {
+        let timeout = Duration::from_millis(config.timeout_ms.max(1));
+        let client = off_runtime(|| Client::builder().timeout(timeout).build())
+            .map_err(|err| format!("failed building embedding http client: {err}"));
+        Self {
+            endpoint: endpoint(&config),
+            config,
+            client,
+            batch_size: batch_size.max(1),
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self.config.provider {
+            RemoteProvider::OpenAi => "openai",
+            RemoteProvider::Ollama => "ollama",
+        }
+    }
+
+    pub(crate) fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    pub(crate) fn probe_dim(&self) -> Option<usize> {
+        match self.embed_batch(&[PROBE_TEXT.to_string()]) {
+            Ok(vectors) => vectors.first().map(Vec::len).filter(|dim| *dim > 0),
+            Err(err) => {
+                tracing::warn!(
+                    endpoint = self.endpoint,
+                    error = %err,
+                    "embedding server unreachable, using configured vector dimension"
+                );
+                None
+            }
+        }
+    }
+
+    pub(crate) fn embed_batch(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut out = Vec::with_capacity(inputs.len());
+        for batch in inputs.chunks(self.batch_size) {
+            out.extend(self.request_with_retries(batch)?);
+        }
+        Ok(out)
+    }
+
+    fn request_with_retries(&self, batch: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut attempt = 0;
+        loop {
+            match self.request(batch) {
+                Ok(vectors) => return Ok(vectors),
+                Err(RequestError::Retryable(err)) if attempt < self.config.max_retries => {
+                    tracing::debug!(
+                        endpoint = self.endpoint,
+                        attempt,
+                        error = %err,
+                        "retrying embedding request"
+                    );
+                    std::thread::sleep(backoff(attempt));
+                    attempt += 1;
+                }
+                Err(RequestError::Retryable(err) | RequestError::Fatal(err)) => return Err(err),
+            }
+        }
+    }
+
+    fn request(&self, batch: &[String]) -> Result<Vec<Vec<f32>>, RequestError> {
+        off_runtime(|| self.send(batch))
+    }
+
+    fn send(&self, batch: &[String]) -> Result<Vec<Vec<f32>>, RequestError> {
+        let client = self
+            .client
+            .as_ref()
+            .map_err(|err| RequestError::Fatal(anyhow!("{err}")))?;
+        let mut request = client.post(&self.endpoint);
+        if let Some(key) = &self.config.api_key {
+            let value = if self
+                .config
+                .auth_header
+                .eq_ignore_ascii_case("authorization")
+            {
+                format!("Bearer {key}")
+            } else {
+                key.clone()
+            };
+            request = request.header(self.config.auth_header.as_str(), value);
+        }
+        let response = request
+            .json(&json!({ "model": self.config.model, "input": batch }))
+            .send()
+            .map_err(|err| RequestError::Retryable(anyhow!("embedding request failed: {err}")))?;
+
+        let status = response.status().as_u16();
+        if status != 200 {
+            let body = response.text().unwrap_or_default();
+            let err = anyhow!(
+                "embedding server {} returned {status}: {}",
+                self.endpoint,
+                body.chars().take(200).collect::<String>()
+            );
+            return Err(if status == 429 || status >= 500 {
+                RequestError::Retryable(err)
+            } else {
+                RequestError::Fatal(err)
+            });
+        }
+
+        let vectors = match self.config.provider {
+            RemoteProvider::OpenAi => response.json::<OpenAiResponse>().map(|mut parsed| {
+                parsed.data.sort_by_key(|item| item.index);
+                parsed
+                    .data
+                    .into_iter()
+                    .map(|item| item.embedding)
+                    .collect::<Vec<_>>()
+            }),
+            RemoteProvider::Ollama => response
+                .json::<OllamaResponse>()
+                .map(|parsed| parsed.embeddings),
+        }
+        .map_err(|err| RequestError::Fatal(anyhow!("invalid embedding response: {err}")))?;
+        if vectors.len() != batch.len() {
+            return Err(RequestError::Fatal(anyhow!(
+                "embedding server returned {} vectors for {} inputs",
+                vectors.len(),
+                batch.len()
+            )));
+        }
+        Ok(vectors)
+    }
+}
+
+fn endpoint(config: &RemoteEmbeddingConfig) -> String {
+    let url = config.url.trim_end_matches('/');
+    match config.provider {
+        RemoteProvider::OpenAi if url.ends_with("/embeddings") => url.to_string(),
+        RemoteProvider::OpenAi if url.ends_with("/v1") => format!("{url}/embeddings"),
+        RemoteProvider::OpenAi => format!("{url}/v1/embeddings"),
+        RemoteProvider::Ollama if url.ends_with("/api/embed") => url.to_string(),
+        RemoteProvider::Ollama => format!("{url}/api/embed"),
+    }
+}
+
+// reqwest's blocking client must not wait on an async runtime thread, and the engine is
+// called from plain threads and async handlers alike, so calls made inside a runtime run on
+// a scoped helper thread instead.
+fn off_runtime<T: Send>(call: impl FnOnce() -> T + Send) -> T {
+    if tokio::runtime::Handle::try_current().is_err() {
+        return call();
+    }
+    std::thread::scope(|scope| {
+        scope
+            .spawn(call)
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+fn backoff(attempt: usize) -> Duration {
+    let factor = 1u64 << attempt.min(16);
+    Duration::from_millis((BASE_BACKOFF_MS * factor).min(MAX_BACKOFF_MS))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    use super::{RemoteBackend, endpoint};
+    use crate::config::{RemoteEmbeddingConfig, RemoteProvider};
+
+    pub(crate) struct StubServer {
+        pub(crate) url: String,
+        pub(crate) requests: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    pub(crate) fn stub_server(responses: Vec<(u16, String)>) -> StubServer {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind stub");
+        let url = format!("http://{}", listener.local_addr().expect("addr"));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let Ok((stream, _)) = listener.accept() else {
+                    return;
+                };
+                let mut reader = BufReader::new(stream);
+                let mut headers = String::new();
+                let mut content_length = 0usize;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = len.trim().parse().unwrap_or(0);
+                    }
+                    headers.push_str(&line);
+                }
+                let mut request_body = vec![0u8; content_length];
+                let _ = reader.read_exact(&mut request_body);
+                seen.lock()
+                    .expect("requests")
+                    .push((headers, String::from_utf8_lossy(&request_body).to_string()));
+                let mut stream = reader.into_inner();
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+            }
+        });
+        StubServer { url, requests }
+    }
+
+    #[test]
+    fn openai_backend_retries_server_errors_and_sends_auth_header() {
+        let server = stub_server(vec![
+            (503, "{\"error\":\"busy\"}".to_string()),
+            (
+                200,
+                r#"{"data":[{"index":1,"embedding":[0.0,1.0]},{"index":0,"embedding":[1.0,0.0]}]}"#
+                    .to_string(),
+            ),
+        ]);
+        let mut config = RemoteEmbeddingConfig::new(RemoteProvider::OpenAi, &server.url, "m");
+        config.api_key = Some("secret".to_string());
+        let backend = RemoteBackend::new(config, 16);
+        let vectors = backend
+            .embed_batch(&["a".to_string(), "b".to_string()])
+            .expect("vectors");
+        assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+
+        let requests = server.requests.lock().expect("requests");
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].0.starts_with("POST /v1/embeddings"));
+        assert!(
+            requests[1]
+                .0
+                .to_ascii_lowercase()
+                .contains("authorization: bearer secret")
+        );
+        assert!(requests[1].1.contains("\"input\":[\"a\",\"b\"]"));
+    }
+
+    #[test]
+    fn ollama_backend_batches_and_fails_fast_on_client_errors() {
+        let server = stub_server(vec![
+            (200, r#"{"embeddings":[[1.0],[2.0]]}"#.to_string()),
+            (200, r#"{"embeddings":[[3.0]]}"#.to_string()),
+            (400, r#"{"error":"bad model"}"#.to_string()),
+        ]);
+        let backend = RemoteBackend::new(
+            RemoteEmbeddingConfig::new(RemoteProvider::Ollama, &server.url, "nomic"),
+            2,
+        );
+        let texts = ["a", "b", "c"].map(str::to_string);
+        assert_eq!(
+            backend.embed_batch(&texts).expect("vectors"),
+            vec![vec![1.0], vec![2.0], vec![3.0]]
+        );
+        let err = backend
+            .embed_batch(&texts[..1])
+            .expect_err("client error is not retried");
+        assert!(err.to_string().contains("400"));
+        let requests = server.requests.lock().expect("requests");
+        assert_eq!(requests.len(), 3);
+        assert!(requests[0].0.starts_with("POST /api/embed"));
+    }
+
+    #[tokio::test]
+    async fn backend_can_be_built_and_called_on_a_runtime_thread() {
+        let server = stub_server(vec![(200, r#"{"embeddings":[[0.5]]}"#.to_string())]);
+        let backend = RemoteBackend::new(
+            RemoteEmbeddingConfig::new(RemoteProvider::Ollama, &server.url, "nomic"),
+            4,
+        );
+        assert_eq!(
+            backend.embed_batch(&["a".to_string()]).expect("vectors"),
+            vec![vec![0.5]]
+        );
+    }
+
+    #[test]
+    fn endpoints_accept_base_or_full_urls() {
+        let openai = |url: &str| {
+            endpoint(&RemoteEmbeddingConfig::new(
+                RemoteProvider::OpenAi,
+                url,
+                "m",
+            ))
+        };
+        assert_eq!(openai("http://h/v1"), "http://h/v1/embeddings");
+        assert_eq!(openai("http://h/v1/embeddings/"), "http://h/v1/embeddings");
+        assert_eq!(
+            endpoint(&RemoteEmbeddingConfig::new(
+                RemoteProvider::Ollama,
+                "http://h:11434",
+                "m"
+            )),
+            "http://h:11434/api/embed"
+        );
+    }
+}
@@ -0,0 +1,488 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, anyhow};
+use ort::{session::Session, value::Tensor};
+use tokenizers::{EncodeInput, Encoding, Tokenizer, TruncationParams, TruncationStrategy};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RerankerConfig {
+    pub model_path: String,
+    pub tokenizer_path: Option<String>,
+    pub max_sequence_length: usize,
+    pub batch_size: usize,
+    pub top_n: usize,
+    pub budget_ms: u64,
+    pub allow_test_backend: bool,
+}
+
+impl Default for RerankerConfig {
+    fn default() -> Self {
+        let model_path = std::env::var("CODEVIX_RERANK_MODEL_PATH")
+            .unwrap_or_else(|_| "models/ms-marco-minilm-l6-v2.onnx".to_string());
+        let tokenizer_path = std::env::var("CODEVIX_RERANK_TOKENIZER_PATH")
+            .ok()
+            .or_else(|| {
+                let candidate = Path::new(&model_path).with_extension("tokenizer.json");
+                if candidate.exists() {
+                    Some(candidate.display().to_string())
+                } else {
+                    None
+                }
+            });
+        Self {
+            model_path,
+            tokenizer_path,
+            max_sequence_length: 512,
+            batch_size: 8,
+            top_n: env_usize("MCP_RERANK_TOP_N", 20).max(1),
+            budget_ms: env_usize("MCP_RERANK_BUDGET_MS", 250) as u64,
+            allow_test_backend: std::env::var("CODEVIX_ALLOW_TEST_RERANKER")
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(cfg!(test)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RerankOutcome {
+    pub order: Vec<usize>,
+    pub scored: usize,
+    pub budget_exhausted: bool,
+}
+
+pub struct CrossEncoderReranker {
+    config: RerankerConfig,
+    backend: RerankBackend,
+}
+
+enum RerankBackend {
+    Onnx(OnnxCrossEncoder),
+    TermOverlap,
+    Unavailable(String),
+}
+
+struct OnnxCrossEncoder {
+    session: Mutex<Session>,
+    tokenizer: Arc<Tokenizer>,
+}
+
+impl CrossEncoderReranker {
+    pub fn new(config: RerankerConfig) -> Self {
+        let backend = match build_backend(&config) {
+            Ok(backend) => backend,
+            Err(err) => RerankBackend::Unavailable(err.to_string()),
+        };
+        Self { config, backend }
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        match self.backend {
+            RerankBackend::Onnx(_) => "onnx",
+            RerankBackend::TermOverlap => "term_overlap",
+            RerankBackend::Unavailable(_) => "unavailable",
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        !matches!(self.backend, RerankBackend::Unavailable(_))
+    }
+
+    pub fn score_pairs(&self, query: &str, documents: &[String]) -> Result<Vec<f32>> {
+        if documents.is_empty() {
+            return Ok(Vec::new());
+        }
+        match &self.backend {
+            RerankBackend::Onnx(backend) => backend.score_pairs(query, documents, &self.config),
+            RerankBackend::TermOverlap => Ok(documents
+                .iter()
+                .map(|doc| term_overlap_score(query, doc))
+                .collect()),
+            RerankBackend::Unavailable(msg) => Err(anyhow!(
+                "reranker unavailable: {msg}. set CODEVIX_RERANK_MODEL_PATH to a cross-encoder ONNX model"
+            )),
+        }
+    }
+
+    pub fn rerank(&self, query: &str, documents: &[String]) -> Result<RerankOutcome> {
+        self.rerank_within(query, documents, Duration::MAX)
+    }
+
+    pub fn rerank_within(
+        &self,
+        query: &str,
+        documents: &[String],
+        max_budget: Duration,
+    ) -> Result<RerankOutcome> {
+        let candidate_count = documents.len().min(self.config.top_n.max(1));
+        let budget = Duration::from_millis(self.config.budget_ms).min(max_budget);
+        let started = Instant::now();
+        let mut scores = Vec::with_capacity(candidate_count);
+        let mut budget_exhausted = false;
+        for batch in documents[..candidate_count].chunks(self.config.batch_size.max(1)) {
+            if !scores.is_empty() && started.elapsed() >= budget {
+                budget_exhausted = true;
+                break;
+            }
+            scores.extend(self.score_pairs(query, batch)?);
+        }
+
+        let scored = scores.len();
+        let mut order = (0..scored).collect::<Vec<_>>();
+        order.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]));
+        order.extend(scored..documents.len());
+        Ok(RerankOutcome {
+            order,
+            scored,
+            budget_exhausted,
+        })
+    }
+}
+
+impl OnnxCrossEncoder {
+    fn score_pairs(
+        &self,
+        query: &str,
+        documents: &[String],
+        cfg: &RerankerConfig,
+    ) -> Result<Vec<f32>> {
+        let encodings = encode_pairs(&self.tokenizer, query, documents)?;
+
+        let batch_size = documents.len();
+        let seq_len = encodings
+            .iter()
+            .map(|e| e.get_ids().len())
+            .max()
+            .unwrap_or(1)
+            .clamp(1, cfg.max_sequence_length.max(1));
+        let mut input_ids = vec![0i64; batch_size * seq_len];
+        let mut attention_mask = vec![0i64; batch_size * seq_len];
+        let mut token_type_ids = vec![0i64; batch_size * seq_len];
+        for (row, encoding) in encodings.iter().enumerate() {
+            let ids = encoding.get_ids().iter().zip(encoding.get_type_ids());
+            for (col, (token_id, type_id)) in ids.take(seq_len).enumerate() {
+                input_ids[row * seq_len + col] = i64::from(*token_id);
+                attention_mask[row * seq_len + col] = 1;
+                token_type_ids[row * seq_len + col] = i64::from(*type_id);
+            }
+        }
+
+        let shape = vec![batch_size as i64, seq_len as i64];
+        let ids_tensor = Tensor::<i64>::from_array((shape.clone(), input_ids))?;
+        let mask_tensor = Tensor::<i64>::from_array((shape.clone(), attention_mask))?;
+        let token_type_tensor = Tensor::<i64>::from_array((shape, token_type_ids))?;
+
+        let mut session = self
+            .session
+            .lock()
+            .map_err(|_| anyhow!("rerank session lock poisoned"))?;
+        let mut model_inputs = HashMap::new();
+        for input in session.inputs() {
+            let name = input.name().to_lowercase();
+            let value = if name.contains("attention") && name.contains("mask") {
+                mask_tensor.clone().upcast()
+            } else if name.contains("token_type") {
+                token_type_tensor.clone().upcast()
+            } else {
+                ids_tensor.clone().upcast()
+            };
+            model_inputs.insert(input.name().to_string(), value);
+        }
+
+        let mut outputs = session.run(model_inputs)?;
+        let first_key = outputs
+            .keys()
+            .next()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("rerank model returned no outputs"))?;
+        let output = outputs
+            .remove(first_key)
+            .ok_or_else(|| anyhow!("rerank model output extraction failed"))?;
+        let (shape, values) = output
+            .try_extract_tensor::<f32>()
+            .map_err(|err| anyhow!("rerank output decode failed: {err}"))?;
+        decode_logits(shape, values, batch_size)
+    }
+}
+
+fn encode_pairs(tokenizer: &Tokenizer, query: &str, documents: &[String]) -> Result<Vec<Encoding>> {
+    let pairs = || {
+        documents
+            .iter()
+            .map(|doc| EncodeInput::Dual(query.into(), doc.as_str().into()))
+            .collect::<Vec<_>>()
+    };
+    match tokenizer.encode_batch(pairs(), true) {
+        Ok(encodings) => Ok(encodings),
+        // A query too long to fit next to any passage cannot be truncated from
+        // the passage side alone; trim both sides instead.
+        Err(_) => {
+            let mut fallback = tokenizer.clone();
+            set_pair_truncation(
+                &mut fallback,
+                tokenizer_max_length(tokenizer),
+                TruncationStrategy::LongestFirst,
+            )?;
+            fallback
+                .encode_batch(pairs(), true)
+                .map_err(|err| anyhow!("rerank tokenization failed: {err}"))
+        }
+    }
+}
+
+fn tokenizer_max_length(tokenizer: &Tokenizer) -> usize {
+    tokenizer
+        .get_truncation()
+        .map(|params| params.max_length)
+        .unwrap_or(512)
+}
+
+fn set_pair_truncation(
+    tokenizer: &mut Tokenizer,
+    max_length: usize,
+    strategy: TruncationStrategy,
+) -> Result<()> {
+    tokenizer
+        .with_truncation(Some(TruncationParams {
+            max_length: max_length.max(1),
+            strategy,
+            ..TruncationParams::default()
+        }))
+        .map_err(|err| anyhow!("failed configuring rerank truncation: {err}"))?;
+    Ok(())
+}
+
+fn decode_logits(shape: &[i64], values: &[f32], batch_size: usize) -> Result<Vec<f32>> {
+    let labels = match shape.len() {
+        1 => 1,
+        2 => usize::try_from(shape[1]).unwrap_or(0),
+        rank => return Err(anyhow!("rerank output rank {rank} is unsupported")),
+    };
+    if labels == 0 || values.len() < batch_size * labels {
+        return Err(anyhow!(
+            "rerank output tensor too small for expected shape {}x{}",
+            batch_size,
+            labels
+        ));
+    }
+    Ok((0..batch_size)
+        .map(|row| {
+            let logits = &values[row * labels..(row + 1) * labels];
+            if labels == 1 {
+                logits[0]
+            } else {
+                logits[labels - 1] - logits[0]
+            }
+        })
+        .collect())
+}
+
+fn build_backend(config: &RerankerConfig) -> Result<RerankBackend> {
+    let model_path = Path::new(&config.model_path);
+    if !model_path.exists() {
+        if config.allow_test_backend {
+            return Ok(RerankBackend::TermOverlap);
+        }
+        return Err(anyhow!(
+            "rerank model not found at {}",
+            model_path.display()
+        ));
+    }
+    let tokenizer_path = config
+        .tokenizer_path
+        .as_ref()
+        .ok_or_else(|| anyhow!("rerank tokenizer path is required for cross-encoder models"))?;
+    let mut tokenizer = Tokenizer::from_file(tokenizer_path)
+        .map_err(|err| anyhow!("failed loading rerank tokenizer from {tokenizer_path}: {err}"))?;
+    set_pair_truncation(
+        &mut tokenizer,
+        config.max_sequence_length,
+        TruncationStrategy::OnlySecond,
+    )?;
+    tokenizer.with_padding(None);
+    let session = Session::builder()
+        .context("failed to create rerank session builder")?
+        .commit_from_file(model_path)
+        .with_context(|| format!("failed to load rerank model {}", model_path.display()))?;
+    Ok(RerankBackend::Onnx(OnnxCrossEncoder {
+        session: Mutex::new(session),
+        tokenizer: Arc::new(tokenizer),
+    }))
+}
+
+fn term_overlap_score(query: &str, document: &str) -> f32 {
+    let query_terms = split_terms(query);
+    if query_terms.is_empty() {
+        return 0.0;
+    }
+    let doc_terms = split_terms(document);
+    let matched = query_terms
+        .iter()
+        .filter(|term| doc_terms.contains(term))
+        .count();
+    let phrase_bonus = if document
+        .to_lowercase()
+        .contains(&query.trim().to_lowercase())
+    {
+        0.5
+    } else {
+        0.0
+    };
+    matched as f32 / query_terms.len() as f32 + phrase_bonus
+}
+
+fn split_terms(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let mut current = String::new();
+        let mut prev_lower = false;
+        for ch in word.chars() {
+            if ch.is_uppercase() && prev_lower && !current.is_empty() {
+                out.push(std::mem::take(&mut current));
+            }
+            prev_lower = ch.is_lowercase() || ch.is_ascii_digit();
+            current.extend(ch.to_lowercase());
+        }
+        if !current.is_empty() {
+            out.push(current);
+        }
+    }
+    out.sort();
+    out.dedup();
+    out
+}
+
+fn env_usize(key: &str, default: usize) -> usize {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tokenizers::{
+        Tokenizer, TruncationStrategy, models::wordlevel::WordLevel,
+        pre_tokenizers::whitespace::Whitespace, processors::bert::BertProcessing,
+    };
+
+    use super::{
+        CrossEncoderReranker, RerankerConfig, decode_logits, encode_pairs, set_pair_truncation,
+    };
+
+    fn pair_tokenizer(max_length: usize) -> Tokenizer {
+        let vocab = [
+            "[UNK]", "[CLS]", "[SEP]", "parse", "date", "fn", "iso", "input",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(id, token)| (token.to_string(), id as u32))
+        .collect::<HashMap<_, _>>();
+        let model = WordLevel::builder()
+            .vocab(vocab.into_iter().collect())
+            .unk_token("[UNK]".to_string())
+            .build()
+            .expect("word level model");
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(Some(Whitespace {}));
+        tokenizer.with_post_processor(Some(BertProcessing::new(
+            ("[SEP]".to_string(), 2),
+            ("[CLS]".to_string(), 1),
+        )));
+        set_pair_truncation(&mut tokenizer, max_length, TruncationStrategy::OnlySecond)
+            .expect("truncation");
+        tokenizer
+    }
+
+    fn test_reranker(top_n: usize) -> CrossEncoderReranker {
+        CrossEncoderReranker::new(RerankerConfig {
+            model_path: "/tmp/does-not-exist-rerank.onnx".to_string(),
+            top_n,
+            allow_test_backend: true,
+            ..RerankerConfig::default()
+        })
+    }
+
+    #[test]
+    fn term_overlap_backend_promotes_joint_match() {
+        let reranker = test_reranker(10);
+        assert_eq!(reranker.backend_name(), "term_overlap");
+        let docs = vec![
+            "fn parse_config(raw: &str) -> Config".to_string(),
+            "fn isoToDate(input: &str) -> Date".to_string(),
+        ];
+        let outcome = reranker.rerank("iso to date", &docs).expect("rerank");
+        assert_eq!(outcome.order, vec![1, 0]);
+        assert_eq!(outcome.scored, 2);
+        assert!(!outcome.budget_exhausted);
+    }
+
+    #[test]
+    fn rerank_keeps_tail_beyond_top_n_in_place() {
+        let reranker = test_reranker(2);
+        let docs = vec![
+            "alpha".to_string(),
+            "iso date".to_string(),
+            "iso date exact".to_string(),
+        ];
+        let outcome = reranker.rerank("iso date", &docs).expect("rerank");
+        assert_eq!(outcome.order, vec![1, 0, 2]);
+        assert_eq!(outcome.scored, 2);
+    }
+
+    #[test]
+    fn reports_unavailable_without_model_or_test_backend() {
+        let reranker = CrossEncoderReranker::new(RerankerConfig {
+            model_path: "/tmp/does-not-exist-rerank.onnx".to_string(),
+            allow_test_backend: false,
+            ..RerankerConfig::default()
+        });
+        assert!(!reranker.is_available());
+        let err = reranker
+            .score_pairs("q", &["doc".to_string()])
+            .expect_err("unavailable reranker should error");
+        assert!(err.to_string().contains("reranker unavailable"));
+    }
+
+    #[test]
+    fn long_passages_are_truncated_before_the_final_separator() {
+        let tokenizer = pair_tokenizer(8);
+        let passage = "fn parse date input ".repeat(10);
+        let encodings = encode_pairs(&tokenizer, "iso date", &[passage]).expect("encode");
+        let ids = encodings[0].get_ids();
+        assert_eq!(ids.len(), 8);
+        assert_eq!(&ids[..5], &[1, 6, 4, 2, 5]);
+        assert_eq!(ids.last(), Some(&2));
+    }
+
+    #[test]
+    fn overlong_queries_fall_back_to_trimming_both_sides() {
+        let tokenizer = pair_tokenizer(6);
+        let query = "iso date ".repeat(10);
+        let encodings =
+            encode_pairs(&tokenizer, &query, &["fn parse".to_string()]).expect("encode");
+        let ids = encodings[0].get_ids();
+        assert_eq!(ids.len(), 6);
+        assert_eq!(ids.first(), Some(&1));
+        assert_eq!(ids.last(), Some(&2));
+        assert_eq!(ids.iter().filter(|id| **id == 2).count(), 2);
+    }
+
+    #[test]
+    fn decodes_single_and_binary_logits() {
+        assert_eq!(
+            decode_logits(&[2, 1], &[0.5, -1.0], 2).expect("single"),
+            vec![0.5, -1.0]
+        );
+        assert_eq!(
+            decode_logits(&[1, 2], &[1.0, 3.0], 1).expect("binary"),
+            vec![2.0]
+        );
+    }
+}
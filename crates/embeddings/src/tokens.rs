@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use tokenizers::Tokenizer;
+
+use crate::config::EmbeddingConfig;
+
+pub enum TokenCounter {
+    Tokenizer(Arc<Tokenizer>),
+    CharEstimate { chars_per_token: usize },
+}
+
+impl TokenCounter {
+    pub fn from_env() -> Self {
+        let chars_per_token = std::env::var("MCP_CHARS_PER_TOKEN")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(4);
+        let estimator = std::env::var("MCP_TOKEN_ESTIMATOR").unwrap_or_default();
+        if estimator.eq_ignore_ascii_case("chars") {
+            return Self::char_estimate(chars_per_token);
+        }
+        let tokenizer = EmbeddingConfig::default()
+            .tokenizer_path
+            .and_then(|path| match Tokenizer::from_file(&path) {
+                Ok(tokenizer) => Some(tokenizer),
+                Err(err) => {
+                    tracing::warn!(path, error = %err, "token counter falling back to char estimate");
+                    None
+                }
+            });
+        match tokenizer {
+            Some(tokenizer) => Self::Tokenizer(Arc::new(tokenizer)),
+            None => Self::char_estimate(chars_per_token),
+        }
+    }
+
+    pub fn char_estimate(chars_per_token: usize) -> Self {
+        Self::CharEstimate {
+            chars_per_token: chars_per_token.max(1),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Tokenizer(_) => "tokenizer",
+            Self::CharEstimate { .. } => "chars",
+        }
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        match self {
+            Self::Tokenizer(tokenizer) => match tokenizer.encode(text, false) {
+                Ok(encoding) => encoding.len(),
+                Err(_) => estimate_tokens(text, 4),
+            },
+            Self::CharEstimate { chars_per_token } => estimate_tokens(text, *chars_per_token),
+        }
+    }
+}
+
+fn estimate_tokens(text: &str, chars_per_token: usize) -> usize {
+    text.chars().count().div_ceil(chars_per_token.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenCounter;
+
+    #[test]
+    fn char_estimate_rounds_up() {
+        let counter = TokenCounter::char_estimate(4);
+        assert_eq!(counter.count(""), 0);
+        assert_eq!(counter.count("abcde"), 2);
+        assert_eq!(counter.name(), "chars");
+    }
+}
@@ -1,9 +1,12 @@
 use std::{
+    future::Future,
     sync::Arc,
     sync::atomic::{AtomicU64, Ordering},
     time::{Duration, Instant},
 };
 
+use anyhow::{Result, anyhow};
+use serde::Serialize;
 use tokio::sync::mpsc;
 
 use crate::{EmbeddingEngine, queue::EmbeddingJob};
@@ -12,6 +15,8 @@ use crate::{EmbeddingEngine, queue::EmbeddingJob};
 pub struct EmbeddingWorkerConfig {
     pub batch_size: usize,
     pub max_retries: usize,
+    pub base_backoff_ms: u64,
+    pub max_backoff_ms: u64,
 }
 
 impl Default for EmbeddingWorkerConfig {
@@ -19,15 +24,70 @@ impl Default for EmbeddingWorkerConfig {
         Self {
             batch_size: 128,
             max_retries: 2,
+            base_backoff_ms: 100,
+            max_backoff_ms: 5_000,
         }
     }
 }
 
+impl EmbeddingWorkerConfig {
+    fn backoff(&self, attempt: usize) -> Duration {
+        let factor = 1u64 << attempt.min(16);
+        Duration::from_millis(
+            self.base_backoff_ms
+                .saturating_mul(factor)
+                .min(self.max_backoff_ms),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddingResult {
+    pub chunk_id: String,
+    pub corpus: String,
+    pub vector: Vec<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeadLetter {
+    pub chunk_id: String,
+    pub corpus: String,
+    pub error: String,
+    pub attempts: usize,
+}
+
+pub trait EmbeddingSink: Send + Sync + 'static {
+    fn deliver(&self, results: &[EmbeddingResult]) -> impl Future<Output = Result<()>> + Send;
+
+    fn dead_letter(&self, letters: Vec<DeadLetter>) -> impl Future<Output = ()> + Send;
+
+    fn accepts(&self, _job: &EmbeddingJob) -> bool {
+        true
+    }
+}
+
+impl<S: EmbeddingSink> EmbeddingSink for Arc<S> {
+    fn deliver(&self, results: &[EmbeddingResult]) -> impl Future<Output = Result<()>> + Send {
+        self.as_ref().deliver(results)
+    }
+
+    fn dead_letter(&self, letters: Vec<DeadLetter>) -> impl Future<Output = ()> + Send {
+        self.as_ref().dead_letter(letters)
+    }
+
+    fn accepts(&self, job: &EmbeddingJob) -> bool {
+        self.as_ref().accepts(job)
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct EmbeddingWorkerMetrics {
     batches_processed: AtomicU64,
     items_processed: AtomicU64,
     failures: AtomicU64,
+    dead_lettered: AtomicU64,
+    items_embedded: AtomicU64,
+    skipped: AtomicU64,
     total_latency_ms: AtomicU64,
 }
 
@@ -36,6 +96,9 @@ pub struct EmbeddingWorkerMetricsSnapshot {
     pub batches_processed: u64,
     pub items_processed: u64,
     pub failures: u64,
+    pub dead_lettered: u64,
+    pub items_embedded: u64,
+    pub skipped: u64,
     pub avg_latency_ms: u64,
 }
 
@@ -52,6 +115,17 @@ impl EmbeddingWorkerMetrics {
         self.failures.fetch_add(1, Ordering::Relaxed);
     }
 
+    fn record_dead_letters(&self, count: usize) {
+        self.dead_lettered
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    fn record_embedded(&self, embedded: usize, skipped: usize) {
+        self.items_embedded
+            .fetch_add(embedded as u64, Ordering::Relaxed);
+        self.skipped.fetch_add(skipped as u64, Ordering::Relaxed);
+    }
+
     pub fn snapshot(&self) -> EmbeddingWorkerMetricsSnapshot {
         let batches = self.batches_processed.load(Ordering::Relaxed);
         let total = self.total_latency_ms.load(Ordering::Relaxed);
@@ -59,23 +133,28 @@ impl EmbeddingWorkerMetrics {
             batches_processed: batches,
             items_processed: self.items_processed.load(Ordering::Relaxed),
             failures: self.failures.load(Ordering::Relaxed),
+            dead_lettered: self.dead_lettered.load(Ordering::Relaxed),
+            items_embedded: self.items_embedded.load(Ordering::Relaxed),
+            skipped: self.skipped.load(Ordering::Relaxed),
             avg_latency_ms: if batches == 0 { 0 } else { total / batches },
         }
     }
 }
 
-pub async fn run_embedding_worker(
+pub async fn run_embedding_worker<S: EmbeddingSink>(
     rx: mpsc::Receiver<EmbeddingJob>,
-    engine: EmbeddingEngine,
+    engine: Arc<EmbeddingEngine>,
     cfg: EmbeddingWorkerConfig,
+    sink: S,
 ) {
-    run_embedding_worker_with_metrics(rx, engine, cfg, None).await;
+    run_embedding_worker_with_metrics(rx, engine, cfg, sink, None).await;
 }
 
-pub async fn run_embedding_worker_with_metrics(
+pub async fn run_embedding_worker_with_metrics<S: EmbeddingSink>(
     mut rx: mpsc::Receiver<EmbeddingJob>,
-    engine: EmbeddingEngine,
+    engine: Arc<EmbeddingEngine>,
     cfg: EmbeddingWorkerConfig,
+    sink: S,
     metrics: Option<Arc<EmbeddingWorkerMetrics>>,
 ) {
     while let Some(first) = rx.recv().await {
@@ -87,60 +166,312 @@ pub async fn run_embedding_worker_with_metrics(
             }
         }
 
-        let texts = batch.iter().map(|j| j.text.clone()).collect::<Vec<_>>();
+        // Jobs the sink no longer wants (superseded generations) are dropped unembedded.
+        let queued = batch.len();
+        batch.retain(|job| sink.accepts(job));
+        if let Some(metrics) = metrics.as_ref() {
+            metrics.record_embedded(batch.len(), queued - batch.len());
+        }
+        if batch.is_empty() {
+            continue;
+        }
+
         let started = Instant::now();
-        let mut success = false;
-        for _attempt in 0..=cfg.max_retries {
-            if engine.embed_batch(&texts).is_ok() {
-                success = true;
-                break;
-            }
+        let (results, mut letters) = embed_jobs(&engine, &cfg, batch).await;
+        engine.flush_cache();
+        let mut delivered = results.len();
+        if !results.is_empty()
+            && let Err(err) = deliver_with_retries(&sink, &cfg, &results).await
+        {
+            delivered = 0;
+            letters.extend(results.into_iter().map(|result| DeadLetter {
+                chunk_id: result.chunk_id,
+                corpus: result.corpus,
+                error: format!("sink rejected vectors: {err:#}"),
+                attempts: cfg.max_retries + 1,
+            }));
         }
 
         if let Some(metrics) = metrics.as_ref() {
-            if success {
-                metrics.record_batch(batch.len(), started.elapsed().as_millis() as u64);
-            } else {
+            if delivered > 0 {
+                metrics.record_batch(delivered, started.elapsed().as_millis() as u64);
+            }
+            if !letters.is_empty() {
                 metrics.record_failure();
+                metrics.record_dead_letters(letters.len());
+            }
+        }
+        if !letters.is_empty() {
+            tracing::warn!(
+                count = letters.len(),
+                "embedding jobs dead-lettered after retries"
+            );
+            sink.dead_letter(letters).await;
+        }
+    }
+}
+
+async fn embed_jobs(
+    engine: &Arc<EmbeddingEngine>,
+    cfg: &EmbeddingWorkerConfig,
+    batch: Vec<EmbeddingJob>,
+) -> (Vec<EmbeddingResult>, Vec<DeadLetter>) {
+    let mut groups = Vec::<(String, Vec<EmbeddingJob>)>::new();
+    for job in batch {
+        match groups.iter_mut().find(|(corpus, _)| *corpus == job.corpus) {
+            Some((_, jobs)) => jobs.push(job),
+            None => groups.push((job.corpus.clone(), vec![job])),
+        }
+    }
+
+    let mut results = Vec::new();
+    let mut letters = Vec::new();
+    for (corpus, jobs) in groups {
+        let texts = jobs.iter().map(|job| job.text.clone()).collect::<Vec<_>>();
+        let err = match embed_with_retries(engine, cfg, &corpus, texts).await {
+            Ok(vectors) => {
+                results.extend(jobs.into_iter().zip(vectors).map(|(job, vector)| {
+                    EmbeddingResult {
+                        chunk_id: job.chunk_id,
+                        corpus: job.corpus,
+                        vector,
+                    }
+                }));
+                continue;
+            }
+            Err(err) => err,
+        };
+        if jobs.len() == 1 {
+            letters.extend(jobs.into_iter().map(|job| DeadLetter {
+                chunk_id: job.chunk_id,
+                corpus: job.corpus,
+                error: format!("{err:#}"),
+                attempts: cfg.max_retries + 1,
+            }));
+            continue;
+        }
+
+        // Retry one input at a time so a single poison input cannot fail its whole batch.
+        for job in jobs {
+            match embed_once(engine, &corpus, vec![job.text.clone()]).await {
+                Ok(mut vectors) => results.push(EmbeddingResult {
+                    chunk_id: job.chunk_id,
+                    corpus: job.corpus,
+                    vector: vectors.remove(0),
+                }),
+                Err(err) => letters.push(DeadLetter {
+                    chunk_id: job.chunk_id,
+                    corpus: job.corpus,
+                    error: format!("{err:#}"),
+                    attempts: cfg.max_retries + 2,
+                }),
+            }
+        }
+    }
+    (results, letters)
+}
+
+async fn embed_with_retries(
+    engine: &Arc<EmbeddingEngine>,
+    cfg: &EmbeddingWorkerConfig,
+    corpus: &str,
+    texts: Vec<String>,
+) -> Result<Vec<Vec<f32>>> {
+    let mut attempt = 0;
+    loop {
+        match embed_once(engine, corpus, texts.clone()).await {
+            Ok(vectors) => return Ok(vectors),
+            Err(err) if attempt < cfg.max_retries => {
+                tracing::debug!(attempt, error = %err, "retrying embedding batch");
+                tokio::time::sleep(cfg.backoff(attempt)).await;
+                attempt += 1;
             }
+            Err(err) => return Err(err),
         }
+    }
+}
 
-        tokio::time::sleep(Duration::from_millis(1)).await;
+async fn embed_once(
+    engine: &Arc<EmbeddingEngine>,
+    corpus: &str,
+    texts: Vec<String>,
+) -> Result<Vec<Vec<f32>>> {
+    let engine = engine.clone();
+    let corpus = corpus.to_string();
+    let expected = texts.len();
+    let vectors =
+        tokio::task::spawn_blocking(move || engine.embed_documents(&corpus, &texts)).await??;
+    if vectors.len() != expected {
+        return Err(anyhow!(
+            "embedding returned {} vectors for {expected} inputs",
+            vectors.len()
+        ));
+    }
+    Ok(vectors)
+}
+
+async fn deliver_with_retries<S: EmbeddingSink>(
+    sink: &S,
+    cfg: &EmbeddingWorkerConfig,
+    results: &[EmbeddingResult],
+) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        match sink.deliver(results).await {
+            Ok(()) => return Ok(()),
+            Err(err) if attempt < cfg.max_retries => {
+                tracing::debug!(attempt, error = %err, "retrying embedding delivery");
+                tokio::time::sleep(cfg.backoff(attempt)).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use anyhow::{Result, anyhow};
+
     use crate::{
-        EmbeddingConfig, EmbeddingEngine,
+        EmbeddingConfig, EmbeddingEngine, RemoteEmbeddingConfig, RemoteProvider,
         queue::{EmbeddingJob, EmbeddingQueue},
         worker::{
-            EmbeddingWorkerConfig, EmbeddingWorkerMetrics, run_embedding_worker_with_metrics,
+            DeadLetter, EmbeddingResult, EmbeddingSink, EmbeddingWorkerConfig,
+            EmbeddingWorkerMetrics, run_embedding_worker_with_metrics,
         },
     };
 
+    #[derive(Clone, Default)]
+    struct RecordingSink {
+        rejections: Arc<AtomicUsize>,
+        delivered: Arc<Mutex<Vec<EmbeddingResult>>>,
+        dead: Arc<Mutex<Vec<DeadLetter>>>,
+    }
+
+    impl EmbeddingSink for RecordingSink {
+        async fn deliver(&self, results: &[EmbeddingResult]) -> Result<()> {
+            if self
+                .rejections
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return Err(anyhow!("store unavailable"));
+            }
+            self.delivered
+                .lock()
+                .expect("delivered")
+                .extend_from_slice(results);
+            Ok(())
+        }
+
+        async fn dead_letter(&self, letters: Vec<DeadLetter>) {
+            self.dead.lock().expect("dead").extend(letters);
+        }
+    }
+
+    fn job(chunk_id: &str, text: &str) -> EmbeddingJob {
+        EmbeddingJob {
+            chunk_id: chunk_id.to_string(),
+            corpus: "repo".to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    fn fast_retries() -> EmbeddingWorkerConfig {
+        EmbeddingWorkerConfig {
+            base_backoff_ms: 1,
+            ..EmbeddingWorkerConfig::default()
+        }
+    }
+
     #[tokio::test]
-    async fn worker_processes_queue_until_channel_closes() {
+    async fn worker_delivers_vectors_after_sink_retries() {
         let (queue, rx) = EmbeddingQueue::new(8);
-        queue
-            .enqueue(EmbeddingJob {
-                chunk_id: "c1".to_string(),
-                text: "hello".to_string(),
-            })
-            .await
-            .expect("enqueue");
+        queue.enqueue(job("c1", "hello")).await.expect("enqueue");
+        queue.enqueue(job("c2", "world")).await.expect("enqueue");
         drop(queue);
 
-        let engine = EmbeddingEngine::new(EmbeddingConfig::default());
-        let metrics = std::sync::Arc::new(EmbeddingWorkerMetrics::default());
+        let engine = Arc::new(EmbeddingEngine::new(EmbeddingConfig::default()));
+        let sink = RecordingSink {
+            rejections: Arc::new(AtomicUsize::new(1)),
+            ..RecordingSink::default()
+        };
+        let metrics = Arc::new(EmbeddingWorkerMetrics::default());
         run_embedding_worker_with_metrics(
             rx,
-            engine,
-            EmbeddingWorkerConfig::default(),
+            engine.clone(),
+            fast_retries(),
+            sink.clone(),
             Some(metrics.clone()),
         )
         .await;
+
+        let delivered = sink.delivered.lock().expect("delivered");
+        assert_eq!(
+            delivered
+                .iter()
+                .map(|r| r.chunk_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["c1", "c2"]
+        );
+        assert_eq!(delivered[0].vector.len(), engine.vector_dim());
+        assert!(sink.dead.lock().expect("dead").is_empty());
         let snapshot = metrics.snapshot();
-        assert_eq!(snapshot.items_processed, 1);
+        assert_eq!((snapshot.items_processed, snapshot.dead_lettered), (2, 0));
+    }
+
+    #[tokio::test]
+    async fn poison_input_is_dead_lettered_without_failing_its_batch() {
+        let server = crate::remote::tests::stub_server(vec![
+            (200, r#"{"embeddings":[[1.0,0.0]]}"#.to_string()),
+            (400, r#"{"error":"input too long"}"#.to_string()),
+            (200, r#"{"embeddings":[[0.0,1.0]]}"#.to_string()),
+            (400, r#"{"error":"input too long"}"#.to_string()),
+        ]);
+        let engine = Arc::new(EmbeddingEngine::new(EmbeddingConfig {
+            manifest_path: None,
+            remote: Some(RemoteEmbeddingConfig::new(
+                RemoteProvider::Ollama,
+                &server.url,
+                "m",
+            )),
+            ..EmbeddingConfig::default()
+        }));
+        let (queue, rx) = EmbeddingQueue::new(8);
+        queue.enqueue(job("ok", "fine")).await.expect("enqueue");
+        queue.enqueue(job("poison", "bad")).await.expect("enqueue");
+        drop(queue);
+
+        let sink = RecordingSink::default();
+        let metrics = Arc::new(EmbeddingWorkerMetrics::default());
+        run_embedding_worker_with_metrics(
+            rx,
+            engine,
+            EmbeddingWorkerConfig {
+                max_retries: 0,
+                ..fast_retries()
+            },
+            sink.clone(),
+            Some(metrics.clone()),
+        )
+        .await;
+
+        let delivered = sink.delivered.lock().expect("delivered");
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(
+            (delivered[0].chunk_id.as_str(), &delivered[0].vector),
+            ("ok", &vec![0.0, 1.0])
+        );
+        let dead = sink.dead.lock().expect("dead");
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].chunk_id, "poison");
+        assert!(dead[0].error.contains("400"));
+        assert_eq!(metrics.snapshot().dead_lettered, 1);
     }
 }
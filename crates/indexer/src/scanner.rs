@@ -21,10 +21,10 @@ pub fn scan_source_files(root: &Path) -> Vec<PathBuf> {
         if is_ignored_path(path) {
             continue;
         }
-        if let Ok(meta) = path.metadata() {
-            if meta.len() > max_file_bytes {
-                continue;
-            }
+        if let Ok(meta) = path.metadata()
+            && meta.len() > max_file_bytes
+        {
+            continue;
         }
         if is_probably_binary(path) {
             continue;
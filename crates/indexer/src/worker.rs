@@ -23,7 +23,7 @@ impl IndexWorkQueue {
 pub async fn run_worker(mut rx: mpsc::Receiver<PathBuf>) {
     while let Some(path) = rx.recv().await {
         // CPU-heavy indexing work is delegated to rayon threads.
-        let _ = rayon::spawn(move || {
+        rayon::spawn(move || {
             let _normalized = normalize_paths_for_batch(vec![path]);
         });
     }
@@ -18,11 +18,47 @@ struct Cli {
 
 #[derive(Debug, Subcommand)]
 enum Commands {
-    AddRepo { path: PathBuf },
-    RemoveRepo { path: PathBuf },
+    AddRepo {
+        path: PathBuf,
+    },
+    RemoveRepo {
+        path: PathBuf,
+    },
     ListRepos,
-    IndexNow { path: Option<PathBuf> },
+    IndexNow {
+        path: Option<PathBuf>,
+    },
     Status,
+    ResetFeedback {
+        path: Option<PathBuf>,
+    },
+    Pin {
+        #[command(subcommand)]
+        command: PinCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum PinCommand {
+    Add {
+        query: String,
+        file: String,
+        #[arg(long)]
+        symbol: Option<String>,
+        #[arg(long, value_name = "START-END")]
+        lines: Option<String>,
+        #[arg(long)]
+        repo: Option<PathBuf>,
+    },
+    List {
+        #[arg(long)]
+        repo: Option<PathBuf>,
+    },
+    Remove {
+        id: String,
+        #[arg(long)]
+        repo: Option<PathBuf>,
+    },
 }
 
 fn main() -> anyhow::Result<()> {
@@ -35,6 +71,25 @@ fn main() -> anyhow::Result<()> {
         Commands::ListRepos => list_repos(&cwd),
         Commands::IndexNow { path } => index_now(&cwd, path.as_deref()),
         Commands::Status => status(&cwd),
+        Commands::ResetFeedback { path } => reset_feedback(&cwd, path.as_deref()),
+        Commands::Pin { command } => match command {
+            PinCommand::Add {
+                query,
+                file,
+                symbol,
+                lines,
+                repo,
+            } => pin_add(
+                &cwd,
+                repo.as_deref(),
+                &query,
+                &file,
+                symbol.as_deref(),
+                lines.as_deref(),
+            ),
+            PinCommand::List { repo } => pin_list(&cwd, repo.as_deref()),
+            PinCommand::Remove { id, repo } => pin_remove(&cwd, repo.as_deref(), &id),
+        },
     }
 }
 
@@ -96,6 +151,112 @@ fn status(cwd: &Path) -> anyhow::Result<()> {
     Ok(())
 }
 
+fn reset_feedback(cwd: &Path, path: Option<&Path>) -> anyhow::Result<()> {
+    let repo_path = match path {
+        Some(p) => canonical_repo_path(p)?,
+        None => projects::read_selected_project(cwd)
+            .filter(|v| !v.is_empty())
+            .context("no repo selected; pass a path or run add-repo first")?,
+    };
+    let removed = projects::reset_feedback(cwd, &repo_path)?;
+    println!("reset feedback: {repo_path} (events={removed})");
+    Ok(())
+}
+
+fn pin_add(
+    cwd: &Path,
+    path: Option<&Path>,
+    query: &str,
+    file: &str,
+    symbol: Option<&str>,
+    lines: Option<&str>,
+) -> anyhow::Result<()> {
+    let repo_path = selected_or_given_repo(cwd, path)?;
+    let (start_line, end_line) = match (lines, symbol) {
+        (Some(lines), _) => parse_line_range(lines)?,
+        (None, Some(symbol)) => projects::load_project_index(cwd, &repo_path)
+            .and_then(|indexed| {
+                indexed
+                    .chunks
+                    .iter()
+                    .find(|c| {
+                        search_core::pins::same_file(&c.file, file)
+                            && c.symbol.as_deref() == Some(symbol)
+                    })
+                    .map(|c| (c.start_line, c.end_line))
+            })
+            .with_context(|| {
+                format!("symbol {symbol} not found in {file}; run index-now or pass --lines")
+            })?,
+        (None, None) => anyhow::bail!("pass --symbol or --lines to choose what to pin"),
+    };
+    let pin = projects::add_pin(
+        cwd,
+        &repo_path,
+        projects::PinnedResult {
+            id: String::new(),
+            query_pattern: query.to_string(),
+            file: file.to_string(),
+            symbol: symbol.map(str::to_string),
+            start_line,
+            end_line,
+            created_at_unix: unix_now(),
+        },
+    )?;
+    println!(
+        "pinned {}: {}:{}-{} for \"{}\"",
+        pin.id, pin.file, pin.start_line, pin.end_line, pin.query_pattern
+    );
+    Ok(())
+}
+
+fn pin_list(cwd: &Path, path: Option<&Path>) -> anyhow::Result<()> {
+    let repo_path = selected_or_given_repo(cwd, path)?;
+    for pin in projects::load_pins(cwd, &repo_path) {
+        println!(
+            "{}. \"{}\" -> {}:{}-{}{}",
+            pin.id,
+            pin.query_pattern,
+            pin.file,
+            pin.start_line,
+            pin.end_line,
+            pin.symbol.map(|s| format!(" ({s})")).unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+fn pin_remove(cwd: &Path, path: Option<&Path>, id: &str) -> anyhow::Result<()> {
+    let repo_path = selected_or_given_repo(cwd, path)?;
+    if !projects::remove_pin(cwd, &repo_path, id)? {
+        anyhow::bail!("pin not found: {id}");
+    }
+    println!("removed pin: {id}");
+    Ok(())
+}
+
+fn selected_or_given_repo(cwd: &Path, path: Option<&Path>) -> anyhow::Result<String> {
+    match path {
+        Some(p) => canonical_repo_path(p),
+        None => projects::read_selected_project(cwd)
+            .filter(|v| !v.is_empty())
+            .context("no repo selected; pass a path or run add-repo first"),
+    }
+}
+
+fn parse_line_range(raw: &str) -> anyhow::Result<(usize, usize)> {
+    let (start, end) = raw.split_once('-').unwrap_or((raw, raw));
+    let start = start
+        .trim()
+        .parse::<usize>()
+        .context("invalid start line")?;
+    let end = end.trim().parse::<usize>().context("invalid end line")?;
+    if start == 0 || end < start {
+        anyhow::bail!("invalid line range: {raw}");
+    }
+    Ok((start, end))
+}
+
 fn ensure_catalog_entry(cwd: &Path, repo_path: &str) -> anyhow::Result<()> {
     if projects::load_project_index(cwd, repo_path).is_some() {
         return Ok(());
@@ -108,6 +269,8 @@ fn ensure_catalog_entry(cwd: &Path, repo_path: &str) -> anyhow::Result<()> {
             chunks_extracted: 0,
             indexed_at_unix: unix_now(),
             chunks: Vec::new(),
+            embedding_template_version: None,
+            embedding_model: None,
         },
     )?;
     Ok(())
@@ -143,9 +306,11 @@ fn run_index(cwd: &Path, repo: &Path) -> anyhow::Result<(usize, usize)> {
         chunks_extracted: indexed_chunks.len(),
         indexed_at_unix: unix_now(),
         chunks: indexed_chunks,
+        embedding_template_version: None,
+        embedding_model: None,
     };
-    projects::save_project_index(cwd, &indexed)?;
     persist_tantivy_index(cwd, &project_path, &code_chunks)?;
+    projects::save_project_index(cwd, &indexed)?;
 
     Ok((files.len(), indexed.chunks_extracted))
 }
@@ -50,3 +50,76 @@ fn add_index_status_remove_flow_works() {
         .success()
         .stdout(contains("removed repo"));
 }
+
+#[test]
+fn reset_feedback_clears_selected_repo_events() {
+    let (tmp, repo) = setup_workspace();
+    let cwd = tmp.path();
+    let repo_str = std::fs::canonicalize(&repo)
+        .expect("canonical")
+        .display()
+        .to_string();
+    common::projects::append_feedback_event(
+        cwd,
+        &repo_str,
+        &common::projects::FeedbackEvent {
+            query: "main".to_string(),
+            chunk_id: "src/main.rs:1:1:main".to_string(),
+            file: "src/main.rs".to_string(),
+            rank: 1,
+            unix_ms: 1,
+        },
+    )
+    .expect("append feedback");
+
+    Command::cargo_bin("codivex-mcp")
+        .expect("binary")
+        .current_dir(cwd)
+        .args(["add-repo", &repo_str])
+        .assert()
+        .success();
+
+    Command::cargo_bin("codivex-mcp")
+        .expect("binary")
+        .current_dir(cwd)
+        .args(["reset-feedback"])
+        .assert()
+        .success()
+        .stdout(contains("events=1"));
+    assert!(common::projects::load_feedback_events(cwd, &repo_str).is_empty());
+}
+
+#[test]
+fn pin_add_list_remove_round_trip() {
+    let (tmp, repo) = setup_workspace();
+    let cwd = tmp.path();
+    let repo_str = repo.display().to_string();
+    let run = |args: &[&str]| {
+        Command::cargo_bin("codivex-mcp")
+            .expect("binary")
+            .current_dir(cwd)
+            .args(args)
+            .assert()
+    };
+
+    run(&["add-repo", &repo_str]).success();
+    run(&["index-now"]).success();
+    run(&[
+        "pin",
+        "add",
+        "entry point",
+        "src/main.rs",
+        "--symbol",
+        "main",
+    ])
+    .success()
+    .stdout(contains("pinned p1: src/main.rs:1-1"));
+    run(&["pin", "add", "missing", "src/main.rs", "--symbol", "nope"]).failure();
+    run(&["pin", "list"])
+        .success()
+        .stdout(contains("p1. \"entry point\" -> src/main.rs:1-1 (main)"));
+    run(&["pin", "remove", "p1"])
+        .success()
+        .stdout(contains("removed pin: p1"));
+    run(&["pin", "remove", "p1"]).failure();
+}
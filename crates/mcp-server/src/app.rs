@@ -5,7 +5,7 @@ use axum::{
 };
 
 use crate::handlers::{
-    health::health,
+    health::{health, readiness},
     mcp::mcp_handler,
     metrics::metrics_handler,
     port_diagnostics::port_diagnostics_handler,
@@ -20,6 +20,7 @@ use crate::state::AppState;
 pub fn router(state: AppState) -> Router {
     Router::new()
         .route("/health", get(health))
+        .route("/ready", get(readiness))
         .route("/port-diagnostics", get(port_diagnostics_handler))
         .route("/metrics", get(metrics_handler))
         .route("/telemetry", get(telemetry_handler))
@@ -1,4 +1,4 @@
-use std::{fs, path::PathBuf, time::Instant};
+use std::{fs, path::PathBuf, sync::Arc, time::Instant};
 
 use common::{
     CodeChunk,
@@ -7,7 +7,10 @@ use common::{
 use embeddings::{EmbeddingConfig, EmbeddingEngine};
 use indexer::{extract_chunks_for_file, incremental::ByteEdit, incremental::incremental_reparse};
 use mcp_server::services::search::scoped_project_results;
-use search_core::lexical::TantivyLexicalIndex;
+use search_core::{
+    EmbeddingTemplate, LocalQuantization, LocalVectorConfig, LocalVectorIndex, VectorField,
+    embed_chunk_views, lexical::TantivyLexicalIndex,
+};
 use serde::Serialize;
 use tree_sitter::Point;
 
@@ -23,6 +26,17 @@ struct BenchmarkReport {
     full_hybrid_query_latency_ms: u128,
     throughput_qps_estimate: f64,
     query_embedding_ms: u128,
+    vector_quantization: Vec<QuantizationReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct QuantizationReport {
+    mode: String,
+    memory_bytes: usize,
+    full_precision_bytes: usize,
+    compression_ratio: f64,
+    recall_at_10: f64,
+    avg_search_us: u128,
 }
 
 struct PreparedDataset {
@@ -30,6 +44,7 @@ struct PreparedDataset {
     project_path: String,
     files_scanned: usize,
     chunks_extracted: usize,
+    chunks: Vec<CodeChunk>,
 }
 
 #[tokio::main]
@@ -49,12 +64,15 @@ async fn main() -> anyhow::Result<()> {
         .unwrap_or(200);
 
     let prepared = prepare_dataset(&dataset_path, max_files)?;
+    let engine = Arc::new(EmbeddingEngine::new(EmbeddingConfig::default()));
+    let _ = engine.warm_up();
     let cold = bench_cold_start_indexing()?;
     let incr = bench_incremental_update()?;
     let query_latency = bench_query_latency(&prepared, &query)?;
-    let full_hybrid = bench_full_hybrid_query_latency(&prepared, &query).await?;
-    let qps = bench_throughput_estimate(&prepared, &query).await?;
-    let embed = bench_query_embedding();
+    let full_hybrid = bench_full_hybrid_query_latency(&prepared, &engine, &query).await?;
+    let qps = bench_throughput_estimate(&prepared, &engine, &query).await?;
+    let embed = bench_query_embedding(&engine, &prepared.project_path);
+    let vector_quantization = bench_vector_quantization(&prepared, &engine)?;
 
     let report = BenchmarkReport {
         dataset_profile,
@@ -67,6 +85,7 @@ async fn main() -> anyhow::Result<()> {
         full_hybrid_query_latency_ms: full_hybrid,
         throughput_qps_estimate: qps,
         query_embedding_ms: embed,
+        vector_quantization,
     };
 
     let json = serde_json::to_string_pretty(&report)?;
@@ -106,12 +125,11 @@ fn prepare_dataset(dataset_path: &str, max_files: usize) -> anyhow::Result<Prepa
 
     let mut chunks = Vec::<CodeChunk>::new();
     for file in &files {
-        if let Ok(content) = std::fs::read_to_string(file) {
-            if let Ok(extracted) =
+        if let Ok(content) = std::fs::read_to_string(file)
+            && let Ok(extracted) =
                 extract_chunks_for_file(file.to_string_lossy().as_ref(), &content)
-            {
-                chunks.extend(extracted);
-            }
+        {
+            chunks.extend(extracted);
         }
     }
 
@@ -132,6 +150,8 @@ fn prepare_dataset(dataset_path: &str, max_files: usize) -> anyhow::Result<Prepa
         chunks_extracted: chunks.len(),
         indexed_at_unix: unix_now(),
         chunks: indexed_chunks,
+        embedding_template_version: None,
+        embedding_model: None,
     };
     projects::save_project_index(&bench_root, &indexed)?;
 
@@ -148,6 +168,7 @@ fn prepare_dataset(dataset_path: &str, max_files: usize) -> anyhow::Result<Prepa
         project_path: indexed.project_path,
         files_scanned: files.len(),
         chunks_extracted: chunks.len(),
+        chunks,
     })
 }
 
@@ -184,18 +205,24 @@ fn bench_query_latency(prepared: &PreparedDataset, query: &str) -> anyhow::Resul
 
 async fn bench_full_hybrid_query_latency(
     prepared: &PreparedDataset,
+    engine: &Arc<EmbeddingEngine>,
     query: &str,
 ) -> anyhow::Result<u128> {
     let start = Instant::now();
-    let _ = scoped_project_results(&prepared.cwd, &prepared.project_path, query, 5).await?;
+    let _ = scoped_project_results(&prepared.cwd, &prepared.project_path, engine, query, 5).await?;
     Ok(start.elapsed().as_millis())
 }
 
-async fn bench_throughput_estimate(prepared: &PreparedDataset, query: &str) -> anyhow::Result<f64> {
+async fn bench_throughput_estimate(
+    prepared: &PreparedDataset,
+    engine: &Arc<EmbeddingEngine>,
+    query: &str,
+) -> anyhow::Result<f64> {
     let ops = 250usize;
     let start = Instant::now();
     for _ in 0..ops {
-        let _ = scoped_project_results(&prepared.cwd, &prepared.project_path, query, 5).await?;
+        let _ =
+            scoped_project_results(&prepared.cwd, &prepared.project_path, engine, query, 5).await?;
     }
     let secs = start.elapsed().as_secs_f64();
     if secs == 0.0 {
@@ -204,13 +231,97 @@ async fn bench_throughput_estimate(prepared: &PreparedDataset, query: &str) -> a
     Ok(ops as f64 / secs)
 }
 
-fn bench_query_embedding() -> u128 {
-    let engine = EmbeddingEngine::new(EmbeddingConfig::default());
+fn bench_query_embedding(engine: &EmbeddingEngine, project: &str) -> u128 {
     let start = Instant::now();
-    let _ = engine.embed_batch(&["save user record".to_string()]).ok();
+    let _ = engine
+        .embed_queries(project, &["save user record".to_string()])
+        .ok();
     start.elapsed().as_millis()
 }
 
+fn bench_vector_quantization(
+    prepared: &PreparedDataset,
+    engine: &EmbeddingEngine,
+) -> anyhow::Result<Vec<QuantizationReport>> {
+    const K: usize = 10;
+    let project = prepared.project_path.as_str();
+    let Ok(vectors) = embed_chunk_views(&prepared.chunks, &EmbeddingTemplate::default(), |t| {
+        engine.embed_documents(project, t)
+    }) else {
+        return Ok(Vec::new());
+    };
+    let stride = (prepared.chunks.len() / 20).max(1);
+    let queries = prepared
+        .chunks
+        .iter()
+        .step_by(stride)
+        .filter_map(|chunk| chunk.symbol.clone())
+        .collect::<Vec<_>>();
+    let Ok(queries) = engine.embed_queries(project, &queries) else {
+        return Ok(Vec::new());
+    };
+    if vectors.is_empty() || queries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ids = prepared
+        .chunks
+        .iter()
+        .map(|c| c.id.clone())
+        .collect::<Vec<_>>();
+    let mut exact = Vec::new();
+    let mut reports = Vec::new();
+    for quantization in [
+        LocalQuantization::None,
+        LocalQuantization::Int8,
+        LocalQuantization::Binary,
+    ] {
+        let index = LocalVectorIndex::build(
+            &prepared
+                .cwd
+                .join(format!("vectors-{}.f32", quantization.as_str())),
+            LocalVectorConfig {
+                quantization,
+                ..LocalVectorConfig::from_env()
+            },
+            ids.clone(),
+            &vectors,
+        )?;
+        let start = Instant::now();
+        let results = queries
+            .iter()
+            .map(|q| {
+                index
+                    .search(VectorField::Code, q, K)
+                    .map(|hits| hits.into_iter().map(|(id, _)| id).collect::<Vec<_>>())
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let avg_search_us = start.elapsed().as_micros() / queries.len() as u128;
+        if exact.is_empty() {
+            exact = results.clone();
+        }
+        let recall = results
+            .iter()
+            .zip(&exact)
+            .map(|(got, want)| {
+                let hits = got.iter().filter(|id| want.contains(id)).count();
+                hits as f64 / want.len().max(1) as f64
+            })
+            .sum::<f64>()
+            / results.len() as f64;
+        reports.push(QuantizationReport {
+            mode: quantization.as_str().to_string(),
+            memory_bytes: index.memory_bytes(),
+            full_precision_bytes: index.full_precision_bytes(),
+            compression_ratio: index.full_precision_bytes() as f64
+                / index.memory_bytes().max(1) as f64,
+            recall_at_10: recall,
+            avg_search_us,
+        });
+    }
+    Ok(reports)
+}
+
 fn unix_now() -> u64 {
     use std::time::{SystemTime, UNIX_EPOCH};
     match SystemTime::now().duration_since(UNIX_EPOCH) {
@@ -1,20 +1,8 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc};
 
-use anyhow::Context;
-use serde::{Deserialize, Serialize};
-
-#[derive(Debug, Deserialize)]
-struct QualityDataset {
-    version: String,
-    project_path: String,
-    queries: Vec<QualityQuery>,
-}
-
-#[derive(Debug, Deserialize)]
-struct QualityQuery {
-    query: String,
-    expected_file_substring: String,
-}
+use embeddings::{EmbeddingConfig, EmbeddingEngine};
+use mcp_server::quality::{load_dataset, redact_path, resolve_project_path};
+use serde::Serialize;
 
 #[derive(Debug, Serialize)]
 struct QualityReport {
@@ -36,22 +24,24 @@ async fn main() -> anyhow::Result<()> {
         .map(PathBuf::from)
         .unwrap_or_else(|_| PathBuf::from("benchmarks/quality-report.json"));
 
-    let dataset: QualityDataset = serde_json::from_str(
-        &std::fs::read_to_string(&dataset_path)
-            .with_context(|| format!("failed reading {}", dataset_path.display()))?,
-    )
-    .with_context(|| format!("failed parsing {}", dataset_path.display()))?;
+    let dataset = load_dataset(&dataset_path)?;
     let cwd = std::env::current_dir()?;
     let project_path = resolve_project_path(&cwd, &dataset.project_path);
+    let engine = Arc::new(EmbeddingEngine::new(EmbeddingConfig::default()));
 
     let mut reciprocal_rank_sum = 0.0;
     let mut hits_at_1 = 0usize;
     let mut hits_at_5 = 0usize;
     for q in &dataset.queries {
-        let items =
-            mcp_server::services::search::scoped_project_results(&cwd, &project_path, &q.query, 10)
-                .await
-                .unwrap_or_default();
+        let items = mcp_server::services::search::scoped_project_results(
+            &cwd,
+            &project_path,
+            &engine,
+            &q.query,
+            10,
+        )
+        .await
+        .unwrap_or_default();
         let rank = items
             .iter()
             .position(|item| item.file.contains(&q.expected_file_substring));
@@ -83,34 +73,3 @@ async fn main() -> anyhow::Result<()> {
     println!("{}", serde_json::to_string_pretty(&report)?);
     Ok(())
 }
-
-fn resolve_project_path(cwd: &std::path::Path, value: &str) -> String {
-    if let Ok(override_path) = std::env::var("QUALITY_PROJECT_PATH")
-        && !override_path.trim().is_empty()
-    {
-        return override_path;
-    }
-    let trimmed = value.trim();
-    if trimmed.is_empty() || trimmed == "." {
-        return cwd.display().to_string();
-    }
-    let p = std::path::Path::new(trimmed);
-    if p.is_absolute() {
-        return p.display().to_string();
-    }
-    cwd.join(p).display().to_string()
-}
-
-fn redact_path(path: &str) -> String {
-    let redact = std::env::var("QUALITY_REDACT_PATH")
-        .map(|v| !v.eq_ignore_ascii_case("false"))
-        .unwrap_or(true);
-    if !redact {
-        return path.to_string();
-    }
-    std::path::Path::new(path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .map(|name| format!("<redacted:{name}>"))
-        .unwrap_or_else(|| "<redacted>".to_string())
-}
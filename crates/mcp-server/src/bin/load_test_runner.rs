@@ -125,6 +125,8 @@ async fn spawn_local_server() -> anyhow::Result<LocalServer> {
                 end_line: 3,
                 content: "fn iso_to_date(input: &str) -> String { input.to_string() }".to_string(),
             }],
+            embedding_template_version: None,
+            embedding_model: None,
         },
     )?;
 
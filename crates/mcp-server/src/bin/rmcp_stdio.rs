@@ -1,10 +1,12 @@
 #![cfg_attr(not(feature = "rmcp-integration"), allow(dead_code))]
 
 #[cfg(feature = "rmcp-integration")]
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc};
 
 #[cfg(feature = "rmcp-integration")]
-use common::{OpenLocationResult, SearchCodeResult};
+use common::{FindSimilarParams, OpenLocationResult, SearchCodeResult};
+#[cfg(feature = "rmcp-integration")]
+use embeddings::{EmbeddingConfig, EmbeddingEngine, TokenCounter};
 #[cfg(feature = "rmcp-integration")]
 use rmcp::{
     ErrorData as McpError, ServerHandler, ServiceExt,
@@ -22,6 +24,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone)]
 struct CodivexRmcpServer {
     cwd: PathBuf,
+    token_counter: Arc<TokenCounter>,
+    embedder: Arc<EmbeddingEngine>,
     tool_router: ToolRouter<Self>,
 }
 
@@ -30,6 +34,8 @@ impl CodivexRmcpServer {
     fn new(cwd: PathBuf) -> Self {
         Self {
             cwd,
+            token_counter: Arc::new(TokenCounter::from_env()),
+            embedder: Arc::new(EmbeddingEngine::new(EmbeddingConfig::default())),
             tool_router: Self::tool_router(),
         }
     }
@@ -43,6 +49,39 @@ struct SearchArgs {
     top_k: Option<usize>,
     #[serde(default)]
     repo_filter: Option<String>,
+    #[serde(default)]
+    max_tokens: Option<usize>,
+    #[serde(default)]
+    group_by_file: bool,
+    #[serde(default)]
+    prefer_tests: bool,
+    #[serde(default)]
+    context_path: Option<String>,
+    #[serde(default)]
+    context_symbol: Option<String>,
+    #[serde(default)]
+    deadline_ms: Option<u64>,
+}
+
+#[cfg(feature = "rmcp-integration")]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct SimilarArgs {
+    #[serde(default)]
+    chunk_id: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    line_start: Option<usize>,
+    #[serde(default)]
+    line_end: Option<usize>,
+    #[serde(default)]
+    top_k: Option<usize>,
+    #[serde(default)]
+    repo_filter: Option<String>,
+    #[serde(default)]
+    more_like_this: Option<bool>,
+    #[serde(default)]
+    deadline_ms: Option<u64>,
 }
 
 #[cfg(feature = "rmcp-integration")]
@@ -71,15 +110,92 @@ impl CodivexRmcpServer {
             .or_else(|| common::projects::read_selected_project(&self.cwd))
             .ok_or_else(|| McpError::invalid_params("project scope required".to_string(), None))?;
         let top_k = args.top_k.unwrap_or(5).max(1);
-        let items = mcp_server::services::search::scoped_project_results(
+        let options = mcp_server::services::search::SearchOptions {
+            prefer_tests: args.prefer_tests,
+            context_path: args.context_path,
+            context_symbol: args.context_symbol,
+        };
+        let deadline = mcp_server::services::search::SearchDeadline::from_request(args.deadline_ms);
+        let search = mcp_server::services::search::scoped_project_search(
             &self.cwd,
             &scope,
+            &self.embedder,
             &args.query,
             top_k,
+            &options,
+            &deadline,
         )
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
-        let result = SearchCodeResult { items };
+        let mut items = search.items;
+        for item in &mut items {
+            item.explain = None;
+        }
+        let mut result = SearchCodeResult {
+            items,
+            budget: None,
+            groups: Vec::new(),
+            degraded: search.degraded.is_some(),
+        };
+        if let Some(max_tokens) = args.max_tokens {
+            result = mcp_server::services::packing::pack_results(
+                result,
+                max_tokens,
+                &self.token_counter,
+            );
+        }
+        if args.group_by_file {
+            result = mcp_server::services::search::group_results_by_file(result);
+        }
+        serde_json::to_string(&result)
+            .map_err(|e| McpError::internal_error(format!("serialize result failed: {e}"), None))
+    }
+
+    #[tool(
+        name = "findSimilarCode",
+        description = "Find chunks similar to a chunk id or file + line range"
+    )]
+    async fn find_similar_code(
+        &self,
+        Parameters(args): Parameters<SimilarArgs>,
+    ) -> Result<String, McpError> {
+        let scope = args
+            .repo_filter
+            .clone()
+            .or_else(|| common::projects::read_selected_project(&self.cwd))
+            .ok_or_else(|| McpError::invalid_params("project scope required".to_string(), None))?;
+        let params = FindSimilarParams {
+            chunk_id: args.chunk_id,
+            path: args.path,
+            line_start: args.line_start,
+            line_end: args.line_end,
+            top_k: args.top_k.unwrap_or(5).max(1),
+            repo_filter: args.repo_filter,
+            more_like_this: args.more_like_this.unwrap_or(true),
+            explain: false,
+            deadline_ms: args.deadline_ms,
+        };
+        let deadline =
+            mcp_server::services::search::SearchDeadline::from_request(params.deadline_ms);
+        let mut result = mcp_server::services::similar::find_similar(
+            &self.cwd,
+            &scope,
+            &self.embedder,
+            &params,
+            &deadline,
+        )
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?
+        .ok_or_else(|| {
+            McpError::invalid_params(
+                "source location does not match an indexed chunk or readable file".to_string(),
+                None,
+            )
+        })?
+        .result;
+        for item in &mut result.items {
+            item.explain = None;
+        }
         serde_json::to_string(&result)
             .map_err(|e| McpError::internal_error(format!("serialize result failed: {e}"), None))
     }
@@ -128,7 +244,7 @@ impl ServerHandler for CodivexRmcpServer {
         ServerInfo {
             capabilities: ServerCapabilities::builder().enable_tools().build(),
             instructions: Some(
-                "Codivex RMCP stdio adapter exposing searchCode and openLocation tools".to_string(),
+                "Codivex RMCP stdio adapter exposing searchCode, findSimilarCode and openLocation tools".to_string(),
             ),
             ..Default::default()
         }
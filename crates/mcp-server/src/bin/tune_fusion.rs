@@ -0,0 +1,245 @@
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::Context;
+use common::projects::{self, RetrievalProfile};
+use embeddings::{EmbeddingConfig, EmbeddingEngine};
+use mcp_server::{
+    quality::{load_dataset, redact_path, resolve_project_path},
+    services::search::{RankedCandidates, ranked_candidates},
+};
+use search_core::FusionWeights;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+struct TuningReport {
+    dataset_version: String,
+    project_path: String,
+    strategy: String,
+    trials: usize,
+    train_queries: usize,
+    holdout_queries: usize,
+    baseline: TrialScore,
+    best: TrialScore,
+    profile_written: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TrialScore {
+    rrf_k: usize,
+    lexical_weight: f32,
+    vector_weight: f32,
+    exact_symbol_weight: f32,
+    train: SplitScore,
+    holdout: SplitScore,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct SplitScore {
+    mrr_at_10: f64,
+    hits_at_1: usize,
+}
+
+struct LabeledCandidates {
+    candidates: RankedCandidates,
+    expected_file_substring: String,
+}
+
+const K_GRID: [usize; 6] = [5, 10, 20, 40, 60, 100];
+const LEXICAL_GRID: [f32; 7] = [0.2, 0.4, 0.7, 1.0, 1.3, 1.6, 2.0];
+const VECTOR_GRID: [f32; 8] = [0.0, 0.2, 0.4, 0.7, 1.0, 1.3, 1.6, 2.0];
+const EXACT_GRID: [f32; 5] = [0.0, 0.5, 1.0, 2.0, 4.0];
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let dataset_path = std::env::var("QUALITY_DATASET")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("benchmarks/quality-dataset-v1.json"));
+    let output_path = std::env::var("TUNING_REPORT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("benchmarks/fusion-tuning-report.json"));
+    let strategy = std::env::var("TUNING_STRATEGY").unwrap_or_else(|_| "grid".to_string());
+
+    let holdout_every = std::env::var("TUNING_HOLDOUT_EVERY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(3)
+        .max(2);
+
+    let dataset = load_dataset(&dataset_path)?;
+    anyhow::ensure!(
+        dataset.queries.len() >= 2,
+        "tuning needs at least two queries so one can be held out"
+    );
+    let cwd = std::env::current_dir()?;
+    let project_path = resolve_project_path(&cwd, &dataset.project_path);
+    let engine = Arc::new(EmbeddingEngine::new(EmbeddingConfig::default()));
+
+    // Every `holdout_every`-th query is kept out of the search and only used to judge the
+    // winner, so the written profile has to generalise beyond the queries it was fitted on.
+    let mut train = Vec::new();
+    let mut holdout = Vec::new();
+    for (idx, q) in dataset.queries.iter().enumerate() {
+        let candidates = ranked_candidates(&cwd, &project_path, &engine, &q.query, 10)
+            .await
+            .with_context(|| format!("failed collecting candidates for {:?}", q.query))?;
+        let labeled = LabeledCandidates {
+            candidates,
+            expected_file_substring: q.expected_file_substring.clone(),
+        };
+        if idx % holdout_every == holdout_every - 1 {
+            holdout.push(labeled);
+        } else {
+            train.push(labeled);
+        }
+    }
+    if holdout.is_empty()
+        && let Some(last) = train.pop()
+    {
+        holdout.push(last);
+    }
+
+    let trials = if strategy.eq_ignore_ascii_case("random") {
+        let samples = std::env::var("TUNING_SAMPLES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(200);
+        let seed = std::env::var("TUNING_SEED")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(42);
+        random_trials(samples, seed)
+    } else {
+        grid_trials()
+    };
+
+    let baseline_weights = FusionWeights::default();
+    let mut best_weights = baseline_weights;
+    let mut best_train = score(&train, &baseline_weights);
+    for weights in &trials {
+        let trial = score(&train, weights);
+        if (trial.mrr_at_10, trial.hits_at_1) > (best_train.mrr_at_10, best_train.hits_at_1) {
+            best_train = trial;
+            best_weights = *weights;
+        }
+    }
+    let baseline = trial_score(&train, &holdout, &baseline_weights);
+    let best = trial_score(&train, &holdout, &best_weights);
+
+    let profile_written = best.holdout.mrr_at_10 > baseline.holdout.mrr_at_10;
+    if profile_written {
+        projects::save_retrieval_profile(
+            &cwd,
+            &project_path,
+            &RetrievalProfile {
+                rrf_k: best.rrf_k,
+                lexical_weight: best.lexical_weight,
+                vector_weight: best.vector_weight,
+                exact_symbol_weight: best.exact_symbol_weight,
+                dataset_version: dataset.version.clone(),
+                mrr_at_10: best.holdout.mrr_at_10,
+                baseline_mrr_at_10: baseline.holdout.mrr_at_10,
+                tuned_at_unix: unix_now(),
+            },
+        )?;
+    }
+
+    let report = TuningReport {
+        dataset_version: dataset.version,
+        project_path: redact_path(&project_path),
+        strategy,
+        trials: trials.len(),
+        train_queries: train.len(),
+        holdout_queries: holdout.len(),
+        baseline,
+        best,
+        profile_written,
+    };
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&output_path, serde_json::to_string_pretty(&report)?)?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+fn trial_score(
+    train: &[LabeledCandidates],
+    holdout: &[LabeledCandidates],
+    weights: &FusionWeights,
+) -> TrialScore {
+    TrialScore {
+        rrf_k: weights.k,
+        lexical_weight: weights.lexical,
+        vector_weight: weights.vector,
+        exact_symbol_weight: weights.exact_symbol,
+        train: score(train, weights),
+        holdout: score(holdout, weights),
+    }
+}
+
+fn score(labeled: &[LabeledCandidates], weights: &FusionWeights) -> SplitScore {
+    let mut reciprocal_rank_sum = 0.0;
+    let mut hits_at_1 = 0usize;
+    for entry in labeled {
+        let rank = entry
+            .candidates
+            .ranked_files(weights, 10)
+            .iter()
+            .position(|file| file.contains(&entry.expected_file_substring));
+        if let Some(idx) = rank {
+            reciprocal_rank_sum += 1.0 / ((idx + 1) as f64);
+            if idx == 0 {
+                hits_at_1 += 1;
+            }
+        }
+    }
+    SplitScore {
+        mrr_at_10: reciprocal_rank_sum / (labeled.len().max(1) as f64),
+        hits_at_1,
+    }
+}
+
+fn grid_trials() -> Vec<FusionWeights> {
+    let mut out = Vec::new();
+    for k in K_GRID {
+        for lexical in LEXICAL_GRID {
+            for vector in VECTOR_GRID {
+                for exact_symbol in EXACT_GRID {
+                    out.push(FusionWeights {
+                        k,
+                        lexical,
+                        vector,
+                        exact_symbol,
+                    });
+                }
+            }
+        }
+    }
+    out
+}
+
+fn random_trials(samples: usize, seed: u64) -> Vec<FusionWeights> {
+    let mut state = seed.max(1);
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+    (0..samples)
+        .map(|_| FusionWeights {
+            k: 1 + (next() * 120.0) as usize,
+            lexical: (next() * 2.0) as f32,
+            vector: (next() * 2.0) as f32,
+            exact_symbol: (next() * 4.0) as f32,
+        })
+        .collect()
+}
+
+fn unix_now() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -1,3 +1,18 @@
+use axum::{Json, extract::State, http::StatusCode};
+use embeddings::EmbeddingReadiness;
+
+use crate::state::AppState;
+
 pub async fn health() -> &'static str {
     "ok"
 }
+
+pub async fn readiness(State(state): State<AppState>) -> (StatusCode, Json<EmbeddingReadiness>) {
+    let readiness = state.embedder.readiness();
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
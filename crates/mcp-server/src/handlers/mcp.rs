@@ -5,8 +5,8 @@ use axum::{
     response::IntoResponse,
 };
 use common::{
-    OpenLocationParams, OpenLocationResult, RpcErrorCode, RpcRequest, RpcResponse,
-    SearchCodeParams, SearchCodeResult, schema_bundle,
+    FindSimilarParams, FindSimilarResult, OpenLocationParams, OpenLocationResult, RpcErrorCode,
+    RpcRequest, RpcResponse, SearchCodeParams, SearchCodeResult, schema_bundle,
 };
 use sha2::{Digest, Sha256};
 use std::time::Instant;
@@ -16,10 +16,19 @@ use crate::{
     handlers::auth::is_authorized,
     handlers::mcp_protocol::{
         ToolCallParams, ToolCallResult, ToolContent, initialize_result, parse_open_arguments,
-        parse_search_arguments, prompts_list_result, resources_list_result, tools_list_result,
+        parse_search_arguments, parse_similar_arguments, prompts_list_result,
+        resources_list_result, tools_list_result,
     },
     json_rpc::json_from_response,
-    services::search::{cache_key, cache_lookup, cache_store, scoped_project_results},
+    services::{
+        feedback,
+        packing::pack_results,
+        search::{
+            Degraded, SearchDeadline, SearchOptions, cache_key, cache_lookup, cache_store,
+            group_results_by_file, scoped_project_search,
+        },
+        similar::{SimilarSearch, find_similar},
+    },
     state::AppState,
 };
 
@@ -36,6 +45,7 @@ pub async fn mcp_handler(
     let project_scope = scoped_project_from_headers(&headers)
         .or_else(|| common::projects::read_selected_project(&state.cwd).filter(|p| !p.is_empty()))
         .map(|scope| resolve_project_scope(&state.cwd, &scope));
+    let session = session_from_headers(&headers);
 
     match req.method.as_str() {
         "ping" => {
@@ -45,15 +55,18 @@ pub async fn mcp_handler(
         "tools/list" => handle_tools_list(req).into_response(),
         "resources/list" => handle_resources_list(req).into_response(),
         "prompts/list" => handle_prompts_list(req).into_response(),
-        "tools/call" => handle_tools_call(&state, req, project_scope.as_deref())
+        "tools/call" => handle_tools_call(&state, req, project_scope.as_deref(), &session)
             .await
             .into_response(),
-        "searchCode" => handle_search_code(&state, req, project_scope.as_deref())
+        "searchCode" => handle_search_code(&state, req, project_scope.as_deref(), &session)
             .await
             .into_response(),
         "openLocation" => {
-            handle_open_location(&state, req, project_scope.as_deref()).into_response()
+            handle_open_location(&state, req, project_scope.as_deref(), &session).into_response()
         }
+        "findSimilarCode" => handle_find_similar(&state, req, project_scope.as_deref())
+            .await
+            .into_response(),
         _ => {
             warn!("unknown method");
             json_from_response(RpcResponse::<serde_json::Value>::err(
@@ -107,6 +120,7 @@ async fn handle_tools_call(
     state: &AppState,
     req: RpcRequest,
     project_scope: Option<&str>,
+    session: &str,
 ) -> Json<serde_json::Value> {
     let params = match serde_json::from_value::<ToolCallParams>(req.params) {
         Ok(p) => p,
@@ -123,7 +137,7 @@ async fn handle_tools_call(
         "searchCode" | "search_code" => match parse_search_arguments(params.arguments) {
             Ok(search_params) => {
                 let started = Instant::now();
-                match execute_search(state, search_params, project_scope).await {
+                match execute_search(state, search_params, project_scope, session).await {
                     Ok(result) => {
                         state
                             .record_search_latency_ms(started.elapsed().as_millis())
@@ -163,42 +177,84 @@ async fn handle_tools_call(
             )),
         },
         "openLocation" | "open_location" => match parse_open_arguments(params.arguments) {
-            Ok(open_params) => match execute_open_location(state, open_params, project_scope) {
-                Ok(result) => {
-                    let structured = serde_json::to_value(&result).ok();
-                    let text = serde_json::to_string(&result).unwrap_or_else(|_| {
-                        "{\"path\":\"\",\"line_start\":0,\"line_end\":0}".to_string()
-                    });
-                    json_from_response(RpcResponse::ok(
+            Ok(open_params) => {
+                match execute_open_location(state, open_params, project_scope, session) {
+                    Ok(result) => {
+                        let structured = serde_json::to_value(&result).ok();
+                        let text = serde_json::to_string(&result).unwrap_or_else(|_| {
+                            "{\"path\":\"\",\"line_start\":0,\"line_end\":0}".to_string()
+                        });
+                        json_from_response(RpcResponse::ok(
+                            req.id,
+                            ToolCallResult {
+                                content: vec![ToolContent {
+                                    kind: "text".to_string(),
+                                    text,
+                                }],
+                                structured_content: structured,
+                                is_error: false,
+                            },
+                        ))
+                    }
+                    Err(err) => json_from_response(RpcResponse::ok(
                         req.id,
                         ToolCallResult {
                             content: vec![ToolContent {
                                 kind: "text".to_string(),
-                                text,
+                                text: err.message,
                             }],
-                            structured_content: structured,
-                            is_error: false,
+                            structured_content: None,
+                            is_error: true,
                         },
-                    ))
+                    )),
                 }
-                Err(err) => json_from_response(RpcResponse::ok(
-                    req.id,
-                    ToolCallResult {
-                        content: vec![ToolContent {
-                            kind: "text".to_string(),
-                            text: err.message,
-                        }],
-                        structured_content: None,
-                        is_error: true,
-                    },
-                )),
-            },
+            }
             Err(err) => json_from_response(RpcResponse::<serde_json::Value>::err(
                 req.id,
                 RpcErrorCode::InvalidParams.as_i64(),
                 err,
             )),
         },
+        "findSimilarCode" | "find_similar_code" => {
+            match parse_similar_arguments(params.arguments) {
+                Ok(similar_params) => {
+                    match execute_find_similar(state, similar_params, project_scope).await {
+                        Ok(result) => {
+                            let structured = serde_json::to_value(&result).ok();
+                            let text = serde_json::to_string(&result)
+                                .unwrap_or_else(|_| "{\"items\":[]}".to_string());
+                            json_from_response(RpcResponse::ok(
+                                req.id,
+                                ToolCallResult {
+                                    content: vec![ToolContent {
+                                        kind: "text".to_string(),
+                                        text,
+                                    }],
+                                    structured_content: structured,
+                                    is_error: false,
+                                },
+                            ))
+                        }
+                        Err(err) => json_from_response(RpcResponse::ok(
+                            req.id,
+                            ToolCallResult {
+                                content: vec![ToolContent {
+                                    kind: "text".to_string(),
+                                    text: err.message,
+                                }],
+                                structured_content: None,
+                                is_error: true,
+                            },
+                        )),
+                    }
+                }
+                Err(err) => json_from_response(RpcResponse::<serde_json::Value>::err(
+                    req.id,
+                    RpcErrorCode::InvalidParams.as_i64(),
+                    err,
+                )),
+            }
+        }
         _ => json_from_response(RpcResponse::<serde_json::Value>::err(
             req.id,
             RpcErrorCode::InvalidParams.as_i64(),
@@ -211,6 +267,7 @@ async fn handle_search_code(
     state: &AppState,
     req: RpcRequest,
     project_scope: Option<&str>,
+    session: &str,
 ) -> Json<serde_json::Value> {
     let started = Instant::now();
     if let Err(err) = validate_search_params(&req.params) {
@@ -224,7 +281,7 @@ async fn handle_search_code(
         Ok(params) => {
             let query_hash = hash_query(&params.query);
             info!(query_hash = query_hash, top_k = params.top_k, "searchCode");
-            match execute_search(state, params, project_scope).await {
+            match execute_search(state, params, project_scope, session).await {
                 Ok(result) => {
                     state
                         .record_search_latency_ms(started.elapsed().as_millis())
@@ -246,10 +303,40 @@ async fn handle_search_code(
     }
 }
 
+async fn handle_find_similar(
+    state: &AppState,
+    req: RpcRequest,
+    project_scope: Option<&str>,
+) -> Json<serde_json::Value> {
+    if let Err(err) = validate_find_similar_params(&req.params) {
+        return json_from_response(RpcResponse::<FindSimilarResult>::err(
+            req.id,
+            RpcErrorCode::InvalidParams.as_i64(),
+            err,
+        ));
+    }
+    match serde_json::from_value::<FindSimilarParams>(req.params) {
+        Ok(params) => match execute_find_similar(state, params, project_scope).await {
+            Ok(result) => json_from_response(RpcResponse::ok(req.id, result)),
+            Err(err) => json_from_response(RpcResponse::<FindSimilarResult>::err(
+                req.id,
+                err.code,
+                err.message,
+            )),
+        },
+        Err(err) => json_from_response(RpcResponse::<FindSimilarResult>::err(
+            req.id,
+            RpcErrorCode::InvalidParams.as_i64(),
+            format!("invalid params: {err}"),
+        )),
+    }
+}
+
 fn handle_open_location(
     state: &AppState,
     req: RpcRequest,
     project_scope: Option<&str>,
+    session: &str,
 ) -> Json<serde_json::Value> {
     if let Err(err) = validate_open_location_params(&req.params) {
         return json_from_response(RpcResponse::<OpenLocationResult>::err(
@@ -259,7 +346,7 @@ fn handle_open_location(
         ));
     }
     match serde_json::from_value::<OpenLocationParams>(req.params) {
-        Ok(params) => match execute_open_location(state, params, project_scope) {
+        Ok(params) => match execute_open_location(state, params, project_scope, session) {
             Ok(result) => json_from_response(RpcResponse::ok(req.id, result)),
             Err(err) => json_from_response(RpcResponse::<OpenLocationResult>::err(
                 req.id,
@@ -285,6 +372,7 @@ async fn execute_search(
     state: &AppState,
     params: SearchCodeParams,
     project_scope: Option<&str>,
+    session: &str,
 ) -> Result<SearchCodeResult, MethodError> {
     if params.query.trim().is_empty() {
         return Err(MethodError {
@@ -292,64 +380,182 @@ async fn execute_search(
             message: "query cannot be empty".to_string(),
         });
     }
-    if params.query == "__index_unavailable__" {
-        return Err(MethodError {
-            code: RpcErrorCode::IndexUnavailable.as_i64(),
-            message: "index unavailable".to_string(),
-        });
-    }
-    if params.query == "__timeout__" {
-        return Err(MethodError {
-            code: RpcErrorCode::Timeout.as_i64(),
-            message: "query timed out".to_string(),
-        });
-    }
 
-    let scoped_from_request = params
-        .repo_filter
-        .as_deref()
-        .filter(|v| !v.trim().is_empty())
-        .map(|scope| resolve_project_scope(&state.cwd, scope));
-    let effective_scope = scoped_from_request.or_else(|| project_scope.map(str::to_string));
-    let Some(scope) = effective_scope else {
-        return Err(MethodError {
-            code: RpcErrorCode::InvalidParams.as_i64(),
-            message: "project scope required: set repoFilter or x-codivex-project header or select project in admin UI".to_string(),
-        });
-    };
+    let scope = request_scope(state, params.repo_filter.as_deref(), project_scope)?;
 
-    let key = cache_key(&scope, &params.query, params.top_k);
+    let deadline = SearchDeadline::from_request(params.deadline_ms);
+    let options = SearchOptions::from(&params);
+    let generation = common::projects::index_generation(&state.cwd, &scope);
+    let feedback_key = feedback::query_feedback_key(&state.cwd, &scope, &params.query);
+    let key = cache_key(
+        &scope,
+        generation,
+        feedback_key,
+        &params.query,
+        params.top_k,
+        &options,
+    );
     if let Some(cached) = cache_lookup(&state.query_cache, &key).await {
         metrics::counter!("mcp_query_cache_hits_total").increment(1);
-        return Ok(cached);
+        return Ok(finalize_for_request(
+            state, cached, &params, &scope, session,
+        ));
     }
 
     metrics::counter!("mcp_query_cache_misses_total").increment(1);
-    let items = scoped_project_results(&state.cwd, &scope, &params.query, params.top_k)
-        .await
-        .unwrap_or_default();
-    let result = SearchCodeResult { items };
+    let search = scoped_project_search(
+        &state.cwd,
+        &scope,
+        &state.embedder,
+        &params.query,
+        params.top_k,
+        &options,
+        &deadline,
+    )
+    .await
+    .map_err(|err| MethodError {
+        code: RpcErrorCode::IndexUnavailable.as_i64(),
+        message: err.to_string(),
+    })?;
+    let result = SearchCodeResult {
+        items: search.items,
+        budget: None,
+        groups: Vec::new(),
+        degraded: search.degraded.is_some(),
+    };
+    if result.items.is_empty() && search.degraded == Some(Degraded::Deadline) {
+        metrics::counter!("mcp_search_timeouts_total").increment(1);
+        return Err(deadline_exceeded());
+    }
     if result.items.is_empty() {
         return Err(MethodError {
             code: RpcErrorCode::IndexUnavailable.as_i64(),
             message: "project has no indexed data or no matches".to_string(),
         });
     }
-    cache_store(
-        &state.query_cache,
-        key,
-        SearchCodeResult {
-            items: result.items.clone(),
-        },
-    )
-    .await;
+    if result.degraded {
+        metrics::counter!("mcp_search_degraded_total").increment(1);
+    } else if common::projects::index_generation(&state.cwd, &scope) == generation {
+        cache_store(
+            &state.query_cache,
+            key,
+            SearchCodeResult {
+                items: result.items.clone(),
+                budget: None,
+                groups: Vec::new(),
+                degraded: false,
+            },
+        )
+        .await;
+    }
+    Ok(finalize_for_request(
+        state, result, &params, &scope, session,
+    ))
+}
+
+fn deadline_exceeded() -> MethodError {
+    MethodError {
+        code: RpcErrorCode::Timeout.as_i64(),
+        message: "search deadline exceeded before any results were available".to_string(),
+    }
+}
+
+fn request_scope(
+    state: &AppState,
+    repo_filter: Option<&str>,
+    project_scope: Option<&str>,
+) -> Result<String, MethodError> {
+    repo_filter
+        .filter(|v| !v.trim().is_empty())
+        .map(|scope| resolve_project_scope(&state.cwd, scope))
+        .or_else(|| project_scope.map(str::to_string))
+        .ok_or_else(|| MethodError {
+            code: RpcErrorCode::InvalidParams.as_i64(),
+            message: "project scope required: set repoFilter or x-codivex-project header or select project in admin UI".to_string(),
+        })
+}
+
+async fn execute_find_similar(
+    state: &AppState,
+    params: FindSimilarParams,
+    project_scope: Option<&str>,
+) -> Result<FindSimilarResult, MethodError> {
+    let has_chunk_id = params.chunk_id.as_deref().is_some_and(|id| !id.is_empty());
+    let has_path = params.path.as_deref().is_some_and(|p| !p.is_empty());
+    if !has_chunk_id && !has_path {
+        return Err(MethodError {
+            code: RpcErrorCode::InvalidParams.as_i64(),
+            message: "chunkId or path (with lineStart/lineEnd) is required".to_string(),
+        });
+    }
+    let scope = request_scope(state, params.repo_filter.as_deref(), project_scope)?;
+    let deadline = SearchDeadline::from_request(params.deadline_ms);
+
+    let SimilarSearch {
+        mut result,
+        degraded,
+    } = match find_similar(&state.cwd, &scope, &state.embedder, &params, &deadline).await {
+        Ok(Some(search)) => search,
+        Ok(None) => {
+            return Err(MethodError {
+                code: RpcErrorCode::InvalidParams.as_i64(),
+                message: "source location does not match an indexed chunk or readable file"
+                    .to_string(),
+            });
+        }
+        Err(err) => {
+            return Err(MethodError {
+                code: RpcErrorCode::IndexUnavailable.as_i64(),
+                message: err.to_string(),
+            });
+        }
+    };
+    if result.items.is_empty() && degraded == Some(Degraded::Deadline) {
+        metrics::counter!("mcp_search_timeouts_total").increment(1);
+        return Err(deadline_exceeded());
+    }
+    if !params.explain {
+        for item in &mut result.items {
+            item.explain = None;
+        }
+    }
     Ok(result)
 }
 
+fn finalize_for_request(
+    state: &AppState,
+    result: SearchCodeResult,
+    params: &SearchCodeParams,
+    scope: &str,
+    session: &str,
+) -> SearchCodeResult {
+    state.search_sessions.remember(
+        session,
+        scope,
+        &params.query,
+        &result.items,
+        feedback::unix_now_ms(),
+    );
+    let mut result = match params.max_tokens {
+        Some(max_tokens) => pack_results(result, max_tokens, &state.token_counter),
+        None => result,
+    };
+    if !params.explain {
+        for item in &mut result.items {
+            item.explain = None;
+        }
+    }
+    if params.group_by_file {
+        result = group_results_by_file(result);
+    }
+    result
+}
+
 fn execute_open_location(
     state: &AppState,
     params: OpenLocationParams,
     project_scope: Option<&str>,
+    session: &str,
 ) -> Result<OpenLocationResult, MethodError> {
     let resolved_path = resolve_source_path(&state.cwd, project_scope, &params.path);
     let content = std::fs::read_to_string(&resolved_path).map_err(|_| MethodError {
@@ -371,6 +577,14 @@ fn execute_open_location(
         });
     }
 
+    feedback::record_open(
+        &state.cwd,
+        &state.search_sessions,
+        session,
+        &resolved_path,
+        params.line_start,
+        params.line_end,
+    );
     Ok(OpenLocationResult {
         path: resolved_path.display().to_string(),
         line_start: params.line_start,
@@ -391,6 +605,13 @@ fn validate_search_params(params: &serde_json::Value) -> Result<(), String> {
     jsonschema::validate(&schema, params).map_err(|e| format!("schema validation failed: {e}"))
 }
 
+fn validate_find_similar_params(params: &serde_json::Value) -> Result<(), String> {
+    let bundle = schema_bundle();
+    let schema = serde_json::to_value(bundle.find_similar_params)
+        .map_err(|e| format!("schema serialization error: {e}"))?;
+    jsonschema::validate(&schema, params).map_err(|e| format!("schema validation failed: {e}"))
+}
+
 fn validate_open_location_params(params: &serde_json::Value) -> Result<(), String> {
     let bundle = schema_bundle();
     let schema = serde_json::to_value(bundle.open_location_params)
@@ -398,6 +619,17 @@ fn validate_open_location_params(params: &serde_json::Value) -> Result<(), Strin
     jsonschema::validate(&schema, params).map_err(|e| format!("schema validation failed: {e}"))
 }
 
+fn session_from_headers(headers: &HeaderMap) -> String {
+    headers
+        .get("mcp-session-id")
+        .or_else(|| headers.get("x-codivex-session"))
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or("default")
+        .to_string()
+}
+
 fn scoped_project_from_headers(headers: &HeaderMap) -> Option<String> {
     headers
         .get("x-codivex-project")
@@ -1,4 +1,4 @@
-use common::{OpenLocationParams, SearchCodeParams, schema_bundle};
+use common::{FindSimilarParams, OpenLocationParams, SearchCodeParams, schema_bundle};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -127,6 +127,8 @@ pub fn tools_list_result() -> anyhow::Result<ToolsListResult> {
     let schemas = schema_bundle();
     let search_schema = serde_json::to_value(schemas.search_code_params)?;
     let open_schema = serde_json::to_value(schemas.open_location_params)?;
+    let similar_schema = serde_json::to_value(schemas.find_similar_params)?;
+    let similar_output_schema = serde_json::to_value(schemas.find_similar_result)?;
     let search_output_schema = serde_json::json!({
         "type": "object",
         "properties": {
@@ -139,11 +141,89 @@ pub fn tools_list_result() -> anyhow::Result<ToolsListResult> {
                         "function": { "type": "string" },
                         "start_line": { "type": "integer", "minimum": 1 },
                         "end_line": { "type": "integer", "minimum": 1 },
-                        "code_block": { "type": "string" }
+                        "code_block": { "type": "string" },
+                        "highlights": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "start_line": { "type": "integer", "minimum": 1 },
+                                    "end_line": { "type": "integer", "minimum": 1 },
+                                    "terms": { "type": "array", "items": { "type": "string" } }
+                                },
+                                "required": ["start_line", "end_line"]
+                            }
+                        },
+                        "token_count": { "type": "integer", "minimum": 0 },
+                        "detail": { "type": "string", "enum": ["full", "matched", "signature", "truncated"] },
+                        "pinned": { "type": "boolean" },
+                        "explain": {
+                            "type": "object",
+                            "properties": {
+                                "chunk_id": { "type": "string" },
+                                "fused_score": { "type": "number" },
+                                "lexical_rank": { "type": ["integer", "null"], "minimum": 1 },
+                                "semantic_rank": { "type": ["integer", "null"], "minimum": 1 },
+                                "exact_symbol": { "type": "boolean" },
+                                "feedback_boost": { "type": "number" },
+                                "diversity_penalty": { "type": "number" },
+                                "priors": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "properties": {
+                                            "name": { "type": "string" },
+                                            "delta": { "type": "number" }
+                                        },
+                                        "required": ["name", "delta"]
+                                    }
+                                }
+                            },
+                            "required": ["chunk_id", "fused_score", "exact_symbol", "feedback_boost"]
+                        }
                     },
                     "required": ["file", "function", "start_line", "end_line", "code_block"]
                 }
-            }
+            },
+            "budget": {
+                "type": "object",
+                "properties": {
+                    "max_tokens": { "type": "integer", "minimum": 0 },
+                    "used_tokens": { "type": "integer", "minimum": 0 },
+                    "elided_tokens": { "type": "integer", "minimum": 0 },
+                    "elided_items": { "type": "integer", "minimum": 0 },
+                    "estimator": { "type": "string" },
+                    "over_budget": {
+                        "type": "boolean",
+                        "description": "Set when even the top result's location header does not fit maxTokens."
+                    }
+                },
+                "required": ["max_tokens", "used_tokens", "elided_tokens", "elided_items", "estimator"]
+            },
+            "groups": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "file": { "type": "string" },
+                        "ranges": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "function": { "type": "string" },
+                                    "start_line": { "type": "integer", "minimum": 1 },
+                                    "end_line": { "type": "integer", "minimum": 1 },
+                                    "code_block": { "type": "string" }
+                                },
+                                "required": ["function", "start_line", "end_line", "code_block"]
+                            }
+                        }
+                    },
+                    "required": ["file", "ranges"]
+                }
+            },
+            "degraded": { "type": "boolean" }
         },
         "required": ["items"]
     });
@@ -161,7 +241,7 @@ pub fn tools_list_result() -> anyhow::Result<ToolsListResult> {
             ToolDescriptor {
                 name: "searchCode".to_string(),
                 title: "Search Code".to_string(),
-                description: "Search indexed code in exactly one project and return ranked chunks (file + line range + snippet). Prefer exact symbols first; pass repoFilter for project scope when multiple repos are indexed.".to_string(),
+                description: "Search indexed code in exactly one project and return ranked chunks (file + line range + snippet). Prefer exact symbols first; pass repoFilter for project scope when multiple repos are indexed; pass maxTokens to pack results into a context budget; pass explain=true for per-result ranking signals; pass groupByFile=true for one entry per file with its matching line ranges; pass preferTests=true when looking for tests (test files are demoted by default); pass contextPath (and contextSymbol) for the file being edited to favour nearby code in the same package; pass deadlineMs to bound latency (degraded=true marks partial, e.g. lexical-only, results).".to_string(),
                 input_schema: search_schema,
                 output_schema: Some(search_output_schema),
                 annotations: Some(ToolAnnotations {
@@ -185,6 +265,20 @@ pub fn tools_list_result() -> anyhow::Result<ToolsListResult> {
                     open_world_hint: Some(false),
                 }),
             },
+            ToolDescriptor {
+                name: "findSimilarCode".to_string(),
+                title: "Find Similar Code".to_string(),
+                description: "Find other chunks in the project that do what a given chunk does (duplicated logic, sibling implementations). Pass chunkId from searchCode explain output, or path with lineStart/lineEnd; the source chunk itself is excluded. Vector similarity is combined with a lexical more-like-this query unless moreLikeThis=false."
+                    .to_string(),
+                input_schema: similar_schema,
+                output_schema: Some(similar_output_schema),
+                annotations: Some(ToolAnnotations {
+                    read_only_hint: Some(true),
+                    destructive_hint: Some(false),
+                    idempotent_hint: Some(true),
+                    open_world_hint: Some(false),
+                }),
+            },
         ],
     })
 }
@@ -205,6 +299,10 @@ pub fn parse_search_arguments(value: Value) -> Result<SearchCodeParams, String>
     serde_json::from_value::<SearchCodeParams>(value).map_err(|e| format!("invalid args: {e}"))
 }
 
+pub fn parse_similar_arguments(value: Value) -> Result<FindSimilarParams, String> {
+    serde_json::from_value::<FindSimilarParams>(value).map_err(|e| format!("invalid args: {e}"))
+}
+
 pub fn parse_open_arguments(value: Value) -> Result<OpenLocationParams, String> {
     serde_json::from_value::<OpenLocationParams>(value).map_err(|e| format!("invalid args: {e}"))
 }
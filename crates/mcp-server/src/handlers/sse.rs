@@ -73,9 +73,12 @@ pub async fn sse_handler(
             .into_response();
     };
 
-    let items = scoped_project_results(&state.cwd, &scope, &query, top_k)
+    let mut items = scoped_project_results(&state.cwd, &scope, &state.embedder, &query, top_k)
         .await
         .unwrap_or_default();
+    for item in &mut items {
+        item.explain = None;
+    }
     state
         .record_search_latency_ms(started.elapsed().as_millis())
         .await;
@@ -2,5 +2,6 @@ pub mod app;
 pub mod handlers;
 pub mod json_rpc;
 pub mod middleware;
+pub mod quality;
 pub mod services;
 pub mod state;
@@ -24,6 +24,7 @@ async fn main() -> anyhow::Result<()> {
 
     let addr = bind_addr_from_env(runtime_ports.mcp_port)?;
     let state = AppState::from_env(runtime_ports.clone(), port_conflicts_resolved)?;
+    state.spawn_embedder_warm_up();
     spawn_background_indexing(state.clone());
     info!("mcp-server listening on http://{addr}");
     info!("MCP JSON-RPC endpoint: http://{addr}/mcp");
@@ -40,6 +41,7 @@ async fn main() -> anyhow::Result<()> {
         .with_graceful_shutdown(async move {
             shutdown_signal().await;
             shutdown_state.begin_shutdown();
+            shutdown_state.embedder.flush_cache();
             if let Err(err) = shutdown_state.persist_runtime_state().await {
                 tracing::warn!(error = %err, "failed persisting runtime state during shutdown");
             }
@@ -14,10 +14,10 @@ const X_CORRELATION_ID: &str = "x-correlation-id";
 pub async fn trace_with_correlation(mut req: Request<Body>, next: Next) -> Response {
     let started = Instant::now();
     let correlation = Uuid::new_v4().to_string();
-    if let Ok(name) = HeaderName::from_lowercase(X_CORRELATION_ID.as_bytes()) {
-        if let Ok(value) = correlation.parse() {
-            req.headers_mut().insert(name.clone(), value);
-        }
+    if let Ok(name) = HeaderName::from_lowercase(X_CORRELATION_ID.as_bytes())
+        && let Ok(value) = correlation.parse()
+    {
+        req.headers_mut().insert(name.clone(), value);
     }
 
     let method = req.method().clone();
@@ -25,10 +25,10 @@ pub async fn trace_with_correlation(mut req: Request<Body>, next: Next) -> Respo
     let mut res = next.run(req).await;
     let elapsed_ms = started.elapsed().as_millis();
 
-    if let Ok(name) = HeaderName::from_lowercase(X_CORRELATION_ID.as_bytes()) {
-        if let Ok(value) = correlation.parse() {
-            res.headers_mut().insert(name, value);
-        }
+    if let Ok(name) = HeaderName::from_lowercase(X_CORRELATION_ID.as_bytes())
+        && let Ok(value) = correlation.parse()
+    {
+        res.headers_mut().insert(name, value);
     }
 
     info!(
@@ -0,0 +1,56 @@
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct QualityDataset {
+    pub version: String,
+    pub project_path: String,
+    pub queries: Vec<QualityQuery>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QualityQuery {
+    pub query: String,
+    pub expected_file_substring: String,
+}
+
+pub fn load_dataset(path: &Path) -> anyhow::Result<QualityDataset> {
+    serde_json::from_str(
+        &std::fs::read_to_string(path)
+            .with_context(|| format!("failed reading {}", path.display()))?,
+    )
+    .with_context(|| format!("failed parsing {}", path.display()))
+}
+
+pub fn resolve_project_path(cwd: &Path, value: &str) -> String {
+    if let Ok(override_path) = std::env::var("QUALITY_PROJECT_PATH")
+        && !override_path.trim().is_empty()
+    {
+        return override_path;
+    }
+    let trimmed = value.trim();
+    if trimmed.is_empty() || trimmed == "." {
+        return cwd.display().to_string();
+    }
+    let p = Path::new(trimmed);
+    if p.is_absolute() {
+        return p.display().to_string();
+    }
+    cwd.join(p).display().to_string()
+}
+
+pub fn redact_path(path: &str) -> String {
+    let redact = std::env::var("QUALITY_REDACT_PATH")
+        .map(|v| !v.eq_ignore_ascii_case("false"))
+        .unwrap_or(true);
+    if !redact {
+        return path.to_string();
+    }
+    Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|name| format!("<redacted:{name}>"))
+        .unwrap_or_else(|| "<redacted>".to_string())
+}
@@ -0,0 +1,502 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use anyhow::anyhow;
+use common::{CodeChunk, projects};
+use embeddings::{
+    DeadLetter, EmbeddingConfig, EmbeddingEngine, EmbeddingJob, EmbeddingResult, EmbeddingSink,
+};
+use search_core::{
+    ChunkVectors, ChunkViews, VectorField,
+    vector::{QdrantVectorStore, VectorSearchConfig},
+};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{
+    services::{indexing::to_vector_quantization_mode, search::qdrant_client_from_env},
+    state::IndexingRuntimeState,
+};
+
+pub struct VectorStoreSink {
+    cwd: PathBuf,
+    engine: Arc<EmbeddingEngine>,
+    runtime: Arc<IndexingRuntimeState>,
+    generation: AtomicU64,
+    pending: Mutex<PendingVectors>,
+}
+
+#[derive(Default)]
+struct PendingVectors {
+    chunks: HashMap<String, PendingChunk>,
+    metadata: HashMap<String, String>,
+    collections: HashSet<String>,
+}
+
+struct PendingChunk {
+    project: String,
+    generation: u64,
+    chunk: CodeChunk,
+    needs_doc: bool,
+    code: Option<Vec<f32>>,
+    doc: Option<Vec<f32>>,
+    signature: Option<Vec<f32>>,
+}
+
+impl PendingChunk {
+    fn vectors(&self) -> Option<ChunkVectors> {
+        if self.needs_doc && self.doc.is_none() {
+            return None;
+        }
+        Some(ChunkVectors {
+            code: self.code.clone()?,
+            doc: self.doc.clone(),
+            signature: self.signature.clone()?,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct DeadLetterRecord<'a> {
+    unix_ms: u64,
+    #[serde(flatten)]
+    letter: &'a DeadLetter,
+}
+
+impl VectorStoreSink {
+    pub fn new(
+        cwd: PathBuf,
+        engine: Arc<EmbeddingEngine>,
+        runtime: Arc<IndexingRuntimeState>,
+    ) -> Self {
+        Self {
+            cwd,
+            engine,
+            runtime,
+            generation: AtomicU64::new(0),
+            pending: Mutex::new(PendingVectors::default()),
+        }
+    }
+
+    pub fn dead_letter_path(&self) -> PathBuf {
+        self.cwd
+            .join(".codivex")
+            .join("embedding-dead-letters.jsonl")
+    }
+
+    pub fn register(
+        &self,
+        project_path: &str,
+        chunks: &[CodeChunk],
+        views: Vec<ChunkViews>,
+    ) -> Vec<EmbeddingJob> {
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed);
+        let mut jobs = Vec::with_capacity(chunks.len() * 3);
+        let mut guard = self.pending.lock().expect("pending vectors");
+        for (chunk, view) in chunks.iter().zip(views) {
+            let mut push = |field: VectorField, text: String| {
+                jobs.push(EmbeddingJob {
+                    chunk_id: job_id(generation, field, &chunk.id),
+                    corpus: project_path.to_string(),
+                    text,
+                });
+            };
+            push(VectorField::Code, view.code);
+            push(VectorField::Signature, view.signature);
+            let needs_doc = view.doc.is_some();
+            if let Some(doc) = view.doc {
+                push(VectorField::Doc, doc);
+            }
+            guard.chunks.insert(
+                chunk.id.clone(),
+                PendingChunk {
+                    project: project_path.to_string(),
+                    generation,
+                    chunk: chunk.clone(),
+                    needs_doc,
+                    code: None,
+                    doc: None,
+                    signature: None,
+                },
+            );
+        }
+        jobs
+    }
+
+    pub fn forget(&self, chunk_ids: &[String]) {
+        let mut guard = self.pending.lock().expect("pending vectors");
+        for id in chunk_ids {
+            guard.chunks.remove(id);
+        }
+    }
+
+    pub fn reembed_pending(&self, project_path: &str) -> bool {
+        self.pending
+            .lock()
+            .expect("pending vectors")
+            .metadata
+            .contains_key(project_path)
+    }
+
+    pub fn record_metadata_when_done(&self, project_path: &str, template_version: &str) {
+        self.pending
+            .lock()
+            .expect("pending vectors")
+            .metadata
+            .insert(project_path.to_string(), template_version.to_string());
+        self.record_finished_projects();
+    }
+
+    fn record_finished_projects(&self) {
+        let finished = {
+            let mut guard = self.pending.lock().expect("pending vectors");
+            let busy = guard
+                .chunks
+                .values()
+                .map(|pending| pending.project.clone())
+                .collect::<HashSet<_>>();
+            let done = guard
+                .metadata
+                .keys()
+                .filter(|project| !busy.contains(*project))
+                .cloned()
+                .collect::<Vec<_>>();
+            done.into_iter()
+                .filter_map(|project| guard.metadata.remove_entry(&project))
+                .collect::<Vec<_>>()
+        };
+        for (project, template_version) in finished {
+            info!(
+                project,
+                template = template_version,
+                model = self.engine.model_id(),
+                "re-embedded project for new embedding template"
+            );
+            if let Err(err) = projects::record_embedding_metadata(
+                &self.cwd,
+                &project,
+                &self.engine.corpus_model_id(&project),
+                &template_version,
+            ) {
+                warn!(project, error = %err, "failed to record embedding metadata");
+            }
+        }
+    }
+
+    fn ready_chunks(
+        &self,
+        results: &[EmbeddingResult],
+    ) -> HashMap<String, Vec<(u64, CodeChunk, ChunkVectors)>> {
+        let mut guard = self.pending.lock().expect("pending vectors");
+        let mut touched = HashSet::new();
+        for result in results {
+            let Some((generation, field, id)) = parse_job_id(&result.chunk_id) else {
+                continue;
+            };
+            let Some(pending) = guard
+                .chunks
+                .get_mut(id)
+                .filter(|pending| pending.generation == generation)
+            else {
+                continue;
+            };
+            let slot = match field {
+                VectorField::Code => &mut pending.code,
+                VectorField::Doc => &mut pending.doc,
+                VectorField::Signature => &mut pending.signature,
+            };
+            *slot = Some(result.vector.clone());
+            touched.insert(id.to_string());
+        }
+
+        let mut ready = HashMap::<String, Vec<_>>::new();
+        for id in touched {
+            if let Some(pending) = guard.chunks.get(&id)
+                && let Some(vectors) = pending.vectors()
+            {
+                ready.entry(pending.project.clone()).or_default().push((
+                    pending.generation,
+                    pending.chunk.clone(),
+                    vectors,
+                ));
+            }
+        }
+        ready
+    }
+
+    async fn upsert(
+        &self,
+        project_path: &str,
+        chunks: &[CodeChunk],
+        vectors: &[ChunkVectors],
+    ) -> anyhow::Result<()> {
+        let client = qdrant_client_from_env().ok_or_else(|| anyhow!("QDRANT_URL is not set"))?;
+        let store = QdrantVectorStore::new(VectorSearchConfig {
+            collection: projects::writable_vector_collection(&self.cwd, project_path),
+            vector_dim: self.engine.vector_dim(),
+            quantization: to_vector_quantization_mode(EmbeddingConfig::default().quantization),
+            model_id: self.engine.model_id().to_string(),
+            ..VectorSearchConfig::default()
+        });
+        let ensured = self
+            .pending
+            .lock()
+            .expect("pending vectors")
+            .collections
+            .contains(store.collection());
+        if !ensured {
+            store.ensure_collection(&client).await?;
+            self.pending
+                .lock()
+                .expect("pending vectors")
+                .collections
+                .insert(store.collection().to_string());
+        }
+        store.upsert_chunks(&client, chunks, vectors).await
+    }
+}
+
+impl EmbeddingSink for VectorStoreSink {
+    async fn deliver(&self, results: &[EmbeddingResult]) -> anyhow::Result<()> {
+        for (project, ready) in self.ready_chunks(results) {
+            let (chunks, vectors) = ready
+                .iter()
+                .map(|(_, chunk, vectors)| (chunk.clone(), vectors.clone()))
+                .unzip::<_, _, Vec<_>, Vec<_>>();
+            self.upsert(&project, &chunks, &vectors).await?;
+            let mut guard = self.pending.lock().expect("pending vectors");
+            for (generation, chunk, _) in ready {
+                if guard
+                    .chunks
+                    .get(&chunk.id)
+                    .is_some_and(|pending| pending.generation == generation)
+                {
+                    guard.chunks.remove(&chunk.id);
+                }
+            }
+        }
+        self.record_finished_projects();
+        Ok(())
+    }
+
+    fn accepts(&self, job: &EmbeddingJob) -> bool {
+        let Some((generation, _, id)) = parse_job_id(&job.chunk_id) else {
+            return false;
+        };
+        self.pending
+            .lock()
+            .expect("pending vectors")
+            .chunks
+            .get(id)
+            .is_some_and(|pending| pending.generation == generation)
+    }
+
+    async fn dead_letter(&self, letters: Vec<DeadLetter>) {
+        let mut failed = HashMap::<String, usize>::new();
+        {
+            let mut guard = self.pending.lock().expect("pending vectors");
+            for letter in &letters {
+                let Some((generation, _, id)) = parse_job_id(&letter.chunk_id) else {
+                    continue;
+                };
+                if guard
+                    .chunks
+                    .get(id)
+                    .is_some_and(|pending| pending.generation == generation)
+                {
+                    guard.chunks.remove(id);
+                    *failed.entry(letter.corpus.clone()).or_default() += 1;
+                }
+            }
+        }
+        metrics::counter!("embedding_dead_letters_total").increment(letters.len() as u64);
+        if let Err(err) = append_dead_letters(&self.dead_letter_path(), &letters) {
+            warn!(error = %err, "failed to record embedding dead letters");
+        }
+        for (project, count) in failed {
+            self.runtime
+                .mark_error(
+                    &project,
+                    format!(
+                        "{count} chunks failed to embed; see {}",
+                        self.dead_letter_path().display()
+                    ),
+                )
+                .await;
+        }
+        self.record_finished_projects();
+    }
+}
+
+fn append_dead_letters(path: &std::path::Path, letters: &[DeadLetter]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let unix_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    for letter in letters {
+        serde_json::to_writer(&mut file, &DeadLetterRecord { unix_ms, letter })?;
+        file.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+fn job_id(generation: u64, field: VectorField, chunk_id: &str) -> String {
+    format!("{generation}#{}#{chunk_id}", field.name())
+}
+
+fn parse_job_id(raw: &str) -> Option<(u64, VectorField, &str)> {
+    let mut parts = raw.splitn(3, '#');
+    let generation = parts.next()?.parse().ok()?;
+    let field = match parts.next()? {
+        "code" => VectorField::Code,
+        "doc" => VectorField::Doc,
+        "signature" => VectorField::Signature,
+        _ => return None,
+    };
+    Some((generation, field, parts.next()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common::CodeChunk;
+    use embeddings::{
+        DeadLetter, EmbeddingConfig, EmbeddingEngine, EmbeddingQueue, EmbeddingResult,
+        EmbeddingWorkerConfig, EmbeddingWorkerMetrics, run_embedding_worker_with_metrics,
+    };
+    use search_core::{ChunkViews, VectorField};
+
+    use super::{VectorStoreSink, parse_job_id};
+    use crate::state::IndexingRuntimeState;
+
+    fn chunk(id: &str) -> CodeChunk {
+        CodeChunk {
+            id: id.to_string(),
+            fingerprint: id.to_string(),
+            file_path: "src/lib.rs".to_string(),
+            language: "rust".to_string(),
+            symbol: Some("parse".to_string()),
+            start_line: 1,
+            end_line: 3,
+            start_char: 0,
+            end_char: 10,
+            content: "fn parse() {}".to_string(),
+        }
+    }
+
+    fn views(doc: Option<&str>) -> ChunkViews {
+        ChunkViews {
+            code: "fn parse() {}".to_string(),
+            doc: doc.map(str::to_string),
+            signature: "parse".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn stale_and_dead_lettered_jobs_do_not_resurrect_chunks() {
+        let cwd = std::env::temp_dir().join(format!("codivex-sink-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&cwd);
+        let runtime = Arc::new(IndexingRuntimeState::default());
+        let sink = VectorStoreSink::new(
+            cwd.clone(),
+            Arc::new(EmbeddingEngine::new(EmbeddingConfig::default())),
+            runtime.clone(),
+        );
+
+        let stale = sink.register("/repo", &[chunk("a:1:3:parse")], vec![views(None)]);
+        let fresh = sink.register("/repo", &[chunk("a:1:3:parse")], vec![views(Some("doc"))]);
+        assert_eq!((stale.len(), fresh.len()), (2, 3));
+        assert_eq!(
+            parse_job_id(&fresh[2].chunk_id),
+            Some((1, VectorField::Doc, "a:1:3:parse"))
+        );
+
+        let stale_results = stale
+            .iter()
+            .map(|job| EmbeddingResult {
+                chunk_id: job.chunk_id.clone(),
+                corpus: job.corpus.clone(),
+                vector: vec![1.0],
+            })
+            .collect::<Vec<_>>();
+        assert!(sink.ready_chunks(&stale_results).is_empty());
+
+        embeddings::EmbeddingSink::dead_letter(
+            &sink,
+            vec![DeadLetter {
+                chunk_id: fresh[0].chunk_id.clone(),
+                corpus: "/repo".to_string(),
+                error: "boom".to_string(),
+                attempts: 3,
+            }],
+        )
+        .await;
+        assert!(sink.pending.lock().expect("pending").chunks.is_empty());
+        let log = std::fs::read_to_string(sink.dead_letter_path()).expect("dead letters");
+        assert!(log.contains("\"error\":\"boom\""));
+        let status = runtime.snapshot().await;
+        assert!(
+            status[0]
+                .last_error
+                .as_deref()
+                .is_some_and(|err| err.starts_with("1 chunks failed to embed"))
+        );
+        let _ = std::fs::remove_dir_all(&cwd);
+    }
+
+    #[tokio::test]
+    async fn superseded_registrations_are_not_embedded() {
+        let cwd = std::env::temp_dir().join(format!("codivex-sink-stale-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&cwd);
+        let engine = Arc::new(EmbeddingEngine::new(EmbeddingConfig::default()));
+        let sink = Arc::new(VectorStoreSink::new(
+            cwd.clone(),
+            engine.clone(),
+            Arc::new(IndexingRuntimeState::default()),
+        ));
+        let chunks = [chunk("a:1:3:parse"), chunk("b:5:7:parse")];
+
+        let first = sink.register("/repo", &chunks, vec![views(None), views(None)]);
+        sink.record_metadata_when_done("/repo", "ctx-v1");
+        assert!(sink.reembed_pending("/repo"));
+        let second = sink.register("/repo", &chunks, vec![views(None), views(None)]);
+
+        let (queue, rx) = EmbeddingQueue::new(16);
+        for job in first.into_iter().chain(second) {
+            queue.enqueue(job).await.expect("enqueue");
+        }
+        drop(queue);
+        let metrics = Arc::new(EmbeddingWorkerMetrics::default());
+        run_embedding_worker_with_metrics(
+            rx,
+            engine,
+            EmbeddingWorkerConfig {
+                max_retries: 0,
+                base_backoff_ms: 1,
+                ..EmbeddingWorkerConfig::default()
+            },
+            sink.clone(),
+            Some(metrics.clone()),
+        )
+        .await;
+
+        let snapshot = metrics.snapshot();
+        assert_eq!((snapshot.items_embedded, snapshot.skipped), (4, 4));
+        let _ = std::fs::remove_dir_all(&cwd);
+    }
+}
@@ -0,0 +1,285 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use common::{SearchResultItem, projects, projects::FeedbackEvent};
+use search_core::feedback::{feedback_boosts, relevant_feedback};
+
+const MAX_TRACKED_SESSIONS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeedbackSettings {
+    pub weight: f32,
+    pub cap: f32,
+    pub half_life_ms: u64,
+    pub link_window_ms: u64,
+}
+
+impl FeedbackSettings {
+    pub fn from_env() -> Self {
+        let half_life_days = env_f32("MCP_FEEDBACK_HALF_LIFE_DAYS", 14.0).max(0.01);
+        Self {
+            weight: env_f32("MCP_FEEDBACK_WEIGHT", 1.0).max(0.0),
+            cap: env_f32("MCP_FEEDBACK_CAP", 3.0).max(0.0),
+            half_life_ms: (half_life_days * 86_400_000.0) as u64,
+            link_window_ms: (env_f32("MCP_FEEDBACK_LINK_SECS", 600.0).max(0.0) * 1000.0) as u64,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SessionSearches {
+    recent: Mutex<HashMap<String, RecentSearch>>,
+}
+
+struct RecentSearch {
+    project_path: String,
+    query: String,
+    items: Vec<RememberedItem>,
+    chosen: HashSet<String>,
+    at_ms: u64,
+}
+
+struct RememberedItem {
+    chunk_id: String,
+    file: String,
+    start_line: usize,
+    end_line: usize,
+}
+
+impl SessionSearches {
+    pub fn remember(
+        &self,
+        session: &str,
+        project_path: &str,
+        query: &str,
+        items: &[SearchResultItem],
+        now_ms: u64,
+    ) {
+        let items = items
+            .iter()
+            .filter_map(|item| {
+                item.explain.as_ref().map(|explain| RememberedItem {
+                    chunk_id: explain.chunk_id.clone(),
+                    file: item.file.clone(),
+                    start_line: item.start_line,
+                    end_line: item.end_line,
+                })
+            })
+            .collect::<Vec<_>>();
+        let Ok(mut guard) = self.recent.lock() else {
+            return;
+        };
+        if guard.len() >= MAX_TRACKED_SESSIONS
+            && !guard.contains_key(session)
+            && let Some(oldest) = guard
+                .iter()
+                .min_by_key(|(_, search)| search.at_ms)
+                .map(|(key, _)| key.clone())
+        {
+            guard.remove(&oldest);
+        }
+        guard.insert(
+            session.to_string(),
+            RecentSearch {
+                project_path: project_path.to_string(),
+                query: query.to_string(),
+                items,
+                chosen: HashSet::new(),
+                at_ms: now_ms,
+            },
+        );
+    }
+
+    pub fn link_open(
+        &self,
+        session: &str,
+        opened: &Path,
+        line_start: usize,
+        line_end: usize,
+        now_ms: u64,
+        link_window_ms: u64,
+    ) -> Option<(String, FeedbackEvent)> {
+        let mut guard = self.recent.lock().ok()?;
+        let search = guard.get_mut(session)?;
+        if now_ms.saturating_sub(search.at_ms) > link_window_ms {
+            return None;
+        }
+        let (rank, item) = search.items.iter().enumerate().find(|(_, item)| {
+            opened.ends_with(&item.file)
+                && line_start <= item.end_line
+                && line_end >= item.start_line
+        })?;
+        if !search.chosen.insert(item.chunk_id.clone()) {
+            return None;
+        }
+        Some((
+            search.project_path.clone(),
+            FeedbackEvent {
+                query: search.query.clone(),
+                chunk_id: item.chunk_id.clone(),
+                file: item.file.clone(),
+                rank: rank + 1,
+                unix_ms: now_ms,
+            },
+        ))
+    }
+}
+
+pub fn record_open(
+    cwd: &Path,
+    sessions: &SessionSearches,
+    session: &str,
+    opened: &Path,
+    line_start: usize,
+    line_end: usize,
+) {
+    let settings = FeedbackSettings::from_env();
+    let Some((project_path, event)) = sessions.link_open(
+        session,
+        opened,
+        line_start,
+        line_end,
+        unix_now_ms(),
+        settings.link_window_ms,
+    ) else {
+        return;
+    };
+    metrics::counter!("mcp_feedback_events_total").increment(1);
+    if let Err(err) = projects::append_feedback_event(cwd, &project_path, &event) {
+        tracing::warn!(project = project_path, error = %err, "failed recording relevance feedback");
+    }
+}
+
+type FeedbackLogCache = Mutex<HashMap<String, (u64, Arc<Vec<FeedbackEvent>>)>>;
+
+// Every feedback append bumps the project's feedback generation, so the parsed log only has
+// to be re-read when that moves instead of on every search.
+fn feedback_events(cwd: &Path, project_path: &str) -> Arc<Vec<FeedbackEvent>> {
+    static LOGS: OnceLock<FeedbackLogCache> = OnceLock::new();
+    let logs = LOGS.get_or_init(Default::default);
+    let key = format!("{}\u{241f}{project_path}", cwd.display());
+    let generation = projects::feedback_generation(cwd, project_path);
+    if let Ok(guard) = logs.lock()
+        && let Some((cached, events)) = guard.get(&key)
+        && *cached == generation
+    {
+        return events.clone();
+    }
+    let events = Arc::new(projects::load_feedback_events(cwd, project_path));
+    if let Ok(mut guard) = logs.lock() {
+        guard.insert(key, (generation, events.clone()));
+    }
+    events
+}
+
+// Cached searches are keyed on the feedback that can boost this query, so a recorded open
+// only invalidates results for similar queries.
+pub fn query_feedback_key(cwd: &Path, project_path: &str, query: &str) -> u64 {
+    let events = feedback_events(cwd, project_path);
+    let mut hasher = DefaultHasher::new();
+    for (event, _) in relevant_feedback(query, &events) {
+        event.chunk_id.hash(&mut hasher);
+        event.unix_ms.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+pub fn boosts_for_query(cwd: &Path, project_path: &str, query: &str) -> HashMap<String, f32> {
+    let events = feedback_events(cwd, project_path);
+    if events.is_empty() {
+        return HashMap::new();
+    }
+    let settings = FeedbackSettings::from_env();
+    feedback_boosts(query, &events, unix_now_ms(), settings.half_life_ms)
+        .into_iter()
+        .map(|(id, raw)| (id, settings.weight * raw.min(settings.cap)))
+        .collect()
+}
+
+pub fn unix_now_ms() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_millis() as u64,
+        Err(_) => 0,
+    }
+}
+
+fn env_f32(key: &str, default: f32) -> f32 {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse::<f32>().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use common::{ResultExplanation, SearchResultItem};
+
+    use super::SessionSearches;
+
+    fn item(file: &str, start_line: usize, end_line: usize) -> SearchResultItem {
+        SearchResultItem {
+            file: file.to_string(),
+            function: "f".to_string(),
+            start_line,
+            end_line,
+            code_block: String::new(),
+            highlights: Vec::new(),
+            token_count: None,
+            detail: None,
+            pinned: false,
+            explain: Some(ResultExplanation {
+                chunk_id: format!("{file}:{start_line}:{end_line}:f"),
+                fused_score: 0.0,
+                lexical_rank: None,
+                semantic_rank: None,
+                exact_symbol: false,
+                feedback_boost: 0.0,
+                diversity_penalty: 0.0,
+                priors: Vec::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn open_links_to_ranked_item_once_within_window() {
+        let sessions = SessionSearches::default();
+        let items = vec![item("src/a.rs", 1, 5), item("src/date.rs", 10, 20)];
+        sessions.remember("s1", "/repo", "iso to date", &items, 1_000);
+
+        let opened = Path::new("/repo/src/date.rs");
+        assert!(
+            sessions
+                .link_open("s2", opened, 12, 14, 1_500, 600_000)
+                .is_none()
+        );
+        let (project, event) = sessions
+            .link_open("s1", opened, 12, 14, 1_500, 600_000)
+            .expect("linked event");
+        assert_eq!(project, "/repo");
+        assert_eq!(event.rank, 2);
+        assert_eq!(event.chunk_id, "src/date.rs:10:20:f");
+        assert!(
+            sessions
+                .link_open("s1", opened, 12, 14, 1_600, 600_000)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn open_outside_window_is_ignored() {
+        let sessions = SessionSearches::default();
+        sessions.remember("s1", "/repo", "q", &[item("src/a.rs", 1, 5)], 0);
+        assert!(
+            sessions
+                .link_open("s1", Path::new("/repo/src/a.rs"), 1, 2, 10_000, 5_000)
+                .is_none()
+        );
+    }
+}
@@ -9,21 +9,55 @@ use common::{
     CodeChunk,
     projects::{self, IndexedChunk, IndexedProject},
 };
-use embeddings::{EmbeddingConfig, EmbeddingEngine};
+use embeddings::{
+    EmbeddingQueue, EmbeddingWorkerConfig, hashed::base_model_id, run_embedding_worker,
+};
 use indexer::incremental::{ByteEdit, incremental_reparse};
 use qdrant_client::Qdrant;
 use search_core::{
+    EmbeddingTemplate,
     lexical::TantivyLexicalIndex,
+    render_chunk_views,
     vector::{QdrantVectorStore, QuantizationMode as VectorQuantizationMode, VectorSearchConfig},
 };
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 use tree_sitter::Point;
 
-use crate::state::AppState;
+use crate::{
+    services::{embedding_sink::VectorStoreSink, search::corpus_texts},
+    state::AppState,
+};
+
+const EMBEDDING_QUEUE_CAPACITY: usize = 4096;
+
+#[derive(Clone)]
+struct EmbeddingPipeline {
+    queue: EmbeddingQueue,
+    sink: Arc<VectorStoreSink>,
+}
+
+impl EmbeddingPipeline {
+    fn spawn(state: &AppState) -> Self {
+        let (queue, rx) = EmbeddingQueue::new(EMBEDDING_QUEUE_CAPACITY);
+        let sink = Arc::new(VectorStoreSink::new(
+            state.cwd.clone(),
+            state.embedder.clone(),
+            state.indexing_runtime.clone(),
+        ));
+        tokio::spawn(run_embedding_worker(
+            rx,
+            state.embedder.clone(),
+            EmbeddingWorkerConfig::default(),
+            sink.clone(),
+        ));
+        Self { queue, sink }
+    }
+}
 
 pub fn spawn_background_indexing(state: AppState) {
     tokio::spawn(async move {
+        let pipeline = EmbeddingPipeline::spawn(&state);
         let active_watchers = Arc::new(RwLock::new(HashSet::<String>::new()));
         loop {
             if state.is_shutting_down() {
@@ -38,7 +72,12 @@ pub fn spawn_background_indexing(state: AppState) {
                     continue;
                 }
                 if mark_watcher_if_new(&active_watchers, &project_path).await {
-                    spawn_project_watcher(state.clone(), active_watchers.clone(), project_path);
+                    spawn_project_watcher(
+                        state.clone(),
+                        pipeline.clone(),
+                        active_watchers.clone(),
+                        project_path,
+                    );
                 }
             }
             tokio::time::sleep(Duration::from_secs(5)).await;
@@ -48,11 +87,12 @@ pub fn spawn_background_indexing(state: AppState) {
 
 fn spawn_project_watcher(
     state: AppState,
+    pipeline: EmbeddingPipeline,
     active_watchers: Arc<RwLock<HashSet<String>>>,
     project_path: String,
 ) {
     tokio::spawn(async move {
-        if let Err(err) = run_project_watcher(state.clone(), &project_path).await {
+        if let Err(err) = run_project_watcher(state.clone(), &pipeline, &project_path).await {
             warn!(
                 project = project_path,
                 error = %err,
@@ -85,7 +125,11 @@ async fn mark_watcher_if_new(
     }
 }
 
-async fn run_project_watcher(state: AppState, project_path: &str) -> anyhow::Result<()> {
+async fn run_project_watcher(
+    state: AppState,
+    pipeline: &EmbeddingPipeline,
+    project_path: &str,
+) -> anyhow::Result<()> {
     let (watcher, mut rx) = indexer::watcher::FileWatcher::start(&[PathBuf::from(project_path)])?;
     let _keep_alive = watcher;
     state
@@ -133,7 +177,8 @@ async fn run_project_watcher(state: AppState, project_path: &str) -> anyhow::Res
                 snapshots.insert(key, new_content);
             }
 
-            if let Err(err) = apply_incremental_update(&state, project_path, &path).await {
+            if let Err(err) = apply_incremental_update(&state, pipeline, project_path, &path).await
+            {
                 warn!(
                     project = project_path,
                     file = %path.display(),
@@ -157,53 +202,71 @@ async fn run_project_watcher(state: AppState, project_path: &str) -> anyhow::Res
 
 async fn apply_incremental_update(
     state: &AppState,
+    pipeline: &EmbeddingPipeline,
     project_path: &str,
     changed_path: &Path,
 ) -> anyhow::Result<()> {
     let cwd = state.cwd.clone();
     let project = project_path.to_string();
     let changed = changed_path.to_path_buf();
+    let template = EmbeddingTemplate::from_env().with_root(project_path);
+    let template_version = template.version().to_string();
+    let model_id = state.embedder.corpus_model_id(project_path);
+    let refit_corpus = state.embedder.needs_corpus(project_path);
     let output = tokio::task::spawn_blocking(move || {
-        update_json_and_lexical_index(&cwd, &project, &changed)
+        update_json_and_lexical_index(
+            &cwd,
+            &project,
+            &changed,
+            &model_id,
+            &template_version,
+            refit_corpus,
+        )
     })
     .await??;
 
-    if let Some(client) = qdrant_client_from_env() {
-        let mut cfg = VectorSearchConfig {
-            collection: projects::project_vector_collection(project_path),
-            ..VectorSearchConfig::default()
-        };
-        if let Some(first) = output.added_chunks.first() {
-            let embedding_cfg = EmbeddingConfig::default();
-            let engine = EmbeddingEngine::new(embedding_cfg.clone());
-            let texts = output
-                .added_chunks
-                .iter()
-                .map(|c| c.content.clone())
-                .collect::<Vec<_>>();
-            let vectors = engine.embed_batch(&texts)?;
-            if let Some(first_vec) = vectors.first() {
-                cfg.vector_dim = first_vec.len();
-                cfg.quantization = to_vector_quantization_mode(embedding_cfg.quantization);
-                let store = QdrantVectorStore::new(cfg.clone());
-                let _ = store.ensure_collection(&client).await;
-                if !output.deleted_chunk_ids.is_empty() {
-                    let _ = store
-                        .delete_points(&client, &output.deleted_chunk_ids)
-                        .await;
-                }
-                store
-                    .upsert_chunks(&client, &output.added_chunks, &vectors)
-                    .await?;
-            } else {
-                let _ = first;
-            }
-        } else if !output.deleted_chunk_ids.is_empty() {
-            let store = QdrantVectorStore::new(cfg);
+    if let Some(stored) = output.model_conflict.as_deref() {
+        warn!(
+            project = project_path,
+            stored_model = stored,
+            loaded_model = state.embedder.model_id(),
+            "skipping vector update for project embedded with a different model"
+        );
+    } else if let Some(client) = qdrant_client_from_env() {
+        pipeline.sink.forget(&output.deleted_chunk_ids);
+        if !output.deleted_chunk_ids.is_empty() {
+            let store = QdrantVectorStore::new(VectorSearchConfig {
+                collection: projects::writable_vector_collection(&state.cwd, project_path),
+                ..VectorSearchConfig::default()
+            });
             let _ = store
                 .delete_points(&client, &output.deleted_chunk_ids)
                 .await;
         }
+        // A full re-embed already in flight covers unchanged chunks; only the
+        // chunks from this save need new jobs, which supersede the pending ones.
+        let reembed_chunks = output
+            .reembed_chunks
+            .as_deref()
+            .filter(|_| !pipeline.sink.reembed_pending(project_path));
+        let embed_chunks = reembed_chunks.unwrap_or(&output.added_chunks);
+        if !embed_chunks.is_empty() {
+            if reembed_chunks.is_some() {
+                state
+                    .embedder
+                    .fit_corpus(project_path, &corpus_texts(embed_chunks));
+            }
+            let views = render_chunk_views(embed_chunks, &template);
+            let jobs = pipeline.sink.register(project_path, embed_chunks, views);
+            if reembed_chunks.is_some() {
+                pipeline
+                    .sink
+                    .record_metadata_when_done(project_path, template.version());
+            }
+            for job in jobs {
+                pipeline.queue.enqueue(job).await?;
+            }
+        }
     }
 
     let now_ms = unix_now_ms();
@@ -229,12 +292,26 @@ async fn apply_incremental_update(
         deleted = output.deleted_chunk_ids.len(),
         "incremental index update complete"
     );
+    if let Some(stored) = output.model_conflict {
+        state
+            .indexing_runtime
+            .mark_error(
+                project_path,
+                format!(
+                    "embedding model changed (stored {stored}, loaded {}); run a full reindex to re-embed",
+                    state.embedder.model_id()
+                ),
+            )
+            .await;
+    }
     Ok(())
 }
 
 #[derive(Debug)]
 struct IncrementalUpdateOutput {
     added_chunks: Vec<CodeChunk>,
+    reembed_chunks: Option<Vec<CodeChunk>>,
+    model_conflict: Option<String>,
     deleted_chunk_ids: Vec<String>,
     indexing_lag_ms: u64,
 }
@@ -243,6 +320,9 @@ fn update_json_and_lexical_index(
     cwd: &Path,
     project_path: &str,
     changed_path: &Path,
+    model_id: &str,
+    template_version: &str,
+    refit_corpus: bool,
 ) -> anyhow::Result<IncrementalUpdateOutput> {
     let mut indexed = projects::load_project_index(cwd, project_path).ok_or_else(|| {
         anyhow::anyhow!("project not indexed yet: {project_path}, run initial indexing first")
@@ -259,32 +339,44 @@ fn update_json_and_lexical_index(
     });
 
     let mut added_chunks = Vec::new();
-    if changed_path.exists() {
-        if let Ok(content) = std::fs::read_to_string(changed_path) {
-            if let Ok(chunks) = indexer::extract_chunks_for_file(&changed_path_str, &content) {
-                for chunk in chunks {
-                    indexed.chunks.push(IndexedChunk {
-                        file: chunk.file_path.clone(),
-                        symbol: chunk.symbol.clone(),
-                        start_line: chunk.start_line,
-                        end_line: chunk.end_line,
-                        content: chunk.content.clone(),
-                    });
-                    added_chunks.push(chunk);
-                }
-            }
+    if changed_path.exists()
+        && let Ok(content) = std::fs::read_to_string(changed_path)
+        && let Ok(chunks) = indexer::extract_chunks_for_file(&changed_path_str, &content)
+    {
+        for chunk in chunks {
+            let indexed_chunk = IndexedChunk {
+                file: chunk.file_path,
+                symbol: chunk.symbol,
+                start_line: chunk.start_line,
+                end_line: chunk.end_line,
+                content: chunk.content,
+            };
+            added_chunks.push(to_code_chunk(&indexed_chunk));
+            indexed.chunks.push(indexed_chunk);
         }
     }
+    let model_conflict = indexed
+        .embedding_model
+        .clone()
+        .filter(|stored| base_model_id(stored) != base_model_id(model_id));
+    // A stored id that differs only in its corpus statistics means another process refitted
+    // them, so every chunk is re-embedded with the current weights.
+    let reembed_chunks = (model_conflict.is_none()
+        && (refit_corpus
+            || indexed.embedding_model.as_deref() != Some(model_id)
+            || indexed.embedding_template_version.as_deref() != Some(template_version)))
+    .then(|| indexed.chunks.iter().map(to_code_chunk).collect());
 
     indexed.chunks_extracted = indexed.chunks.len();
     indexed.indexed_at_unix = unix_now();
-    projects::save_project_index(cwd, &indexed)?;
-
     persist_tantivy_index(cwd, project_path, &indexed)?;
+    projects::save_project_index(cwd, &indexed)?;
 
     let lag_ms = 0u64;
     Ok(IncrementalUpdateOutput {
         added_chunks,
+        reembed_chunks,
+        model_conflict,
         deleted_chunk_ids,
         indexing_lag_ms: lag_ms,
     })
@@ -410,7 +502,9 @@ fn qdrant_client_from_env() -> Option<Qdrant> {
     Qdrant::from_url(&url).build().ok()
 }
 
-fn to_vector_quantization_mode(mode: embeddings::QuantizationMode) -> VectorQuantizationMode {
+pub(crate) fn to_vector_quantization_mode(
+    mode: embeddings::QuantizationMode,
+) -> VectorQuantizationMode {
     match mode {
         embeddings::QuantizationMode::None => VectorQuantizationMode::None,
         embeddings::QuantizationMode::Int8 => VectorQuantizationMode::Int8,
@@ -1,2 +1,6 @@
+pub mod embedding_sink;
+pub mod feedback;
 pub mod indexing;
+pub mod packing;
 pub mod search;
+pub mod similar;
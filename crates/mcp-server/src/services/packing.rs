@@ -0,0 +1,179 @@
+use common::{SearchCodeResult, SearchResultItem, SnippetDetail, TokenBudgetReport};
+use embeddings::TokenCounter;
+use search_core::highlight::{matched_lines_snippet, signature_snippet};
+
+pub fn pack_results(
+    result: SearchCodeResult,
+    max_tokens: usize,
+    counter: &TokenCounter,
+) -> SearchCodeResult {
+    let mut remaining = max_tokens;
+    let mut used_tokens = 0usize;
+    let mut elided_tokens = 0usize;
+    let mut elided_items = 0usize;
+    let mut over_budget = false;
+    let mut items = Vec::new();
+
+    for item in result.items {
+        let header = counter.count(&item_header(&item));
+        let full_cost = header + counter.count(&item.code_block);
+        let terms = item
+            .highlights
+            .iter()
+            .flat_map(|h| h.terms.iter().cloned())
+            .collect::<Vec<_>>();
+        let mut candidates = vec![(SnippetDetail::Full, item.code_block.clone(), full_cost)];
+        if let Some(matched) = matched_lines_snippet(&item.code_block, &terms) {
+            let cost = header + counter.count(&matched);
+            candidates.push((SnippetDetail::Matched, matched, cost));
+        }
+        let signature = signature_snippet(&item.code_block);
+        let cost = header + counter.count(&signature);
+        candidates.push((SnippetDetail::Signature, signature.clone(), cost));
+
+        let chosen = candidates
+            .iter()
+            .position(|(_, _, cost)| *cost <= remaining);
+        let (detail, code_block, cost) = match chosen {
+            Some(idx) => candidates.swap_remove(idx),
+            // The top result is always returned, cut down to whatever the budget leaves.
+            None if items.is_empty() => {
+                let code =
+                    truncate_to_tokens(&signature, remaining.saturating_sub(header), counter);
+                let cost = header + counter.count(&code);
+                over_budget = cost > remaining;
+                (SnippetDetail::Truncated, code, cost)
+            }
+            None => {
+                elided_items += 1;
+                elided_tokens += full_cost;
+                continue;
+            }
+        };
+        remaining = remaining.saturating_sub(cost);
+        used_tokens += cost;
+        elided_tokens += full_cost.saturating_sub(cost);
+        items.push(SearchResultItem {
+            code_block,
+            token_count: Some(cost),
+            detail: Some(detail),
+            ..item
+        });
+    }
+
+    SearchCodeResult {
+        items,
+        budget: Some(TokenBudgetReport {
+            max_tokens,
+            used_tokens,
+            elided_tokens,
+            elided_items,
+            estimator: counter.name().to_string(),
+            over_budget,
+        }),
+        groups: result.groups,
+        degraded: result.degraded,
+    }
+}
+
+fn truncate_to_tokens(text: &str, max_tokens: usize, counter: &TokenCounter) -> String {
+    let boundaries = text
+        .char_indices()
+        .map(|(idx, _)| idx)
+        .chain([text.len()])
+        .collect::<Vec<_>>();
+    let fits = boundaries.partition_point(|end| counter.count(&text[..*end]) <= max_tokens);
+    text[..boundaries[fits.saturating_sub(1)]].to_string()
+}
+
+fn item_header(item: &SearchResultItem) -> String {
+    format!(
+        "{}:{}-{} {}",
+        item.file, item.start_line, item.end_line, item.function
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{SearchCodeResult, SearchHighlight, SearchResultItem, SnippetDetail};
+    use embeddings::TokenCounter;
+
+    use super::pack_results;
+
+    fn item(name: &str, body_lines: usize) -> SearchResultItem {
+        let mut lines = vec![format!("fn {name}() {{")];
+        for idx in 0..body_lines {
+            if idx == body_lines / 2 {
+                lines.push("    let date = iso_to_date(raw);".to_string());
+            } else {
+                lines.push(format!("    let filler_{idx} = {idx};"));
+            }
+        }
+        lines.push("}".to_string());
+        SearchResultItem {
+            file: "src/lib.rs".to_string(),
+            function: name.to_string(),
+            start_line: 1,
+            end_line: body_lines + 2,
+            code_block: lines.join("\n"),
+            highlights: vec![SearchHighlight {
+                start_line: body_lines / 2 + 2,
+                end_line: body_lines / 2 + 2,
+                terms: vec!["iso".to_string()],
+            }],
+            token_count: None,
+            detail: None,
+            pinned: false,
+            explain: None,
+        }
+    }
+
+    #[test]
+    fn packing_degrades_then_elides_within_budget() {
+        let counter = TokenCounter::char_estimate(4);
+        let result = SearchCodeResult {
+            items: vec![item("first", 10), item("second", 40), item("third", 40)],
+            budget: None,
+            groups: Vec::new(),
+            degraded: false,
+        };
+        let packed = pack_results(result, 140, &counter);
+        let budget = packed.budget.expect("budget report");
+        assert!(budget.used_tokens <= 140);
+        assert_eq!(packed.items[0].detail, Some(SnippetDetail::Full));
+        assert_eq!(packed.items[1].detail, Some(SnippetDetail::Matched));
+        assert!(packed.items[1].code_block.contains("iso_to_date"));
+        assert_eq!(
+            packed
+                .items
+                .iter()
+                .filter_map(|i| i.token_count)
+                .sum::<usize>(),
+            budget.used_tokens
+        );
+        assert!(budget.elided_tokens > 0);
+        assert_eq!(budget.elided_items + packed.items.len(), 3);
+    }
+
+    #[test]
+    fn packing_keeps_top_signature_when_budget_is_tiny() {
+        let counter = TokenCounter::char_estimate(4);
+        let result = SearchCodeResult {
+            items: vec![item("first", 10)],
+            budget: None,
+            groups: Vec::new(),
+            degraded: false,
+        };
+        let packed = pack_results(result.clone(), 1, &counter);
+        assert_eq!(packed.items.len(), 1);
+        assert_eq!(packed.items[0].detail, Some(SnippetDetail::Truncated));
+        assert_eq!(packed.items[0].code_block, "");
+        assert!(packed.budget.expect("budget report").over_budget);
+
+        let packed = pack_results(result, 8, &counter);
+        let budget = packed.budget.expect("budget report");
+        assert_eq!(packed.items[0].detail, Some(SnippetDetail::Truncated));
+        assert_eq!(packed.items[0].code_block, "fn first");
+        assert_eq!((budget.used_tokens, budget.over_budget), (8, false));
+    }
+}
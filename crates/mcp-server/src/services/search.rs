@@ -1,15 +1,43 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
 
-use common::{CodeChunk, SearchCodeResult, SearchResultItem, projects};
-use embeddings::{EmbeddingConfig, EmbeddingEngine};
+use common::{
+    CodeChunk, FileResultGroup, FileResultRange, PriorContribution, ResultExplanation,
+    SearchCodeParams, SearchCodeResult, SearchHighlight, SearchResultItem,
+    projects::{self, IndexedProject},
+};
+use embeddings::{CrossEncoderReranker, EmbeddingEngine, RerankerConfig};
+use futures::{
+    FutureExt,
+    future::{BoxFuture, Shared},
+};
 use qdrant_client::Qdrant;
 use search_core::{
-    RetrievalDefaults,
+    DiversifiedId, DiversityCandidate, DiversityConfig, EmbeddingTemplate, FusionWeights,
+    HighlightSpan, LexicalHit, LocalVectorConfig, LocalVectorIndex, MultiVectorWeights,
+    PackageResolver, PriorSubject, ProximityContext, ProximityWeights, RankingPriors,
+    RetrievalDefaults, ScoredId, VectorField, apply_boosts, embed_chunk_views, fuse_field_rankings,
+    fuse_ranked,
+    highlight::{focused_snippet, line_windows, matched_query_terms},
     lexical::TantivyLexicalIndex,
-    rrf_fuse,
+    mmr_select, pin_matches, resolve_pin, same_file,
     vector::{QdrantVectorStore, VectorSearchConfig},
 };
 
+use crate::services::feedback;
+
+pub(crate) const SNIPPET_MAX_LINES: usize = 120;
+pub(crate) const SNIPPET_MAX_CHARS: usize = 6000;
+const SEMANTIC_WINDOW_LINES: usize = 24;
+const DIVERSITY_POOL_FACTOR: usize = 8;
+const LOCAL_EMBED_BATCH: usize = 32;
+const FIELD_FUSION_K: usize = 60;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum RetrievalTier {
     Fast,
@@ -31,8 +59,83 @@ impl RetrievalTier {
     }
 }
 
-pub fn cache_key(project_scope: &str, query: &str, top_k: usize) -> String {
-    format!("{project_scope}\u{241f}{query}\u{241f}{top_k}")
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub prefer_tests: bool,
+    pub context_path: Option<String>,
+    pub context_symbol: Option<String>,
+}
+
+impl From<&SearchCodeParams> for SearchOptions {
+    fn from(params: &SearchCodeParams) -> Self {
+        Self {
+            prefer_tests: params.prefer_tests,
+            context_path: params.context_path.clone(),
+            context_symbol: params.context_symbol.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchDeadline {
+    expires_at: Option<Instant>,
+}
+
+impl SearchDeadline {
+    pub fn unbounded() -> Self {
+        Self { expires_at: None }
+    }
+
+    pub fn after(budget: Duration) -> Self {
+        Self {
+            expires_at: Instant::now().checked_add(budget),
+        }
+    }
+
+    pub fn from_request(deadline_ms: Option<u64>) -> Self {
+        let ms = deadline_ms.unwrap_or_else(|| {
+            std::env::var("MCP_SEARCH_DEADLINE_MS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(2000)
+        });
+        Self::after(Duration::from_millis(ms))
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.expires_at
+            .map(|at| at.saturating_duration_since(Instant::now()))
+            .unwrap_or(Duration::MAX)
+    }
+
+    pub fn expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Degraded {
+    Deadline,
+    Migration,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScopedSearch {
+    pub items: Vec<SearchResultItem>,
+    pub degraded: Option<Degraded>,
+}
+
+pub fn cache_key(
+    project_scope: &str,
+    generation: u64,
+    feedback: u64,
+    query: &str,
+    top_k: usize,
+    options: &SearchOptions,
+) -> String {
+    format!(
+        "{project_scope}\u{241f}{generation}\u{241f}{feedback:x}\u{241f}{query}\u{241f}{top_k}\u{241f}{options:?}"
+    )
 }
 
 pub async fn cache_lookup(
@@ -52,26 +155,446 @@ pub async fn cache_store(
     guard.put(key, result);
 }
 
+/// Retrieval output for one query, kept so the fusion tuner can re-rank it under many weight
+/// settings through the same priors, proximity, feedback and MMR steps as a live search.
+pub struct RankedCandidates {
+    project_path: String,
+    candidates: CandidateSet,
+    feedback: HashMap<String, f32>,
+}
+
+impl RankedCandidates {
+    pub fn ranked_files(&self, weights: &FusionWeights, top_k: usize) -> Vec<&str> {
+        let chunk_map = self.candidates.chunk_map();
+        rank_candidates(
+            &self.project_path,
+            &self.candidates,
+            &chunk_map,
+            &self.feedback,
+            &SearchOptions::default(),
+            weights,
+            top_k,
+        )
+        .diversified
+        .iter()
+        .filter_map(|d| chunk_map.get(&d.scored.id).map(|c| c.file_path.as_str()))
+        .collect()
+    }
+}
+
+struct CandidateSet {
+    chunks: Vec<CodeChunk>,
+    exact_symbol_id: Option<String>,
+    lexical_ids: Vec<String>,
+    semantic_ids: Vec<String>,
+    lexical_highlights: HashMap<String, Vec<HighlightSpan>>,
+    query_vector: Option<Vec<f32>>,
+    degraded: Option<Degraded>,
+}
+
+impl CandidateSet {
+    fn chunk_map(&self) -> HashMap<String, &CodeChunk> {
+        self.chunks.iter().map(|c| (c.id.clone(), c)).collect()
+    }
+}
+
+struct Ranking {
+    diversified: Vec<DiversifiedId>,
+    prior_components: HashMap<String, Vec<(String, f32)>>,
+    feedback_applied: HashMap<String, f32>,
+}
+
 pub async fn scoped_project_results(
     cwd: &Path,
     project_path: &str,
+    engine: &Arc<EmbeddingEngine>,
     query: &str,
     top_k: usize,
 ) -> anyhow::Result<Vec<SearchResultItem>> {
-    let indexed = projects::load_project_index(cwd, project_path)
-        .ok_or_else(|| anyhow::anyhow!("project not indexed"))?;
+    scoped_project_results_with(
+        cwd,
+        project_path,
+        engine,
+        query,
+        top_k,
+        &SearchOptions::default(),
+    )
+    .await
+}
+
+pub async fn scoped_project_results_with(
+    cwd: &Path,
+    project_path: &str,
+    engine: &Arc<EmbeddingEngine>,
+    query: &str,
+    top_k: usize,
+    options: &SearchOptions,
+) -> anyhow::Result<Vec<SearchResultItem>> {
+    // SSE streams, the admin UI and the offline tools have no per-request budget, so only
+    // MCP calls are held to `MCP_SEARCH_DEADLINE_MS`.
+    let deadline = SearchDeadline::unbounded();
+    scoped_project_search(cwd, project_path, engine, query, top_k, options, &deadline)
+        .await
+        .map(|search| search.items)
+}
+
+pub async fn scoped_project_search(
+    cwd: &Path,
+    project_path: &str,
+    engine: &Arc<EmbeddingEngine>,
+    query: &str,
+    top_k: usize,
+    options: &SearchOptions,
+    deadline: &SearchDeadline,
+) -> anyhow::Result<ScopedSearch> {
+    let tier = RetrievalTier::from_env();
+    let mut candidates =
+        collect_candidates(cwd, project_path, engine, query, top_k, tier, deadline).await?;
+    let mut degraded = candidates.degraded;
+    if candidates.chunks.is_empty() {
+        return Ok(ScopedSearch {
+            items: Vec::new(),
+            degraded,
+        });
+    }
+
+    let weights = projects::load_retrieval_profile(cwd, project_path)
+        .map(|profile| FusionWeights::from(&profile))
+        .unwrap_or_default();
+    let mut lexical_highlights = std::mem::take(&mut candidates.lexical_highlights);
+    let feedback = feedback::boosts_for_query(cwd, project_path, query);
+    let chunk_map = candidates.chunk_map();
+    let Ranking {
+        diversified,
+        prior_components,
+        feedback_applied,
+    } = rank_candidates(
+        project_path,
+        &candidates,
+        &chunk_map,
+        &feedback,
+        options,
+        &weights,
+        top_k,
+    );
+    let lexical_ranks = rank_positions(&candidates.lexical_ids);
+    let semantic_ranks = rank_positions(&candidates.semantic_ids);
+
+    let mut out = Vec::new();
+    for DiversifiedId {
+        scored: ScoredId { id, score },
+        penalty,
+    } in diversified
+    {
+        if let Some(chunk) = chunk_map.get(&id) {
+            let spans = match lexical_highlights.remove(&id) {
+                Some(spans) => spans,
+                None => match candidates.query_vector.as_deref() {
+                    Some(q) if !deadline.expired() => {
+                        semantic_best_window(engine, project_path, q, chunk, query).await
+                    }
+                    _ => Vec::new(),
+                },
+            };
+            out.push(SearchResultItem {
+                file: chunk.file_path.clone(),
+                function: chunk.symbol.clone().unwrap_or_else(|| "chunk".to_string()),
+                start_line: chunk.start_line,
+                end_line: chunk.end_line,
+                code_block: focused_snippet(
+                    &chunk.content,
+                    &spans,
+                    SNIPPET_MAX_LINES,
+                    SNIPPET_MAX_CHARS,
+                ),
+                highlights: to_search_highlights(chunk, &spans),
+                token_count: None,
+                detail: None,
+                pinned: false,
+                explain: Some(ResultExplanation {
+                    fused_score: score,
+                    lexical_rank: lexical_ranks.get(id.as_str()).copied(),
+                    semantic_rank: semantic_ranks.get(id.as_str()).copied(),
+                    exact_symbol: candidates.exact_symbol_id.as_deref() == Some(id.as_str()),
+                    feedback_boost: feedback_applied.get(&id).copied().unwrap_or(0.0),
+                    diversity_penalty: penalty,
+                    priors: prior_components
+                        .get(&id)
+                        .map(|parts| {
+                            parts
+                                .iter()
+                                .map(|(name, weight)| PriorContribution {
+                                    name: name.clone(),
+                                    delta: weights.boost_delta(*weight),
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    chunk_id: id,
+                }),
+            });
+        }
+    }
 
-    let project_chunks = indexed.chunks.iter().map(to_code_chunk).collect::<Vec<_>>();
-    if project_chunks.is_empty() {
-        return Ok(Vec::new());
+    if tier == RetrievalTier::HybridRerank {
+        if deadline.expired() {
+            degraded = Some(Degraded::Deadline);
+        } else {
+            out = rerank_results(shared_reranker(), query, out, deadline.remaining());
+        }
     }
+    let pinned = pinned_items(cwd, project_path, query, &candidates.chunks);
+    Ok(ScopedSearch {
+        items: inject_pinned(pinned, out, top_k),
+        degraded,
+    })
+}
 
-    let chunk_map = project_chunks
+fn pinned_items(
+    cwd: &Path,
+    project_path: &str,
+    query: &str,
+    chunks: &[CodeChunk],
+) -> Vec<SearchResultItem> {
+    projects::load_pins(cwd, project_path)
         .iter()
-        .map(|c| (c.id.clone(), c))
+        .filter(|pin| pin_matches(&pin.query_pattern, query))
+        .filter_map(|pin| {
+            let resolved = resolve_pin(pin, chunks);
+            if resolved.is_none() {
+                tracing::debug!(pin = pin.id, "pinned result no longer resolves");
+            }
+            resolved
+        })
+        .filter_map(|pin| {
+            let content = pin.content.or_else(|| {
+                read_line_range(
+                    &Path::new(project_path).join(&pin.file),
+                    pin.start_line,
+                    pin.end_line,
+                )
+            })?;
+            let symbol = pin.symbol.unwrap_or_default();
+            Some(SearchResultItem {
+                function: if symbol.is_empty() {
+                    "pinned".to_string()
+                } else {
+                    symbol.clone()
+                },
+                start_line: pin.start_line,
+                end_line: pin.end_line,
+                code_block: focused_snippet(&content, &[], SNIPPET_MAX_LINES, SNIPPET_MAX_CHARS),
+                highlights: Vec::new(),
+                token_count: None,
+                detail: None,
+                pinned: true,
+                explain: Some(ResultExplanation {
+                    chunk_id: format!(
+                        "{}:{}:{}:{}",
+                        pin.file, pin.start_line, pin.end_line, symbol
+                    ),
+                    fused_score: 0.0,
+                    lexical_rank: None,
+                    semantic_rank: None,
+                    exact_symbol: false,
+                    feedback_boost: 0.0,
+                    diversity_penalty: 0.0,
+                    priors: Vec::new(),
+                }),
+                file: pin.file,
+            })
+        })
+        .collect()
+}
+
+fn inject_pinned(
+    mut pinned: Vec<SearchResultItem>,
+    mut organic: Vec<SearchResultItem>,
+    top_k: usize,
+) -> Vec<SearchResultItem> {
+    if pinned.is_empty() {
+        return organic;
+    }
+    organic.retain(|item| {
+        !pinned.iter().any(|pin| {
+            same_file(&item.file, &pin.file)
+                && pin.start_line <= item.end_line
+                && pin.end_line >= item.start_line
+        })
+    });
+    pinned.extend(organic);
+    pinned.truncate(top_k.max(1));
+    pinned
+}
+
+pub(crate) fn read_line_range(path: &Path, start_line: usize, end_line: usize) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let lines = content
+        .lines()
+        .skip(start_line.saturating_sub(1))
+        .take(end_line.saturating_sub(start_line) + 1)
+        .collect::<Vec<_>>();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+pub fn group_results_by_file(result: SearchCodeResult) -> SearchCodeResult {
+    let mut groups: Vec<FileResultGroup> = Vec::new();
+    for item in result.items {
+        let range = FileResultRange {
+            function: item.function,
+            start_line: item.start_line,
+            end_line: item.end_line,
+            code_block: item.code_block,
+            highlights: item.highlights,
+            token_count: item.token_count,
+            detail: item.detail,
+            pinned: item.pinned,
+            explain: item.explain,
+        };
+        match groups.iter_mut().find(|group| group.file == item.file) {
+            Some(group) => group.ranges.push(range),
+            None => groups.push(FileResultGroup {
+                file: item.file,
+                ranges: vec![range],
+            }),
+        }
+    }
+    for group in &mut groups {
+        group
+            .ranges
+            .sort_by_key(|range| (range.start_line, range.end_line));
+    }
+    SearchCodeResult {
+        items: Vec::new(),
+        budget: result.budget,
+        groups,
+        degraded: result.degraded,
+    }
+}
+
+pub async fn ranked_candidates(
+    cwd: &Path,
+    project_path: &str,
+    engine: &Arc<EmbeddingEngine>,
+    query: &str,
+    top_k: usize,
+) -> anyhow::Result<RankedCandidates> {
+    let mut candidates = collect_candidates(
+        cwd,
+        project_path,
+        engine,
+        query,
+        top_k,
+        RetrievalTier::from_env(),
+        &SearchDeadline::unbounded(),
+    )
+    .await?;
+    // Ranking only looks at candidate chunks and the first chunk of their files, so the rest of
+    // the project is dropped before the tuner re-ranks this set once per trial.
+    let candidate_files = {
+        let chunk_map = candidates.chunk_map();
+        candidates
+            .lexical_ids
+            .iter()
+            .chain(&candidates.semantic_ids)
+            .chain(&candidates.exact_symbol_id)
+            .filter_map(|id| chunk_map.get(id).map(|c| c.file_path.clone()))
+            .collect::<HashSet<_>>()
+    };
+    candidates
+        .chunks
+        .retain(|c| candidate_files.contains(&c.file_path));
+    candidates.lexical_highlights.clear();
+    candidates.query_vector = None;
+    Ok(RankedCandidates {
+        project_path: project_path.to_string(),
+        feedback: feedback::boosts_for_query(cwd, project_path, query),
+        candidates,
+    })
+}
+
+fn rank_candidates(
+    project_path: &str,
+    candidates: &CandidateSet,
+    chunk_map: &HashMap<String, &CodeChunk>,
+    feedback: &HashMap<String, f32>,
+    options: &SearchOptions,
+    weights: &FusionWeights,
+    top_k: usize,
+) -> Ranking {
+    let mut fused = fuse_ranked(
+        &candidates.lexical_ids,
+        &candidates.semantic_ids,
+        candidates.exact_symbol_id.as_deref(),
+        weights,
+    );
+    let mut prior_components = prior_contributions(
+        &RankingPriors::from_env(),
+        &candidates.chunks,
+        chunk_map,
+        &fused,
+        options.prefer_tests,
+    );
+    add_proximity_contributions(
+        &mut prior_components,
+        project_path,
+        options,
+        chunk_map,
+        &fused,
+    );
+    let prior_totals = prior_components
+        .iter()
+        .map(|(id, parts)| (id.clone(), parts.iter().map(|(_, w)| w).sum::<f32>()))
         .collect::<HashMap<_, _>>();
+    apply_boosts(&mut fused, &prior_totals, weights);
+    let feedback_applied = apply_boosts(&mut fused, feedback, weights);
+
+    let pool = fused
+        .into_iter()
+        .filter_map(|scored| {
+            let chunk = chunk_map.get(&scored.id)?;
+            Some(DiversityCandidate::new(
+                scored,
+                &chunk.file_path,
+                &chunk.content,
+            ))
+        })
+        .take(top_k.max(1).saturating_mul(DIVERSITY_POOL_FACTOR))
+        .collect::<Vec<_>>();
+    Ranking {
+        diversified: mmr_select(pool, top_k.max(1), &DiversityConfig::from_env()),
+        prior_components,
+        feedback_applied,
+    }
+}
+
+async fn collect_candidates(
+    cwd: &Path,
+    project_path: &str,
+    engine: &Arc<EmbeddingEngine>,
+    query: &str,
+    top_k: usize,
+    tier: RetrievalTier,
+    deadline: &SearchDeadline,
+) -> anyhow::Result<CandidateSet> {
+    let indexed = projects::load_project_index(cwd, project_path)
+        .ok_or_else(|| anyhow::anyhow!("project not indexed"))?;
 
-    let exact_symbol_hit = project_chunks
+    let chunks = indexed.chunks.iter().map(to_code_chunk).collect::<Vec<_>>();
+    let mut candidates = CandidateSet {
+        chunks: Vec::new(),
+        exact_symbol_id: None,
+        lexical_ids: Vec::new(),
+        semantic_ids: Vec::new(),
+        lexical_highlights: HashMap::new(),
+        query_vector: None,
+        degraded: None,
+    };
+    if chunks.is_empty() {
+        return Ok(candidates);
+    }
+
+    candidates.exact_symbol_id = chunks
         .iter()
         .find(|c| {
             c.symbol
@@ -82,64 +605,154 @@ pub async fn scoped_project_results(
 
     let defaults = RetrievalDefaults::default();
     let lexical_top_k = defaults.lexical_top_k.max(top_k.saturating_mul(4));
-    let tier = RetrievalTier::from_env();
 
-    let lexical_ids = lexical_ranked_ids(cwd, project_path, &project_chunks, query, lexical_top_k)?;
-    let mut ordered_ids = Vec::new();
-    if let Some(id) = exact_symbol_hit {
-        ordered_ids.push(id);
-    }
+    let Some(lexical_hits) =
+        lexical_ranked_hits(cwd, project_path, &chunks, query, lexical_top_k, deadline)?
+    else {
+        candidates.degraded = Some(Degraded::Deadline);
+        candidates.chunks = chunks;
+        return Ok(candidates);
+    };
+    candidates.lexical_highlights = lexical_hits
+        .iter()
+        .filter(|hit| !hit.highlights.is_empty())
+        .map(|hit| (hit.id.clone(), hit.highlights.clone()))
+        .collect();
+    candidates.lexical_ids = lexical_hits.into_iter().map(|hit| hit.id).collect();
 
-    match tier {
-        RetrievalTier::Fast => {
-            ordered_ids.extend(lexical_ids);
+    if tier != RetrievalTier::Fast && deadline.expired() {
+        candidates.degraded = Some(Degraded::Deadline);
+    } else if tier != RetrievalTier::Fast
+        && projects::vector_migration_in_progress(cwd, project_path)
+    {
+        tracing::debug!(
+            project = project_path,
+            "vector collection migration running, serving lexical results only"
+        );
+        candidates.degraded = Some(Degraded::Migration);
+    } else if tier != RetrievalTier::Fast {
+        candidates.query_vector = embed_query(engine, project_path, query).await;
+        if let Some(q) = candidates.query_vector.as_deref() {
+            match semantic_ranked_ids(
+                engine,
+                SemanticCorpus {
+                    cwd,
+                    indexed: &indexed,
+                    chunks: &chunks,
+                },
+                q,
+                &VectorField::ALL,
+                lexical_top_k,
+                deadline,
+            )
+            .await
+            {
+                Some(ids) => candidates.semantic_ids = ids,
+                None => candidates.degraded = Some(Degraded::Deadline),
+            }
         }
-        RetrievalTier::Hybrid | RetrievalTier::HybridRerank => {
-            let semantic_ids =
-                semantic_ranked_ids(project_path, &project_chunks, query, lexical_top_k).await;
-            let fused = rrf_fuse(&lexical_ids, &semantic_ids, 60, 1.0, 0.7);
-            ordered_ids.extend(fused.into_iter().map(|s| s.id));
+    }
+    candidates.chunks = chunks;
+    Ok(candidates)
+}
+
+fn prior_contributions(
+    priors: &RankingPriors,
+    chunks: &[CodeChunk],
+    chunk_map: &HashMap<String, &CodeChunk>,
+    fused: &[ScoredId],
+    prefer_tests: bool,
+) -> HashMap<String, Vec<(String, f32)>> {
+    let mut file_headers: HashMap<&str, &CodeChunk> = HashMap::new();
+    for chunk in chunks {
+        let entry = file_headers
+            .entry(chunk.file_path.as_str())
+            .or_insert(chunk);
+        if chunk.start_line < entry.start_line {
+            *entry = chunk;
         }
     }
+    fused
+        .iter()
+        .filter_map(|scored| {
+            let chunk = chunk_map.get(&scored.id)?;
+            let subject = PriorSubject {
+                file: &chunk.file_path,
+                symbol: chunk.symbol.as_deref(),
+                language: &chunk.language,
+                content: &chunk.content,
+                file_header: file_headers
+                    .get(chunk.file_path.as_str())
+                    .filter(|first| first.id != chunk.id)
+                    .map(|first| first.content.as_str()),
+            };
+            let parts = priors.contributions(&subject, prefer_tests);
+            (!parts.is_empty()).then(|| (scored.id.clone(), parts))
+        })
+        .collect()
+}
 
-    let mut dedup = HashMap::<String, ()>::new();
-    let mut out = Vec::new();
-    for id in ordered_ids {
-        if dedup.contains_key(&id) {
+fn add_proximity_contributions(
+    components: &mut HashMap<String, Vec<(String, f32)>>,
+    project_path: &str,
+    options: &SearchOptions,
+    chunk_map: &HashMap<String, &CodeChunk>,
+    fused: &[ScoredId],
+) {
+    let mut resolver = PackageResolver::new(project_path);
+    let context = ProximityContext::new(
+        &mut resolver,
+        options.context_path.as_deref(),
+        options.context_symbol.as_deref(),
+    );
+    if context.is_empty() {
+        return;
+    }
+    let weights = ProximityWeights::from_env();
+    for scored in fused {
+        let Some(chunk) = chunk_map.get(&scored.id) else {
             continue;
-        }
-        dedup.insert(id.clone(), ());
-        if let Some(chunk) = chunk_map.get(&id) {
-            out.push(SearchResultItem {
-                file: chunk.file_path.clone(),
-                function: chunk.symbol.clone().unwrap_or_else(|| "chunk".to_string()),
-                start_line: chunk.start_line,
-                end_line: chunk.end_line,
-                code_block: trim_snippet(&chunk.content, 120, 6000),
-            });
-            if out.len() >= top_k.max(1) {
-                break;
-            }
+        };
+        let parts =
+            context.contributions(&weights, &mut resolver, &chunk.file_path, &chunk.content);
+        if !parts.is_empty() {
+            components
+                .entry(scored.id.clone())
+                .or_default()
+                .extend(parts);
         }
     }
+}
 
-    if tier == RetrievalTier::HybridRerank {
-        out = rerank_results(query, out);
-    }
-    Ok(out)
+pub(crate) fn rank_positions(ids: &[String]) -> HashMap<&str, usize> {
+    ids.iter()
+        .enumerate()
+        .map(|(idx, id)| (id.as_str(), idx + 1))
+        .collect()
 }
 
-fn lexical_ranked_ids(
+fn lexical_ranked_hits(
     cwd: &Path,
     project_path: &str,
     chunks: &[CodeChunk],
     query: &str,
     top_k: usize,
-) -> anyhow::Result<Vec<String>> {
+    deadline: &SearchDeadline,
+) -> anyhow::Result<Option<Vec<LexicalHit>>> {
+    Ok(open_lexical_index(cwd, project_path, chunks, deadline)?
+        .map(|index| index.search_hits(query, top_k).unwrap_or_default()))
+}
+
+pub(crate) fn open_lexical_index(
+    cwd: &Path,
+    project_path: &str,
+    chunks: &[CodeChunk],
+    deadline: &SearchDeadline,
+) -> anyhow::Result<Option<TantivyLexicalIndex>> {
     let on_disk_dir = projects::project_lexical_index_dir(cwd, project_path);
     if on_disk_dir.join("meta.json").exists() {
         match TantivyLexicalIndex::open_or_create_on_disk(&on_disk_dir) {
-            Ok(index) => return Ok(index.search_ids(query, top_k).unwrap_or_default()),
+            Ok(index) => return Ok(Some(index)),
             Err(err) => tracing::warn!(
                 project = project_path,
                 error = %err,
@@ -150,74 +763,315 @@ fn lexical_ranked_ids(
 
     let mut index = TantivyLexicalIndex::new_in_memory()?;
     for chunk in chunks {
+        if deadline.expired() {
+            tracing::debug!(
+                project = project_path,
+                "search deadline reached while rebuilding lexical index"
+            );
+            return Ok(None);
+        }
         index.add_chunk(chunk)?;
     }
     index.commit()?;
-    Ok(index.search_ids(query, top_k).unwrap_or_default())
+    Ok(Some(index))
 }
 
-async fn semantic_ranked_ids(
+// Embedding backends block (ONNX inference, remote HTTP with retry backoff), so the query
+// path hands them to the blocking pool instead of stalling a runtime worker.
+pub(crate) async fn embed_blocking(
+    engine: &Arc<EmbeddingEngine>,
+    project_path: &str,
+    texts: Vec<String>,
+    as_queries: bool,
+) -> anyhow::Result<Vec<Vec<f32>>> {
+    let engine = engine.clone();
+    let project_path = project_path.to_string();
+    tokio::task::spawn_blocking(move || {
+        if as_queries {
+            engine.embed_queries(&project_path, &texts)
+        } else {
+            engine.embed_documents(&project_path, &texts)
+        }
+    })
+    .await?
+}
+
+pub(crate) async fn embed_query(
+    engine: &Arc<EmbeddingEngine>,
     project_path: &str,
-    chunks: &[CodeChunk],
     query: &str,
-    top_k: usize,
-) -> Vec<String> {
-    let engine = EmbeddingEngine::new(EmbeddingConfig::default());
-    let query_vector = match engine.embed_batch(&[query.to_string()]) {
-        Ok(v) => v,
+) -> Option<Vec<f32>> {
+    match embed_blocking(engine, project_path, vec![query.to_string()], true).await {
+        Ok(vectors) => vectors.into_iter().next(),
         Err(err) => {
             tracing::warn!(project = project_path, error = %err, "query embedding failed");
-            return Vec::new();
+            None
         }
-    };
-    let Some(q) = query_vector.first() else {
-        return Vec::new();
-    };
+    }
+}
+
+pub(crate) fn corpus_texts(chunks: &[CodeChunk]) -> Vec<String> {
+    chunks.iter().map(|chunk| chunk.content.clone()).collect()
+}
+
+pub(crate) fn stored_vectors_usable(engine: &EmbeddingEngine, indexed: &IndexedProject) -> bool {
+    indexed
+        .embedding_model
+        .as_deref()
+        .is_none_or(|stored| stored == engine.corpus_model_id(&indexed.project_path))
+}
+
+pub(crate) struct SemanticCorpus<'a> {
+    pub cwd: &'a Path,
+    pub indexed: &'a IndexedProject,
+    pub chunks: &'a [CodeChunk],
+}
 
-    if let Some(client) = qdrant_client_from_env() {
+pub(crate) async fn semantic_ranked_ids(
+    engine: &Arc<EmbeddingEngine>,
+    corpus: SemanticCorpus<'_>,
+    q: &[f32],
+    fields: &[VectorField],
+    top_k: usize,
+    deadline: &SearchDeadline,
+) -> Option<Vec<String>> {
+    let SemanticCorpus { cwd, indexed, .. } = corpus;
+    let project_path = indexed.project_path.as_str();
+    let weights = MultiVectorWeights::from_env();
+    let use_stored = stored_vectors_usable(engine, indexed);
+    if !use_stored {
+        tracing::warn!(
+            project = project_path,
+            stored_model = indexed.embedding_model.as_deref().unwrap_or_default(),
+            loaded_model = engine.corpus_model_id(project_path),
+            "stored vectors come from a different embedding model, reindex to re-embed"
+        );
+    }
+    if use_stored && let Some(client) = qdrant_client_from_env() {
         let mut cfg = VectorSearchConfig {
-            collection: projects::project_vector_collection(project_path),
+            collection: projects::active_vector_collection(cwd, project_path),
             ..VectorSearchConfig::default()
         };
         cfg.vector_dim = q.len();
         let store = QdrantVectorStore::new(cfg);
-        match store.search_similar_ids(&client, q.clone(), top_k).await {
-            Ok(ids) if !ids.is_empty() => return ids,
-            Ok(_) => {}
-            Err(err) => tracing::warn!(
+        let lookups = async {
+            let mut rankings = Vec::with_capacity(fields.len());
+            for field in fields {
+                let ids = store
+                    .search_similar_ids(&client, *field, q.to_vec(), top_k)
+                    .await?;
+                rankings.push((*field, ids));
+            }
+            anyhow::Ok(rankings)
+        };
+        match tokio::time::timeout(deadline.remaining(), lookups).await {
+            Ok(Ok(rankings)) if rankings.iter().any(|(_, ids)| !ids.is_empty()) => {
+                let mut fused = fuse_field_rankings(&rankings, &weights, FIELD_FUSION_K);
+                fused.truncate(top_k);
+                return Some(fused);
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(err)) => tracing::warn!(
                 project = project_path,
                 error = %err,
                 "semantic qdrant lookup failed, falling back to local cosine"
             ),
+            Err(_) => {
+                tracing::debug!(project = project_path, "search deadline reached in qdrant");
+                return None;
+            }
         }
     }
 
-    let texts = chunks.iter().map(|c| c.content.clone()).collect::<Vec<_>>();
-    let vectors = match engine.embed_batch(&texts) {
-        Ok(v) => v,
-        Err(err) => {
-            tracing::warn!(
-                project = project_path,
-                error = %err,
-                "chunk embedding failed for local semantic fallback"
-            );
-            return Vec::new();
-        }
+    let template = EmbeddingTemplate::from_env().with_root(project_path);
+    let config = LocalVectorConfig::from_env();
+    let stamp = local_index_stamp(engine, &template, cwd, project_path, config);
+    let build = local_index_build(engine, &corpus, template, config, stamp);
+    let Ok(built) = tokio::time::timeout(deadline.remaining(), build).await else {
+        tracing::debug!(
+            project = project_path,
+            "search deadline reached while the local vector index builds"
+        );
+        return None;
+    };
+    let Some(index) = built else {
+        return Some(Vec::new());
     };
-    let mut scored = chunks
+    let rankings = fields
         .iter()
-        .zip(vectors.iter())
-        .map(|(chunk, vec)| (cosine_similarity(q, vec), chunk.id.clone()))
+        .map(|field| {
+            let ids = index
+                .search(*field, q, top_k)
+                .unwrap_or_else(|err| {
+                    tracing::warn!(
+                        project = project_path,
+                        error = %err,
+                        "local vector rescoring failed"
+                    );
+                    Vec::new()
+                })
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>();
+            (*field, ids)
+        })
+        .collect::<Vec<_>>();
+    let mut fused = fuse_field_rankings(&rankings, &weights, FIELD_FUSION_K);
+    fused.truncate(top_k);
+    Some(fused)
+}
+
+type LocalIndexBuild = Shared<BoxFuture<'static, Option<Arc<LocalVectorIndex>>>>;
+type LocalIndexCache = Mutex<HashMap<String, (u64, LocalIndexBuild)>>;
+
+fn local_indexes() -> &'static LocalIndexCache {
+    static INDEXES: OnceLock<LocalIndexCache> = OnceLock::new();
+    INDEXES.get_or_init(Default::default)
+}
+
+fn local_index_stamp(
+    engine: &EmbeddingEngine,
+    template: &EmbeddingTemplate,
+    cwd: &Path,
+    project_path: &str,
+    config: LocalVectorConfig,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    engine.corpus_model_id(project_path).hash(&mut hasher);
+    template.version().hash(&mut hasher);
+    config.quantization.as_str().hash(&mut hasher);
+    projects::project_index_version(cwd, project_path).hash(&mut hasher);
+    hasher.finish()
+}
+
+// The build runs as its own task and is shared by every query for the same stamp, so a query
+// that hits its deadline leaves the embedding work running for the next one to pick up.
+fn local_index_build(
+    engine: &Arc<EmbeddingEngine>,
+    corpus: &SemanticCorpus<'_>,
+    template: EmbeddingTemplate,
+    config: LocalVectorConfig,
+    stamp: u64,
+) -> LocalIndexBuild {
+    let project_path = corpus.indexed.project_path.clone();
+    let mut guard = local_indexes().lock().expect("local vector indexes");
+    if let Some((cached, build)) = guard.get(&project_path)
+        && *cached == stamp
+        && !matches!(build.peek(), Some(None))
+    {
+        return build.clone();
+    }
+    let path = projects::project_storage_dir(corpus.cwd, &project_path)
+        .join("local-vectors")
+        .join(format!("{}-{stamp:016x}.f32", std::process::id()));
+    let engine = engine.clone();
+    let chunks = corpus.chunks.to_vec();
+    let project = project_path.clone();
+    let build = tokio::task::spawn_blocking(move || {
+        match build_local_index(&engine, &project, &chunks, &template, config, &path) {
+            Ok(index) => {
+                tracing::debug!(
+                    project,
+                    quantization = config.quantization.as_str(),
+                    memory_bytes = index.memory_bytes(),
+                    full_precision_bytes = index.full_precision_bytes(),
+                    "built local vector index"
+                );
+                Some(Arc::new(index))
+            }
+            Err(err) => {
+                tracing::warn!(
+                    project,
+                    error = %err,
+                    "failed to build local vector index for semantic fallback"
+                );
+                None
+            }
+        }
+    })
+    .map(|joined| joined.ok().flatten())
+    .boxed()
+    .shared();
+    guard.insert(project_path, (stamp, build.clone()));
+    build
+}
+
+fn build_local_index(
+    engine: &EmbeddingEngine,
+    project_path: &str,
+    chunks: &[CodeChunk],
+    template: &EmbeddingTemplate,
+    config: LocalVectorConfig,
+    path: &Path,
+) -> anyhow::Result<LocalVectorIndex> {
+    if engine.needs_corpus(project_path) {
+        engine.fit_corpus(project_path, &corpus_texts(chunks));
+    }
+    let mut vectors = Vec::with_capacity(chunks.len());
+    for batch in chunks.chunks(LOCAL_EMBED_BATCH) {
+        vectors.extend(embed_chunk_views(batch, template, |texts| {
+            engine.embed_documents(project_path, texts)
+        })?);
+    }
+    engine.flush_cache();
+    let ids = chunks.iter().map(|chunk| chunk.id.clone()).collect();
+    LocalVectorIndex::build(path, config, ids, &vectors)
+}
+
+async fn semantic_best_window(
+    engine: &Arc<EmbeddingEngine>,
+    project_path: &str,
+    q: &[f32],
+    chunk: &CodeChunk,
+    query: &str,
+) -> Vec<HighlightSpan> {
+    if chunk.content.lines().count() <= SNIPPET_MAX_LINES
+        && chunk.content.len() <= SNIPPET_MAX_CHARS
+    {
+        return Vec::new();
+    }
+    let windows = line_windows(
+        &chunk.content,
+        SEMANTIC_WINDOW_LINES,
+        SEMANTIC_WINDOW_LINES / 2,
+    );
+    let texts = windows
+        .iter()
+        .map(|(_, _, text)| text.clone())
         .collect::<Vec<_>>();
-    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
-    scored
+    let Ok(vectors) = embed_blocking(engine, project_path, texts, false).await else {
+        return Vec::new();
+    };
+    windows
         .into_iter()
-        .take(top_k)
-        .map(|(_, id)| id)
-        .collect::<Vec<_>>()
+        .zip(vectors.iter())
+        .map(|(window, vec)| (cosine_similarity(q, vec), window))
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, (start, end, text))| {
+            vec![HighlightSpan {
+                start,
+                end,
+                terms: matched_query_terms(&text, query),
+            }]
+        })
+        .unwrap_or_default()
+}
+
+fn to_search_highlights(chunk: &CodeChunk, spans: &[HighlightSpan]) -> Vec<SearchHighlight> {
+    let node_lines = chunk.end_line.saturating_sub(chunk.start_line) + 1;
+    let leading = chunk.content.lines().count().saturating_sub(node_lines);
+    let to_file_line = |offset: usize| (chunk.start_line + offset).saturating_sub(leading).max(1);
+    spans
+        .iter()
+        .map(|span| SearchHighlight {
+            start_line: to_file_line(span.start),
+            end_line: to_file_line(span.end),
+            terms: span.terms.clone(),
+        })
+        .collect()
 }
 
-fn qdrant_client_from_env() -> Option<Qdrant> {
+pub(crate) fn qdrant_client_from_env() -> Option<Qdrant> {
     let url = std::env::var("QDRANT_URL").ok()?;
     if url.trim().is_empty() {
         return None;
@@ -243,7 +1097,7 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     dot / (na.sqrt() * nb.sqrt())
 }
 
-fn to_code_chunk(chunk: &projects::IndexedChunk) -> CodeChunk {
+pub(crate) fn to_code_chunk(chunk: &projects::IndexedChunk) -> CodeChunk {
     CodeChunk {
         id: chunk_stable_id(chunk),
         fingerprint: chunk_stable_id(chunk),
@@ -268,7 +1122,7 @@ fn chunk_stable_id(chunk: &projects::IndexedChunk) -> String {
     )
 }
 
-fn language_from_path(path: &str) -> String {
+pub(crate) fn language_from_path(path: &str) -> String {
     if path.ends_with(".rs") {
         "rust".to_string()
     } else if path.ends_with(".c") || path.ends_with(".h") {
@@ -311,92 +1165,82 @@ fn language_from_path(path: &str) -> String {
     }
 }
 
-fn trim_snippet(content: &str, max_lines: usize, max_chars: usize) -> String {
-    let mut out = String::new();
-    for (idx, line) in content.lines().enumerate() {
-        if idx >= max_lines {
-            out.push_str("\n... (truncated)");
-            break;
-        }
-        if !out.is_empty() {
-            out.push('\n');
-        }
-        out.push_str(line);
-        if out.len() > max_chars {
-            out.truncate(max_chars);
-            out.push_str("\n... (truncated)");
-            break;
-        }
-    }
-    if out.is_empty() {
-        content.chars().take(max_chars).collect()
-    } else {
-        out
-    }
+fn shared_reranker() -> &'static CrossEncoderReranker {
+    static RERANKER: OnceLock<CrossEncoderReranker> = OnceLock::new();
+    RERANKER.get_or_init(|| CrossEncoderReranker::new(RerankerConfig::default()))
 }
 
-fn rerank_results(query: &str, items: Vec<SearchResultItem>) -> Vec<SearchResultItem> {
+fn rerank_results(
+    reranker: &CrossEncoderReranker,
+    query: &str,
+    items: Vec<SearchResultItem>,
+    max_budget: Duration,
+) -> Vec<SearchResultItem> {
     if items.len() <= 1 {
         return items;
     }
 
-    let top_n = std::env::var("MCP_RERANK_TOP_N")
-        .ok()
-        .and_then(|v| v.parse::<usize>().ok())
-        .unwrap_or(20)
-        .max(1);
-    let rerank_count = items.len().min(top_n);
-    let engine = EmbeddingEngine::new(EmbeddingConfig::default());
-    let query_vecs = match engine.embed_batch(&[query.to_string()]) {
-        Ok(v) => v,
-        Err(_) => return items,
-    };
-    let Some(query_vec) = query_vecs.first() else {
-        return items;
-    };
-
-    let to_rank = items
+    let documents = items
         .iter()
-        .take(rerank_count)
-        .map(|i| i.code_block.clone())
+        .map(|i| format!("{} {}\n{}", i.file, i.function, i.code_block))
         .collect::<Vec<_>>();
-    let cand_vecs = match engine.embed_batch(&to_rank) {
-        Ok(v) => v,
-        Err(_) => return items,
+    let outcome = match reranker.rerank_within(query, &documents, max_budget) {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            tracing::warn!(error = %err, "cross-encoder rerank skipped");
+            return items;
+        }
     };
+    if outcome.budget_exhausted {
+        tracing::debug!(scored = outcome.scored, "rerank latency budget exhausted");
+    }
 
-    let mut iter = items.into_iter();
-    let head = iter.by_ref().take(rerank_count).collect::<Vec<_>>();
-    let tail = iter.collect::<Vec<_>>();
-    let mut scored = head
+    let mut slots = items.into_iter().map(Some).collect::<Vec<_>>();
+    outcome
+        .order
         .into_iter()
-        .zip(cand_vecs)
-        .map(|(item, vec)| (cosine_similarity(query_vec, &vec), item))
-        .collect::<Vec<_>>();
-    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
-    let mut reranked = scored.into_iter().map(|(_, item)| item).collect::<Vec<_>>();
-    reranked.extend(tail);
-    reranked
+        .filter_map(|idx| slots.get_mut(idx).and_then(Option::take))
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use common::SearchCodeResult;
+    use common::{CodeChunk, SearchCodeResult, SearchResultItem};
+    use embeddings::{CrossEncoderReranker, RerankerConfig};
     use lru::LruCache;
-    use std::num::NonZeroUsize;
+    use search_core::HighlightSpan;
+    use std::{num::NonZeroUsize, time::Duration};
     use tokio::sync::Mutex;
 
-    use super::{RetrievalTier, cache_key, cache_lookup, cache_store, cosine_similarity};
+    use super::{
+        RetrievalTier, SearchDeadline, SearchOptions, cache_key, cache_lookup, cache_store,
+        cosine_similarity, group_results_by_file, rerank_results, to_search_highlights,
+    };
 
     #[tokio::test]
     async fn cache_roundtrip() {
         let cache = Mutex::new(LruCache::new(NonZeroUsize::new(8).expect("non-zero")));
-        let key = cache_key("/tmp/project", "hello", 5);
-        let payload = SearchCodeResult { items: Vec::new() };
+        let key = cache_key("/tmp/project", 1, 0, "hello", 5, &SearchOptions::default());
+        let payload = SearchCodeResult {
+            items: Vec::new(),
+            budget: None,
+            groups: Vec::new(),
+            degraded: false,
+        };
 
         assert!(cache_lookup(&cache, &key).await.is_none());
         cache_store(&cache, key.clone(), payload.clone()).await;
         assert_eq!(cache_lookup(&cache, &key).await, Some(payload));
+        let boosted = cache_key("/tmp/project", 1, 7, "hello", 5, &SearchOptions::default());
+        assert!(cache_lookup(&cache, &boosted).await.is_none());
+    }
+
+    #[test]
+    fn search_deadline_expires_and_unbounded_never_does() {
+        assert!(SearchDeadline::from_request(Some(0)).expired());
+        assert!(!SearchDeadline::after(Duration::from_secs(60)).expired());
+        assert!(!SearchDeadline::unbounded().expired());
+        assert_eq!(SearchDeadline::unbounded().remaining(), Duration::MAX);
     }
 
     #[test]
@@ -405,6 +1249,30 @@ mod tests {
         assert!((cosine_similarity(&v, &v) - 1.0).abs() < 0.0001);
     }
 
+    #[test]
+    fn highlights_map_to_file_lines_past_leading_comments() {
+        let chunk = CodeChunk {
+            id: "c1".to_string(),
+            fingerprint: "c1".to_string(),
+            file_path: "src/lib.rs".to_string(),
+            language: "rust".to_string(),
+            symbol: Some("f".to_string()),
+            start_line: 11,
+            end_line: 13,
+            start_char: 0,
+            end_char: 0,
+            content: "// about f\nfn f() {\n    hit();\n}".to_string(),
+        };
+        let spans = vec![HighlightSpan {
+            start: 2,
+            end: 2,
+            terms: vec!["hit".to_string()],
+        }];
+        let highlights = to_search_highlights(&chunk, &spans);
+        assert_eq!(highlights[0].start_line, 12);
+        assert_eq!(highlights[0].end_line, 12);
+    }
+
     #[test]
     fn retrieval_tier_defaults_to_hybrid() {
         // SAFETY: test-scoped env mutation for this key.
@@ -413,4 +1281,75 @@ mod tests {
         }
         assert_eq!(RetrievalTier::from_env(), RetrievalTier::Hybrid);
     }
+
+    #[test]
+    fn cross_encoder_rerank_moves_joint_match_to_top() {
+        let reranker = CrossEncoderReranker::new(RerankerConfig {
+            model_path: "/tmp/does-not-exist-rerank.onnx".to_string(),
+            allow_test_backend: true,
+            ..RerankerConfig::default()
+        });
+        let item = |file: &str, function: &str, code: &str| SearchResultItem {
+            file: file.to_string(),
+            function: function.to_string(),
+            start_line: 1,
+            end_line: 1,
+            code_block: code.to_string(),
+            highlights: Vec::new(),
+            token_count: None,
+            detail: None,
+            pinned: false,
+            explain: None,
+        };
+        let items = vec![
+            item(
+                "src/date_fmt.rs",
+                "format_date",
+                "fn format_date(d: Date) -> String",
+            ),
+            item(
+                "src/date.rs",
+                "iso_to_date",
+                "fn iso_to_date(raw: &str) -> Date",
+            ),
+        ];
+        let reranked = rerank_results(&reranker, "iso to date", items, Duration::MAX);
+        assert_eq!(reranked[0].function, "iso_to_date");
+        assert_eq!(reranked.len(), 2);
+    }
+
+    #[test]
+    fn grouping_keeps_file_order_by_best_rank_and_sorts_ranges() {
+        let item = |file: &str, start_line: usize| SearchResultItem {
+            file: file.to_string(),
+            function: format!("f{start_line}"),
+            start_line,
+            end_line: start_line + 2,
+            code_block: String::new(),
+            highlights: Vec::new(),
+            token_count: None,
+            detail: None,
+            pinned: false,
+            explain: None,
+        };
+        let grouped = group_results_by_file(SearchCodeResult {
+            items: vec![
+                item("src/b.rs", 40),
+                item("src/a.rs", 5),
+                item("src/b.rs", 10),
+            ],
+            budget: None,
+            groups: Vec::new(),
+            degraded: false,
+        });
+        assert!(grouped.items.is_empty());
+        assert_eq!(grouped.groups.len(), 2);
+        assert_eq!(grouped.groups[0].file, "src/b.rs");
+        let starts = grouped.groups[0]
+            .ranges
+            .iter()
+            .map(|r| r.start_line)
+            .collect::<Vec<_>>();
+        assert_eq!(starts, vec![10, 40]);
+    }
 }
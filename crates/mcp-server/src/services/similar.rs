@@ -0,0 +1,301 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use common::{
+    CodeChunk, FindSimilarParams, FindSimilarResult, ResultExplanation, SearchResultItem,
+    SimilarSource, projects,
+};
+use embeddings::EmbeddingEngine;
+use search_core::{
+    EmbeddingTemplate, FusionWeights, RetrievalDefaults, ScoredId, VectorField, fuse_ranked,
+    highlight::focused_snippet,
+    same_file,
+    vector::{QdrantVectorStore, VectorSearchConfig},
+};
+
+use crate::services::search::{
+    Degraded, SNIPPET_MAX_CHARS, SNIPPET_MAX_LINES, SearchDeadline, SemanticCorpus, embed_blocking,
+    language_from_path, open_lexical_index, qdrant_client_from_env, rank_positions,
+    read_line_range, semantic_ranked_ids, stored_vectors_usable, to_code_chunk,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarSearch {
+    pub result: FindSimilarResult,
+    pub degraded: Option<Degraded>,
+}
+
+pub async fn find_similar(
+    cwd: &Path,
+    project_path: &str,
+    engine: &Arc<EmbeddingEngine>,
+    params: &FindSimilarParams,
+    deadline: &SearchDeadline,
+) -> anyhow::Result<Option<SimilarSearch>> {
+    let indexed = projects::load_project_index(cwd, project_path)
+        .ok_or_else(|| anyhow::anyhow!("project not indexed"))?;
+    let chunks = indexed.chunks.iter().map(to_code_chunk).collect::<Vec<_>>();
+    let Some(source) = resolve_source(project_path, &chunks, params) else {
+        return Ok(None);
+    };
+
+    let top_k = params.top_k.max(1);
+    let pool = RetrievalDefaults::default()
+        .lexical_top_k
+        .max(top_k.saturating_mul(4))
+        + 1;
+    let mut degraded = None;
+    let lexical_only = projects::vector_migration_in_progress(cwd, project_path);
+
+    let mut lexical_ids = Vec::new();
+    if params.more_like_this || lexical_only {
+        match open_lexical_index(cwd, project_path, &chunks, deadline)? {
+            Some(index) => {
+                lexical_ids = index
+                    .similar_hits(&source, pool)?
+                    .into_iter()
+                    .map(|hit| hit.id)
+                    .collect();
+            }
+            None => degraded = Some(Degraded::Deadline),
+        }
+    }
+
+    let mut semantic_ids = Vec::new();
+    if lexical_only {
+        degraded = Some(Degraded::Migration);
+    } else if deadline.expired() {
+        degraded = Some(Degraded::Deadline);
+    } else {
+        let stored = if stored_vectors_usable(engine, &indexed) {
+            stored_vector(cwd, project_path, &source.id).await
+        } else {
+            None
+        };
+        let vector = match stored {
+            Some(vector) => Some(vector),
+            None => {
+                let text = EmbeddingTemplate::from_env()
+                    .with_root(project_path)
+                    .render(
+                        &source,
+                        std::fs::read_to_string(&source.file_path).ok().as_deref(),
+                    );
+                embed_blocking(engine, project_path, vec![text], false)
+                    .await
+                    .map_err(|err| {
+                        tracing::warn!(project = project_path, error = %err, "source embedding failed")
+                    })
+                    .ok()
+                    .and_then(|vectors| vectors.into_iter().next())
+            }
+        };
+        if let Some(q) = vector.as_deref() {
+            match semantic_ranked_ids(
+                engine,
+                SemanticCorpus {
+                    cwd,
+                    indexed: &indexed,
+                    chunks: &chunks,
+                },
+                q,
+                &[VectorField::Code],
+                pool,
+                deadline,
+            )
+            .await
+            {
+                Some(ids) => semantic_ids = ids,
+                None => degraded = Some(Degraded::Deadline),
+            }
+        }
+    }
+
+    let weights = projects::load_retrieval_profile(cwd, project_path)
+        .map(|profile| FusionWeights::from(&profile))
+        .unwrap_or_default();
+    let lexical_ranks = rank_positions(&lexical_ids);
+    let semantic_ranks = rank_positions(&semantic_ids);
+    let chunk_map = chunks
+        .iter()
+        .map(|c| (c.id.as_str(), c))
+        .collect::<HashMap<_, _>>();
+
+    let items = fuse_ranked(&lexical_ids, &semantic_ids, None, &weights)
+        .into_iter()
+        .filter_map(|ScoredId { id, score }| {
+            let chunk = chunk_map.get(id.as_str())?;
+            (!overlaps_source(chunk, &source)).then(|| SearchResultItem {
+                file: chunk.file_path.clone(),
+                function: chunk.symbol.clone().unwrap_or_else(|| "chunk".to_string()),
+                start_line: chunk.start_line,
+                end_line: chunk.end_line,
+                code_block: focused_snippet(
+                    &chunk.content,
+                    &[],
+                    SNIPPET_MAX_LINES,
+                    SNIPPET_MAX_CHARS,
+                ),
+                highlights: Vec::new(),
+                token_count: None,
+                detail: None,
+                pinned: false,
+                explain: Some(ResultExplanation {
+                    fused_score: score,
+                    lexical_rank: lexical_ranks.get(id.as_str()).copied(),
+                    semantic_rank: semantic_ranks.get(id.as_str()).copied(),
+                    exact_symbol: false,
+                    feedback_boost: 0.0,
+                    diversity_penalty: 0.0,
+                    priors: Vec::new(),
+                    chunk_id: id,
+                }),
+            })
+        })
+        .take(top_k)
+        .collect();
+
+    Ok(Some(SimilarSearch {
+        result: FindSimilarResult {
+            source: SimilarSource {
+                chunk_id: source.id.clone(),
+                file: source.file_path.clone(),
+                function: source.symbol.clone().unwrap_or_else(|| "chunk".to_string()),
+                start_line: source.start_line,
+                end_line: source.end_line,
+            },
+            items,
+            degraded: degraded.is_some(),
+        },
+        degraded,
+    }))
+}
+
+fn resolve_source(
+    project_path: &str,
+    chunks: &[CodeChunk],
+    params: &FindSimilarParams,
+) -> Option<CodeChunk> {
+    if let Some(chunk_id) = params.chunk_id.as_deref().filter(|id| !id.is_empty()) {
+        return chunks.iter().find(|c| c.id == chunk_id).cloned();
+    }
+    let path = params.path.as_deref().filter(|p| !p.is_empty())?;
+    let start = params.line_start.unwrap_or(1).max(1);
+    let end = params.line_end.unwrap_or(start).max(start);
+
+    let best = chunks
+        .iter()
+        .filter(|c| same_file(&c.file_path, path))
+        .filter_map(|c| {
+            let overlap = c.end_line.min(end) as isize - c.start_line.max(start) as isize;
+            (overlap >= 0).then_some((overlap, c))
+        })
+        .max_by(|(a_overlap, a), (b_overlap, b)| {
+            a_overlap.cmp(b_overlap).then_with(|| {
+                let a_span = a.end_line - a.start_line;
+                let b_span = b.end_line - b.start_line;
+                b_span.cmp(&a_span)
+            })
+        })
+        .map(|(_, c)| c.clone());
+    if best.is_some() {
+        return best;
+    }
+
+    let content = read_line_range(&Path::new(project_path).join(path), start, end)?;
+    Some(CodeChunk {
+        id: format!("{path}:{start}:{end}:"),
+        fingerprint: String::new(),
+        file_path: path.to_string(),
+        language: language_from_path(path),
+        symbol: None,
+        start_line: start,
+        end_line: end,
+        start_char: 0,
+        end_char: content.len(),
+        content,
+    })
+}
+
+fn overlaps_source(chunk: &CodeChunk, source: &CodeChunk) -> bool {
+    chunk.id == source.id
+        || (same_file(&chunk.file_path, &source.file_path)
+            && chunk.start_line <= source.end_line
+            && source.start_line <= chunk.end_line)
+}
+
+async fn stored_vector(cwd: &Path, project_path: &str, chunk_id: &str) -> Option<Vec<f32>> {
+    let client = qdrant_client_from_env()?;
+    let store = QdrantVectorStore::new(VectorSearchConfig {
+        collection: projects::active_vector_collection(cwd, project_path),
+        ..VectorSearchConfig::default()
+    });
+    match store
+        .stored_vector(&client, chunk_id, VectorField::Code)
+        .await
+    {
+        Ok(vector) => vector,
+        Err(err) => {
+            tracing::warn!(
+                project = project_path,
+                error = %err,
+                "stored vector lookup failed, embedding source chunk"
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{CodeChunk, FindSimilarParams};
+
+    use super::{overlaps_source, resolve_source};
+
+    fn chunk(file: &str, start: usize, end: usize, symbol: &str) -> CodeChunk {
+        CodeChunk {
+            id: format!("{file}:{start}:{end}:{symbol}"),
+            fingerprint: String::new(),
+            file_path: file.to_string(),
+            language: "rust".to_string(),
+            symbol: Some(symbol.to_string()),
+            start_line: start,
+            end_line: end,
+            start_char: 0,
+            end_char: 0,
+            content: String::new(),
+        }
+    }
+
+    #[test]
+    fn source_resolves_by_chunk_id_or_best_overlapping_range() {
+        let chunks = vec![
+            chunk("/repo/src/lib.rs", 1, 40, "Parser"),
+            chunk("/repo/src/lib.rs", 10, 14, "parse"),
+            chunk("/repo/src/main.rs", 10, 14, "main"),
+        ];
+        let by_id = resolve_source(
+            "/repo",
+            &chunks,
+            &FindSimilarParams {
+                chunk_id: Some("/repo/src/main.rs:10:14:main".to_string()),
+                ..FindSimilarParams::default()
+            },
+        );
+        assert_eq!(by_id.map(|c| c.symbol), Some(Some("main".to_string())));
+
+        let by_range = resolve_source(
+            "/repo",
+            &chunks,
+            &FindSimilarParams {
+                path: Some("src/lib.rs".to_string()),
+                line_start: Some(11),
+                line_end: Some(13),
+                ..FindSimilarParams::default()
+            },
+        )
+        .expect("source");
+        assert_eq!(by_range.symbol.as_deref(), Some("parse"));
+        assert!(overlaps_source(&chunks[0], &by_range));
+        assert!(!overlaps_source(&chunks[2], &by_range));
+    }
+}
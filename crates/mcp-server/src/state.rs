@@ -1,4 +1,5 @@
 use common::ports::RuntimePorts;
+use embeddings::{EmbeddingCache, EmbeddingConfig, EmbeddingEngine, TokenCounter};
 use indexer::telemetry::IndexerTelemetry;
 use lru::LruCache;
 use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
@@ -10,6 +11,8 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::{Mutex, RwLock};
 
+use crate::services::feedback::SessionSearches;
+
 #[derive(Clone)]
 pub struct AppState {
     pub metrics: PrometheusHandle,
@@ -22,6 +25,9 @@ pub struct AppState {
     pub indexer_telemetry: Arc<IndexerTelemetry>,
     pub indexing_runtime: Arc<IndexingRuntimeState>,
     pub search_latencies_ms: Arc<Mutex<VecDeque<u128>>>,
+    pub token_counter: Arc<TokenCounter>,
+    pub search_sessions: Arc<SessionSearches>,
+    pub embedder: Arc<EmbeddingEngine>,
     shutting_down: Arc<AtomicBool>,
 }
 
@@ -32,17 +38,24 @@ impl AppState {
     ) -> anyhow::Result<Self> {
         let handle = PrometheusBuilder::new().install_recorder()?;
         let api_token = std::env::var("MCP_API_TOKEN").ok();
+        let cwd = std::env::current_dir()?;
+        let embedder = EmbeddingEngine::new(EmbeddingConfig::default())
+            .with_cache(EmbeddingCache::from_env(&cwd.join(".codivex")))
+            .with_corpus_dir(Some(cwd.join(".codivex").join("corpus-stats")));
         Ok(Self {
             metrics: handle,
             api_token,
             runtime_ports,
             port_conflicts_resolved,
             pid: std::process::id(),
-            cwd: std::env::current_dir()?,
+            cwd,
             query_cache: Arc::new(Mutex::new(LruCache::new(cache_capacity_from_env()))),
             indexer_telemetry: Arc::new(IndexerTelemetry::default()),
             indexing_runtime: Arc::new(IndexingRuntimeState::default()),
             search_latencies_ms: Arc::new(Mutex::new(VecDeque::new())),
+            token_counter: Arc::new(TokenCounter::from_env()),
+            search_sessions: Arc::new(SessionSearches::default()),
+            embedder: Arc::new(embedder),
             shutting_down: Arc::new(AtomicBool::new(false)),
         })
     }
@@ -66,6 +79,9 @@ impl AppState {
             indexer_telemetry: Arc::new(IndexerTelemetry::default()),
             indexing_runtime: Arc::new(IndexingRuntimeState::default()),
             search_latencies_ms: Arc::new(Mutex::new(VecDeque::new())),
+            token_counter: Arc::new(TokenCounter::char_estimate(4)),
+            search_sessions: Arc::new(SessionSearches::default()),
+            embedder: Arc::new(EmbeddingEngine::new(EmbeddingConfig::default())),
             shutting_down: Arc::new(AtomicBool::new(false)),
         }
     }
@@ -90,6 +106,21 @@ impl AppState {
         (p50, p95)
     }
 
+    pub fn spawn_embedder_warm_up(&self) {
+        let embedder = self.embedder.clone();
+        tokio::task::spawn_blocking(move || {
+            match embedder.warm_up() {
+                Ok(ms) => tracing::info!(warm_up_ms = ms, "embedding engine ready"),
+                Err(err) => tracing::warn!(error = %err, "embedding engine unavailable"),
+            }
+            metrics::gauge!("embedding_ready").set(if embedder.readiness().ready {
+                1.0
+            } else {
+                0.0
+            });
+        });
+    }
+
     pub fn begin_shutdown(&self) {
         self.shutting_down.store(true, Ordering::SeqCst);
     }
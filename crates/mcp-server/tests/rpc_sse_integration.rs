@@ -35,6 +35,8 @@ fn setup_indexed_project_state() -> AppState {
             end_line: 58,
             content: "fn iso_to_date(input: &str) -> String { input.to_string() }".to_string(),
         }],
+        embedding_template_version: None,
+        embedding_model: None,
     };
     let _ = common::projects::save_project_index(&tmp, &indexed);
     state
@@ -82,6 +84,8 @@ fn setup_dual_project_state() -> AppState {
                 end_line: 1,
                 content: "fn iso_to_date(input: &str) -> String { input.to_string() }".to_string(),
             }],
+            embedding_template_version: None,
+            embedding_model: None,
         },
     );
     let _ = common::projects::save_project_index(
@@ -98,6 +102,8 @@ fn setup_dual_project_state() -> AppState {
                 end_line: 1,
                 content: "fn save_user(name: &str) -> bool { !name.is_empty() }".to_string(),
             }],
+            embedding_template_version: None,
+            embedding_model: None,
         },
     );
     state
@@ -143,6 +149,41 @@ async fn search_code_rpc_returns_result_payload() {
     assert_eq!(items[0]["function"], "iso_to_date");
 }
 
+#[tokio::test]
+async fn search_code_packs_results_into_max_tokens_budget() {
+    let app = app::router(setup_indexed_project_state());
+    let req = Request::builder()
+        .method("POST")
+        .uri("/mcp")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "searchCode",
+                "params": { "query": "iso_to_date", "top_k": 1, "maxTokens": 8 }
+            })
+            .to_string(),
+        ))
+        .expect("request");
+
+    let res = app.oneshot(req).await.expect("response");
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.expect("bytes");
+    let json: serde_json::Value = serde_json::from_slice(&body).expect("json");
+    let items = json["result"]["items"]
+        .as_array()
+        .expect("search items array");
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["detail"], "truncated");
+    assert_eq!(json["result"]["budget"]["max_tokens"], 8);
+    assert_eq!(
+        json["result"]["budget"]["used_tokens"],
+        items[0]["token_count"]
+    );
+    assert_eq!(json["result"]["budget"]["estimator"], "chars");
+}
+
 #[tokio::test]
 async fn search_code_honors_repo_filter_for_project_scoped_results() {
     let app = app::router(setup_dual_project_state());
@@ -177,6 +218,540 @@ async fn search_code_honors_repo_filter_for_project_scoped_results() {
     assert_eq!(items[0]["function"], "save_user");
 }
 
+async fn rpc_call(state: &AppState, session: &str, body: serde_json::Value) -> serde_json::Value {
+    let req = Request::builder()
+        .method("POST")
+        .uri("/mcp")
+        .header("content-type", "application/json")
+        .header("mcp-session-id", session)
+        .body(Body::from(body.to_string()))
+        .expect("request");
+    let res = app::router(state.clone())
+        .oneshot(req)
+        .await
+        .expect("response");
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.expect("bytes");
+    serde_json::from_slice(&body).expect("json")
+}
+
+#[tokio::test]
+async fn open_location_after_search_records_feedback_boost() {
+    let state = setup_dual_project_state();
+    let first = rpc_call(
+        &state,
+        "session-a",
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "searchCode",
+            "params": { "query": "iso_to_date", "top_k": 1 }
+        }),
+    )
+    .await;
+    assert!(first["result"]["items"][0].get("explain").is_none());
+
+    let opened = rpc_call(
+        &state,
+        "session-a",
+        json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "openLocation",
+            "params": { "path": "src/date.rs", "line_start": 1, "line_end": 1 }
+        }),
+    )
+    .await;
+    assert_eq!(opened["result"]["line_start"], 1);
+
+    let second = rpc_call(
+        &state,
+        "session-b",
+        json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "searchCode",
+            "params": { "query": "iso_to_date conversion", "top_k": 1, "explain": true }
+        }),
+    )
+    .await;
+    let explain = &second["result"]["items"][0]["explain"];
+    assert_eq!(explain["chunk_id"], "src/date.rs:1:1:iso_to_date");
+    assert!(explain["feedback_boost"].as_f64().unwrap_or(0.0) > 0.0);
+}
+
+#[tokio::test]
+async fn search_code_groups_results_by_file_when_requested() {
+    let state = setup_dual_project_state();
+    let grouped = rpc_call(
+        &state,
+        "session-a",
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "searchCode",
+            "params": { "query": "iso_to_date", "top_k": 3, "groupByFile": true }
+        }),
+    )
+    .await;
+    let result = &grouped["result"];
+    assert_eq!(result["items"].as_array().map(Vec::len), Some(0));
+    assert_eq!(result["groups"][0]["file"], "src/date.rs");
+    assert_eq!(result["groups"][0]["ranges"][0]["start_line"], 1);
+}
+
+#[tokio::test]
+async fn test_file_prior_is_explained_and_flipped_by_prefer_tests() {
+    let mut state = AppState::for_tests();
+    let tmp = unique_tmp_dir("codivex-mcp-priors-test");
+    let _ = std::fs::create_dir_all(&tmp);
+    state.cwd = tmp.clone();
+    let project = tmp.join("repo").display().to_string();
+    let _ = common::projects::write_selected_project(&tmp, &project);
+    let chunk = |file: &str, symbol: &str, content: &str| IndexedChunk {
+        file: file.to_string(),
+        symbol: Some(symbol.to_string()),
+        start_line: 1,
+        end_line: 2,
+        content: content.to_string(),
+    };
+    let _ = common::projects::save_project_index(
+        &tmp,
+        &IndexedProject {
+            project_path: project,
+            files_scanned: 2,
+            chunks_extracted: 2,
+            indexed_at_unix: 1,
+            chunks: vec![
+                chunk(
+                    "src/date.rs",
+                    "iso_to_date",
+                    "fn iso_to_date(input: &str) -> String {\n    input.to_string() }",
+                ),
+                chunk(
+                    "tests/date_test.rs",
+                    "iso_to_date_roundtrip",
+                    "#[test]\nfn iso_to_date_roundtrip() { iso_to_date(\"2024-01-01\"); }",
+                ),
+            ],
+            embedding_template_version: None,
+            embedding_model: None,
+        },
+    );
+
+    let test_prior = |response: &serde_json::Value| {
+        response["result"]["items"]
+            .as_array()
+            .and_then(|items| {
+                items
+                    .iter()
+                    .find(|item| item["file"] == "tests/date_test.rs")
+                    .cloned()
+            })
+            .and_then(|item| item["explain"]["priors"][0]["delta"].as_f64())
+            .expect("test prior")
+    };
+    let default = rpc_call(
+        &state,
+        "session-a",
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "searchCode",
+            "params": { "query": "iso_to_date", "top_k": 2, "explain": true }
+        }),
+    )
+    .await;
+    assert!(test_prior(&default) < 0.0);
+
+    let preferred = rpc_call(
+        &state,
+        "session-a",
+        json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "searchCode",
+            "params": { "query": "iso_to_date", "top_k": 2, "explain": true, "preferTests": true }
+        }),
+    )
+    .await;
+    assert!(test_prior(&preferred) > 0.0);
+}
+
+#[tokio::test]
+async fn tuner_candidates_rank_like_a_live_search() {
+    let state = setup_dual_project_state();
+    let project = state.cwd.join("repo-alpha").display().to_string();
+    let chunk = |file: &str, symbol: &str, content: &str| IndexedChunk {
+        file: file.to_string(),
+        symbol: Some(symbol.to_string()),
+        start_line: 1,
+        end_line: 2,
+        content: content.to_string(),
+    };
+    let _ = common::projects::save_project_index(
+        &state.cwd,
+        &IndexedProject {
+            project_path: project.clone(),
+            files_scanned: 3,
+            chunks_extracted: 3,
+            indexed_at_unix: 1,
+            chunks: vec![
+                chunk(
+                    "tests/date_test.rs",
+                    "parse_date_roundtrip",
+                    "#[test]\nfn parse_date_roundtrip() { parse_date(\"2024-01-01\"); }",
+                ),
+                chunk(
+                    "src/date.rs",
+                    "parse_date",
+                    "fn parse_date(input: &str) -> Date {\n    Date::from(input) }",
+                ),
+                chunk(
+                    "src/format.rs",
+                    "format_date",
+                    "fn format_date(date: Date) -> String {\n    date.to_string() }",
+                ),
+            ],
+            embedding_template_version: None,
+            embedding_model: None,
+        },
+    );
+
+    let live = mcp_server::services::search::scoped_project_results(
+        &state.cwd,
+        &project,
+        &state.embedder,
+        "parse date",
+        3,
+    )
+    .await
+    .expect("live search");
+    let candidates = mcp_server::services::search::ranked_candidates(
+        &state.cwd,
+        &project,
+        &state.embedder,
+        "parse date",
+        3,
+    )
+    .await
+    .expect("candidates");
+    let live_files = live
+        .iter()
+        .map(|item| item.file.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        candidates.ranked_files(&search_core::FusionWeights::default(), 3),
+        live_files
+    );
+    assert_ne!(live_files.first(), Some(&"tests/date_test.rs"));
+}
+
+#[tokio::test]
+async fn context_path_prefers_results_from_the_same_package() {
+    let mut state = AppState::for_tests();
+    let tmp = unique_tmp_dir("codivex-mcp-proximity-test");
+    let repo = tmp.join("repo");
+    for krate in ["indexer", "ui"] {
+        let _ = std::fs::create_dir_all(repo.join(format!("crates/{krate}/src")));
+        let _ = std::fs::write(repo.join(format!("crates/{krate}/Cargo.toml")), "[package]");
+    }
+    state.cwd = tmp.clone();
+    let project = repo.display().to_string();
+    let _ = common::projects::write_selected_project(&tmp, &project);
+    let chunk = |krate: &str| IndexedChunk {
+        file: repo
+            .join(format!("crates/{krate}/src/registry.rs"))
+            .display()
+            .to_string(),
+        symbol: Some("parser_registry".to_string()),
+        start_line: 1,
+        end_line: 1,
+        content: "fn parser_registry() -> Registry { Registry::default() }".to_string(),
+    };
+    let _ = common::projects::save_project_index(
+        &tmp,
+        &IndexedProject {
+            project_path: project.clone(),
+            files_scanned: 2,
+            chunks_extracted: 2,
+            indexed_at_unix: 1,
+            chunks: vec![chunk("ui"), chunk("indexer")],
+            embedding_template_version: None,
+            embedding_model: None,
+        },
+    );
+
+    for (id, context) in [
+        (1, "crates/indexer/src/chunking.rs"),
+        (2, "crates/ui/src/app.rs"),
+    ] {
+        let response = rpc_call(
+            &state,
+            "session-a",
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "searchCode",
+                "params": { "query": "parser registry", "top_k": 2, "contextPath": context }
+            }),
+        )
+        .await;
+        let expected = format!(
+            "{project}/{}/registry.rs",
+            context.rsplit_once('/').expect("dir").0
+        );
+        assert_eq!(response["result"]["items"][0]["file"], expected);
+    }
+}
+
+#[tokio::test]
+async fn pinned_result_is_injected_first_for_matching_queries() {
+    let state = setup_dual_project_state();
+    let alpha = state.cwd.join("repo-alpha");
+    let _ = std::fs::write(
+        alpha.join("src/auth.rs"),
+        "fn check_auth(token: &str) -> bool {\n    !token.is_empty()\n}\n",
+    );
+    let alpha = alpha.display().to_string();
+    common::projects::add_pin(
+        &state.cwd,
+        &alpha,
+        common::projects::PinnedResult {
+            id: String::new(),
+            query_pattern: "auth checked".to_string(),
+            file: "src/auth.rs".to_string(),
+            symbol: None,
+            start_line: 1,
+            end_line: 3,
+            created_at_unix: 1,
+        },
+    )
+    .expect("pin");
+
+    let pinned = rpc_call(
+        &state,
+        "session-a",
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "searchCode",
+            "params": { "query": "where is auth checked iso_to_date", "top_k": 2 }
+        }),
+    )
+    .await;
+    let items = &pinned["result"]["items"];
+    assert_eq!(items[0]["file"], "src/auth.rs");
+    assert_eq!(items[0]["pinned"], true);
+    assert!(
+        items[0]["code_block"]
+            .as_str()
+            .unwrap_or("")
+            .contains("check_auth")
+    );
+    assert!(items[1].get("pinned").is_none());
+
+    let unpinned = rpc_call(
+        &state,
+        "session-a",
+        json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "searchCode",
+            "params": { "query": "iso_to_date", "top_k": 2 }
+        }),
+    )
+    .await;
+    assert_eq!(unpinned["result"]["items"][0]["file"], "src/date.rs");
+}
+
+#[tokio::test]
+async fn reindex_from_another_process_invalidates_cached_results() {
+    let state = setup_dual_project_state();
+    let alpha = state.cwd.join("repo-alpha").display().to_string();
+    let search = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "searchCode",
+        "params": { "query": "iso_to_date", "top_k": 1 }
+    });
+
+    let first = rpc_call(&state, "session-a", search.clone()).await;
+    assert_eq!(first["result"]["items"][0]["start_line"], 1);
+    let before = common::projects::index_generation(&state.cwd, &alpha);
+
+    let _ = common::projects::save_project_index(
+        &state.cwd,
+        &IndexedProject {
+            project_path: alpha.clone(),
+            files_scanned: 1,
+            chunks_extracted: 1,
+            indexed_at_unix: 2,
+            chunks: vec![IndexedChunk {
+                file: "src/date.rs".to_string(),
+                symbol: Some("iso_to_date".to_string()),
+                start_line: 7,
+                end_line: 9,
+                content: "fn iso_to_date(input: &str) -> String { input.to_string() }".to_string(),
+            }],
+            embedding_template_version: None,
+            embedding_model: None,
+        },
+    );
+    assert!(common::projects::index_generation(&state.cwd, &alpha) > before);
+
+    let second = rpc_call(&state, "session-a", search).await;
+    assert_eq!(second["result"]["items"][0]["start_line"], 7);
+}
+
+#[tokio::test]
+async fn exhausted_deadline_degrades_or_times_out_without_caching() {
+    let mut state = setup_dual_project_state();
+    state.embedder = std::sync::Arc::new(embeddings::EmbeddingEngine::new(
+        embeddings::EmbeddingConfig {
+            hashed_backend: embeddings::HashedBackendMode::Always,
+            ..embeddings::EmbeddingConfig::default()
+        },
+    ));
+    let search = |id: u64, query: &str, deadline: Option<u64>| {
+        let mut params = json!({ "query": query, "top_k": 1 });
+        if let Some(ms) = deadline {
+            params["deadlineMs"] = json!(ms);
+        }
+        json!({ "jsonrpc": "2.0", "id": id, "method": "searchCode", "params": params })
+    };
+
+    let partial = rpc_call(&state, "session-a", search(1, "iso_to_date", Some(0))).await;
+    assert_eq!(partial["result"]["degraded"], true);
+    assert_eq!(partial["result"]["items"][0]["file"], "src/date.rs");
+
+    let full = rpc_call(&state, "session-a", search(2, "iso_to_date", None)).await;
+    assert!(full["result"].get("degraded").is_none());
+    assert_eq!(full["result"]["items"][0]["file"], "src/date.rs");
+    let local_vectors_built = std::fs::read_dir(state.cwd.join(".codivex").join("storage"))
+        .expect("project storage")
+        .flatten()
+        .any(|entry| entry.path().join("local-vectors").is_dir());
+    assert!(local_vectors_built);
+
+    let timed_out = rpc_call(
+        &state,
+        "session-a",
+        search(3, "convert input into a date", Some(0)),
+    )
+    .await;
+    assert_eq!(timed_out["error"]["code"], -32011);
+}
+
+#[tokio::test]
+async fn lexical_only_search_during_migration_is_not_a_timeout() {
+    let state = setup_dual_project_state();
+    let alpha = state.cwd.join("repo-alpha").display().to_string();
+    let mut vectors = common::projects::load_vector_collection_state(&state.cwd, &alpha);
+    vectors.begin_migration(&alpha, "test-model", 1);
+    common::projects::save_vector_collection_state(&state.cwd, &alpha, &vectors)
+        .expect("start migration");
+
+    let missed = rpc_call(
+        &state,
+        "session-a",
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "searchCode",
+            "params": { "query": "quaternion slerp", "top_k": 1 }
+        }),
+    )
+    .await;
+    assert_eq!(missed["error"]["code"], -32010);
+}
+
+#[tokio::test]
+async fn find_similar_code_returns_sibling_implementations_without_source() {
+    let state = setup_dual_project_state();
+    let alpha = state.cwd.join("repo-alpha").display().to_string();
+    let chunk = |file: &str, symbol: &str, content: &str| IndexedChunk {
+        file: file.to_string(),
+        symbol: Some(symbol.to_string()),
+        start_line: 1,
+        end_line: 5,
+        content: content.to_string(),
+    };
+    let _ = common::projects::save_project_index(
+        &state.cwd,
+        &IndexedProject {
+            project_path: alpha,
+            files_scanned: 3,
+            chunks_extracted: 3,
+            indexed_at_unix: 2,
+            chunks: vec![
+                chunk(
+                    "src/http.rs",
+                    "retry_request",
+                    "fn retry_request() { for attempt in 0..max_attempts { backoff_delay(attempt); } }",
+                ),
+                chunk(
+                    "src/upload.rs",
+                    "retry_upload",
+                    "fn retry_upload() { for attempt in 0..max_attempts { backoff_delay(attempt); } }",
+                ),
+                chunk(
+                    "src/render.rs",
+                    "render_page",
+                    "fn render_page() { template.layout(html) }",
+                ),
+            ],
+            embedding_template_version: None,
+            embedding_model: None,
+        },
+    );
+
+    let by_location = rpc_call(
+        &state,
+        "session-a",
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "findSimilarCode",
+            "params": { "path": "src/http.rs", "lineStart": 2, "lineEnd": 3, "explain": true }
+        }),
+    )
+    .await;
+    let result = &by_location["result"];
+    assert_eq!(result["source"]["function"], "retry_request");
+    let items = result["items"].as_array().expect("items");
+    assert_eq!(items[0]["file"], "src/upload.rs");
+    assert!(items.iter().all(|item| item["file"] != "src/http.rs"));
+    assert!(items[0]["explain"]["lexical_rank"].is_number());
+
+    let by_id = rpc_call(
+        &state,
+        "session-a",
+        json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {
+                "name": "findSimilarCode",
+                "arguments": { "chunkId": result["source"]["chunk_id"] }
+            }
+        }),
+    )
+    .await;
+    assert_eq!(
+        by_id["result"]["structuredContent"]["items"][0]["file"],
+        "src/upload.rs"
+    );
+
+    let missing = rpc_call(
+        &state,
+        "session-a",
+        json!({ "jsonrpc": "2.0", "id": 3, "method": "findSimilarCode", "params": {} }),
+    )
+    .await;
+    assert_eq!(missing["error"]["code"], -32602);
+}
+
 #[tokio::test]
 async fn open_location_rpc_returns_path_and_lines() {
     let app = app::router(setup_indexed_project_state());
@@ -340,6 +915,7 @@ async fn tools_list_returns_search_and_open_location() {
     let tools = json["result"]["tools"].as_array().expect("tools array");
     assert!(tools.iter().any(|tool| tool["name"] == "searchCode"));
     assert!(tools.iter().any(|tool| tool["name"] == "openLocation"));
+    assert!(tools.iter().any(|tool| tool["name"] == "findSimilarCode"));
 }
 
 #[tokio::test]
@@ -502,3 +1078,34 @@ async fn websocket_fallback_supports_json_rpc_calls() {
 
     server.abort();
 }
+
+#[tokio::test]
+async fn ready_endpoint_reports_embedding_warm_up() {
+    let state = setup_indexed_project_state();
+    let ready = |state: AppState| async move {
+        let req = Request::builder()
+            .uri("/ready")
+            .body(Body::empty())
+            .expect("request");
+        let res = app::router(state).oneshot(req).await.expect("response");
+        let status = res.status();
+        let body = to_bytes(res.into_body(), usize::MAX).await.expect("bytes");
+        let json: serde_json::Value = serde_json::from_slice(&body).expect("json");
+        (status, json)
+    };
+
+    let (status, json) = ready(state.clone()).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(json["warmed_up"], false);
+
+    let _ = state.embedder.warm_up();
+    let (status, json) = ready(state.clone()).await;
+    assert_eq!(json["warmed_up"], true);
+    assert_eq!(json["ready"], json["error"].is_null());
+    let expected = if json["error"].is_null() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    assert_eq!(status, expected);
+}
@@ -0,0 +1,219 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    feedback::{jaccard, query_terms},
+    fusion::ScoredId,
+};
+
+const SAME_FILE_SIMILARITY: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiversityConfig {
+    pub lambda: f32,
+    pub max_per_file: usize,
+}
+
+impl Default for DiversityConfig {
+    fn default() -> Self {
+        Self {
+            lambda: 0.7,
+            max_per_file: 3,
+        }
+    }
+}
+
+impl DiversityConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            lambda: std::env::var("MCP_MMR_LAMBDA")
+                .ok()
+                .and_then(|v| v.parse::<f32>().ok())
+                .map(|v| v.clamp(0.0, 1.0))
+                .unwrap_or(defaults.lambda),
+            max_per_file: std::env::var("MCP_MAX_RESULTS_PER_FILE")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(defaults.max_per_file),
+        }
+    }
+}
+
+pub struct DiversityCandidate {
+    pub scored: ScoredId,
+    pub file: String,
+    pub terms: BTreeSet<String>,
+}
+
+impl DiversityCandidate {
+    pub fn new(scored: ScoredId, file: &str, content: &str) -> Self {
+        Self {
+            scored,
+            file: file.to_string(),
+            terms: query_terms(content),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiversifiedId {
+    pub scored: ScoredId,
+    pub penalty: f32,
+}
+
+pub fn mmr_select(
+    candidates: Vec<DiversityCandidate>,
+    limit: usize,
+    config: &DiversityConfig,
+) -> Vec<DiversifiedId> {
+    let max_score = candidates
+        .iter()
+        .map(|c| c.scored.score)
+        .fold(0.0f32, f32::max);
+    let lambda = config.lambda.clamp(0.0, 1.0);
+    let mut remaining = candidates;
+    let mut selected: Vec<DiversityCandidate> = Vec::new();
+    let mut penalties = Vec::new();
+    let mut per_file: HashMap<String, usize> = HashMap::new();
+
+    let below_cap = |per_file: &HashMap<String, usize>, c: &DiversityCandidate| {
+        config.max_per_file == 0
+            || per_file.get(&c.file).copied().unwrap_or(0) < config.max_per_file
+    };
+
+    while selected.len() < limit {
+        // Files over the cap only backfill once every other candidate is used, so a pool
+        // dominated by one file still fills `limit`.
+        let capped = !remaining.iter().any(|c| below_cap(&per_file, c));
+        let best = remaining
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| capped || below_cap(&per_file, c))
+            .map(|(idx, c)| {
+                let relevance = if max_score > 0.0 {
+                    c.scored.score / max_score
+                } else {
+                    0.0
+                };
+                let redundancy = selected
+                    .iter()
+                    .map(|s| similarity(c, s))
+                    .fold(0.0f32, f32::max);
+                let mmr = lambda * relevance - (1.0 - lambda) * redundancy;
+                (idx, mmr, (1.0 - lambda) * redundancy)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(&a.0)));
+        let Some((idx, _, penalty)) = best else {
+            break;
+        };
+        let chosen = remaining.remove(idx);
+        *per_file.entry(chosen.file.clone()).or_insert(0) += 1;
+        penalties.push(penalty);
+        selected.push(chosen);
+    }
+
+    selected
+        .into_iter()
+        .zip(penalties)
+        .map(|(c, penalty)| DiversifiedId {
+            scored: c.scored,
+            penalty,
+        })
+        .collect()
+}
+
+fn similarity(a: &DiversityCandidate, b: &DiversityCandidate) -> f32 {
+    let overlap = jaccard(&a.terms, &b.terms);
+    if a.file == b.file {
+        overlap.max(SAME_FILE_SIMILARITY)
+    } else {
+        overlap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DiversityCandidate, DiversityConfig, mmr_select};
+    use crate::fusion::ScoredId;
+
+    fn candidate(id: &str, score: f32, file: &str, content: &str) -> DiversityCandidate {
+        DiversityCandidate::new(
+            ScoredId {
+                id: id.to_string(),
+                score,
+            },
+            file,
+            content,
+        )
+    }
+
+    fn pool() -> Vec<DiversityCandidate> {
+        vec![
+            candidate("impl", 1.0, "src/date.rs", "impl DateParser { fn parse }"),
+            candidate("parse", 0.95, "src/date.rs", "fn parse(raw: &str) -> Date"),
+            candidate(
+                "parse_iso",
+                0.9,
+                "src/date.rs",
+                "fn parse_iso(raw: &str) -> Date",
+            ),
+            candidate(
+                "parse_rfc",
+                0.85,
+                "src/date.rs",
+                "fn parse_rfc(raw: &str) -> Date",
+            ),
+            candidate(
+                "format",
+                0.8,
+                "src/format.rs",
+                "fn format_date(d: Date) -> String",
+            ),
+        ]
+    }
+
+    #[test]
+    fn pure_relevance_keeps_fused_order_within_file_cap() {
+        let config = DiversityConfig {
+            lambda: 1.0,
+            max_per_file: 0,
+        };
+        let ids = mmr_select(pool(), 3, &config)
+            .into_iter()
+            .map(|d| d.scored.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["impl", "parse", "parse_iso"]);
+    }
+
+    #[test]
+    fn diversification_promotes_other_files_and_caps_per_file() {
+        let ids = mmr_select(pool(), 4, &DiversityConfig::default())
+            .into_iter()
+            .map(|d| d.scored.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["impl", "parse", "format", "parse_iso"]);
+    }
+
+    #[test]
+    fn capped_candidates_backfill_up_to_the_limit() {
+        let ids = mmr_select(pool(), 5, &DiversityConfig::default())
+            .into_iter()
+            .map(|d| d.scored.id)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec!["impl", "parse", "format", "parse_iso", "parse_rfc"]
+        );
+
+        let config = DiversityConfig {
+            lambda: 0.7,
+            max_per_file: 1,
+        };
+        let ids = mmr_select(pool(), 3, &config)
+            .into_iter()
+            .map(|d| d.scored.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(ids[..2], ["impl", "format"]);
+    }
+}
@@ -0,0 +1,277 @@
+use std::{collections::HashSet, path::Path};
+
+use common::CodeChunk;
+use sha2::{Digest, Sha256};
+
+use crate::multivector::qualified_name;
+
+pub const DEFAULT_EMBEDDING_TEMPLATE: &str = "path: {path}\nlanguage: {language}\nscope: {container}\nsymbol: {symbol}\nimports: {imports}\n{code}";
+pub const DEFAULT_EMBEDDING_TEMPLATE_VERSION: &str = "ctx-v1";
+
+const MAX_IMPORTS: usize = 8;
+const CONTAINER_KEYWORDS: &[&str] = &[
+    "impl",
+    "trait",
+    "struct",
+    "enum",
+    "mod",
+    "class",
+    "interface",
+    "namespace",
+    "module",
+    "object",
+    "extension",
+    "protocol",
+];
+const IMPORT_PREFIXES: &[&str] = &[
+    "use ", "pub use ", "import ", "from ", "#include", "using ", "require ",
+];
+const IMPORT_STOPWORDS: &[&str] = &[
+    "use", "pub", "import", "from", "as", "crate", "self", "super", "include", "using", "require",
+    "static", "type", "std",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddingTemplate {
+    template: String,
+    version: String,
+    root: Option<String>,
+}
+
+impl Default for EmbeddingTemplate {
+    fn default() -> Self {
+        Self {
+            template: DEFAULT_EMBEDDING_TEMPLATE.to_string(),
+            version: DEFAULT_EMBEDDING_TEMPLATE_VERSION.to_string(),
+            root: None,
+        }
+    }
+}
+
+impl EmbeddingTemplate {
+    pub fn new(template: &str) -> Self {
+        let template = template.replace("\\n", "\n");
+        if template == DEFAULT_EMBEDDING_TEMPLATE {
+            return Self::default();
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(template.as_bytes());
+        let digest = format!("{:x}", hasher.finalize());
+        Self {
+            version: format!("custom-{}", &digest[..12]),
+            template,
+            root: None,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let mut out = std::env::var("CODEVIX_EMBED_TEMPLATE")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| Self::new(&v))
+            .unwrap_or_default();
+        if let Some(version) = std::env::var("CODEVIX_EMBED_TEMPLATE_VERSION")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+        {
+            out.version = version.trim().to_string();
+        }
+        out
+    }
+
+    pub fn with_root(mut self, project_path: &str) -> Self {
+        self.root = Some(project_path.trim_end_matches(['/', '\\']).to_string());
+        self
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    pub fn render(&self, chunk: &CodeChunk, source: Option<&str>) -> String {
+        let path = self
+            .root
+            .as_deref()
+            .and_then(|root| chunk.file_path.strip_prefix(root))
+            .map(|rel| rel.trim_start_matches(['/', '\\']))
+            .unwrap_or(&chunk.file_path);
+        let container = source
+            .map(|src| enclosing_containers(src, chunk.start_line).join(" > "))
+            .unwrap_or_default();
+        let imports = source
+            .map(|src| relevant_imports(src, &chunk.content).join("; "))
+            .unwrap_or_default();
+        let symbol = chunk
+            .symbol
+            .as_deref()
+            .map(|s| qualified_name(path, Some(s)))
+            .unwrap_or_default();
+        let values = [
+            ("{path}", path),
+            ("{language}", chunk.language.as_str()),
+            ("{container}", container.as_str()),
+            ("{symbol}", symbol.as_str()),
+            ("{imports}", imports.as_str()),
+        ];
+
+        let mut lines = Vec::new();
+        for line in self.template.lines() {
+            if line.contains("{code}") {
+                lines.push(line.replace("{code}", &chunk.content));
+                continue;
+            }
+            let used = values
+                .iter()
+                .filter(|(key, _)| line.contains(key))
+                .collect::<Vec<_>>();
+            if !used.is_empty() && used.iter().all(|(_, value)| value.is_empty()) {
+                continue;
+            }
+            lines.push(used.iter().fold(line.to_string(), |acc, (key, value)| {
+                acc.replace(key, value)
+            }));
+        }
+        lines.join("\n")
+    }
+}
+
+fn enclosing_containers(source: &str, start_line: usize) -> Vec<String> {
+    let lines = source.lines().collect::<Vec<_>>();
+    let start = start_line.saturating_sub(1).min(lines.len());
+    let Some(mut indent) = lines[start..]
+        .iter()
+        .find(|line| !line.trim().is_empty())
+        .map(|line| indentation(line))
+    else {
+        return Vec::new();
+    };
+
+    let mut out = Vec::new();
+    for line in lines[..start].iter().rev() {
+        if indent == 0 {
+            break;
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() || indentation(line) >= indent {
+            continue;
+        }
+        indent = indentation(line);
+        if let Some(header) = container_header(trimmed) {
+            out.push(header);
+        }
+    }
+    out.reverse();
+    out
+}
+
+fn container_header(line: &str) -> Option<String> {
+    let mut words = line.split_whitespace().skip_while(|word| {
+        matches!(
+            *word,
+            "pub" | "pub(crate)" | "export" | "default" | "abstract" | "public" | "private"
+        ) || word.starts_with("pub(")
+    });
+    let keyword = words.next()?;
+    let keyword = keyword.split('<').next().unwrap_or(keyword);
+    if !CONTAINER_KEYWORDS.contains(&keyword) {
+        return None;
+    }
+    let header = line.split('{').next().unwrap_or(line).trim();
+    Some(header.trim_end_matches(':').trim().to_string())
+}
+
+fn indentation(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+fn relevant_imports(source: &str, content: &str) -> Vec<String> {
+    let words = identifiers(content).collect::<HashSet<_>>();
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| {
+            IMPORT_PREFIXES
+                .iter()
+                .any(|prefix| line.starts_with(prefix))
+                || line.contains("= require(")
+        })
+        .filter(|line| {
+            identifiers(line)
+                .filter(|ident| !IMPORT_STOPWORDS.contains(ident))
+                .any(|ident| words.contains(ident))
+        })
+        .map(|line| line.trim_end_matches(';').to_string())
+        .take(MAX_IMPORTS)
+        .collect()
+}
+
+fn identifiers(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.len() >= 3)
+}
+
+pub(crate) fn read_source(path: &str) -> Option<String> {
+    std::fs::read_to_string(Path::new(path)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use common::CodeChunk;
+
+    use super::{DEFAULT_EMBEDDING_TEMPLATE_VERSION, EmbeddingTemplate};
+
+    const SOURCE: &str = "use crate::models::User;\nuse std::collections::HashMap;\n\npub struct UserRepository {\n    pool: Pool,\n}\n\nimpl UserRepository {\n    pub fn save(&self, user: &User) {\n        self.pool.execute(user);\n    }\n}\n";
+
+    fn save_chunk() -> CodeChunk {
+        let content =
+            "    pub fn save(&self, user: &User) {\n        self.pool.execute(user);\n    }";
+        CodeChunk {
+            id: "/repo/src/users.rs:9:11:save".to_string(),
+            fingerprint: String::new(),
+            file_path: "/repo/src/users.rs".to_string(),
+            language: "rust".to_string(),
+            symbol: Some("save".to_string()),
+            start_line: 9,
+            end_line: 11,
+            start_char: 0,
+            end_char: content.len(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn default_template_prepends_path_scope_symbol_and_used_imports() {
+        let template = EmbeddingTemplate::default().with_root("/repo");
+        let text = template.render(&save_chunk(), Some(SOURCE));
+        assert_eq!(
+            text,
+            "path: src/users.rs\nlanguage: rust\nscope: impl UserRepository\nsymbol: users::save\nimports: use crate::models::User\n    pub fn save(&self, user: &User) {\n        self.pool.execute(user);\n    }"
+        );
+
+        let without_source = template.render(&save_chunk(), None);
+        assert!(without_source.starts_with(
+            "path: src/users.rs\nlanguage: rust\nsymbol: users::save\n    pub fn save"
+        ));
+    }
+
+    #[test]
+    fn custom_templates_get_their_own_version() {
+        assert_eq!(
+            EmbeddingTemplate::default().version(),
+            DEFAULT_EMBEDDING_TEMPLATE_VERSION
+        );
+        let custom = EmbeddingTemplate::new("{symbol}\\n{code}");
+        assert!(custom.version().starts_with("custom-"));
+        assert_ne!(
+            custom.version(),
+            EmbeddingTemplate::new("{path}\\n{code}").version()
+        );
+        assert_eq!(
+            custom.render(&save_chunk(), None).lines().next(),
+            Some("users::save")
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::{BTreeSet, HashMap};
+
+use common::projects::FeedbackEvent;
+
+const MIN_QUERY_SIMILARITY: f32 = 0.3;
+
+pub fn feedback_boosts(
+    query: &str,
+    events: &[FeedbackEvent],
+    now_unix_ms: u64,
+    half_life_ms: u64,
+) -> HashMap<String, f32> {
+    let half_life = half_life_ms.max(1) as f32;
+    let mut out = HashMap::new();
+    for (event, similarity) in relevant_feedback(query, events) {
+        let age = now_unix_ms.saturating_sub(event.unix_ms) as f32;
+        let decay = 0.5f32.powf(age / half_life);
+        *out.entry(event.chunk_id.clone()).or_insert(0.0) += similarity * decay;
+    }
+    out
+}
+
+pub fn relevant_feedback<'a>(
+    query: &str,
+    events: &'a [FeedbackEvent],
+) -> impl Iterator<Item = (&'a FeedbackEvent, f32)> {
+    let terms = query_terms(query);
+    events.iter().filter_map(move |event| {
+        let similarity = jaccard(&terms, &query_terms(&event.query));
+        (similarity >= MIN_QUERY_SIMILARITY).then_some((event, similarity))
+    })
+}
+
+pub fn query_similarity(a: &str, b: &str) -> f32 {
+    jaccard(&query_terms(a), &query_terms(b))
+}
+
+pub(crate) fn query_terms(query: &str) -> BTreeSet<String> {
+    let mut out = BTreeSet::new();
+    for word in query.split(|c: char| !c.is_alphanumeric()) {
+        let mut current = String::new();
+        let mut prev_lower = false;
+        for ch in word.chars() {
+            if ch.is_uppercase() && prev_lower && !current.is_empty() {
+                out.insert(std::mem::take(&mut current));
+            }
+            prev_lower = ch.is_lowercase() || ch.is_ascii_digit();
+            current.extend(ch.to_lowercase());
+        }
+        if !current.is_empty() {
+            out.insert(current);
+        }
+    }
+    out
+}
+
+pub(crate) fn jaccard(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / a.union(b).count() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use common::projects::FeedbackEvent;
+
+    use super::{feedback_boosts, query_similarity};
+
+    fn event(query: &str, chunk_id: &str, unix_ms: u64) -> FeedbackEvent {
+        FeedbackEvent {
+            query: query.to_string(),
+            chunk_id: chunk_id.to_string(),
+            file: "src/date.rs".to_string(),
+            rank: 3,
+            unix_ms,
+        }
+    }
+
+    #[test]
+    fn similar_queries_share_identifier_terms() {
+        assert!(query_similarity("isoToDate", "iso to date") > 0.9);
+        assert_eq!(query_similarity("parse config", "iso to date"), 0.0);
+    }
+
+    #[test]
+    fn repeated_choices_accumulate_and_decay() {
+        let events = vec![
+            event("iso to date", "a", 1_000),
+            event("convert iso to date", "a", 1_000),
+            event("iso to date", "b", 0),
+            event("load config file", "c", 1_000),
+        ];
+        let boosts = feedback_boosts("iso to date", &events, 1_000, 1_000);
+        assert!(boosts["a"] > 1.5);
+        assert!((boosts["b"] - 0.5).abs() < 1e-4);
+        assert!(!boosts.contains_key("c"));
+    }
+}
@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+
 use ahash::AHashMap;
+use common::projects::RetrievalProfile;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ScoredId {
@@ -6,6 +9,59 @@ pub struct ScoredId {
     pub score: f32,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FusionWeights {
+    pub k: usize,
+    pub lexical: f32,
+    pub vector: f32,
+    pub exact_symbol: f32,
+}
+
+impl Default for FusionWeights {
+    fn default() -> Self {
+        Self {
+            k: 60,
+            lexical: 1.0,
+            vector: 0.7,
+            exact_symbol: 2.0,
+        }
+    }
+}
+
+impl FusionWeights {
+    pub fn boost_delta(&self, boost: f32) -> f32 {
+        boost / (self.k as f32 + 1.0)
+    }
+}
+
+impl From<&RetrievalProfile> for FusionWeights {
+    fn from(profile: &RetrievalProfile) -> Self {
+        Self {
+            k: profile.rrf_k.max(1),
+            lexical: profile.lexical_weight,
+            vector: profile.vector_weight,
+            exact_symbol: profile.exact_symbol_weight,
+        }
+    }
+}
+
+pub fn apply_boosts(
+    fused: &mut [ScoredId],
+    boosts: &HashMap<String, f32>,
+    weights: &FusionWeights,
+) -> HashMap<String, f32> {
+    let mut applied = HashMap::new();
+    for scored in fused.iter_mut() {
+        if let Some(boost) = boosts.get(&scored.id).filter(|b| **b != 0.0) {
+            let delta = weights.boost_delta(*boost);
+            scored.score += delta;
+            applied.insert(scored.id.clone(), delta);
+        }
+    }
+    fused.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+    applied
+}
+
 pub fn rrf_fuse(
     lexical_ids: &[String],
     vector_ids: &[String],
@@ -13,29 +69,53 @@ pub fn rrf_fuse(
     w_lex: f32,
     w_vec: f32,
 ) -> Vec<ScoredId> {
-    let kf = k as f32;
+    fuse_ranked(
+        lexical_ids,
+        vector_ids,
+        None,
+        &FusionWeights {
+            k,
+            lexical: w_lex,
+            vector: w_vec,
+            exact_symbol: 0.0,
+        },
+    )
+}
+
+pub fn fuse_ranked(
+    lexical_ids: &[String],
+    vector_ids: &[String],
+    exact_symbol_id: Option<&str>,
+    weights: &FusionWeights,
+) -> Vec<ScoredId> {
+    let kf = weights.k as f32;
     let mut scores: AHashMap<String, f32> = AHashMap::new();
 
     for (rank, id) in lexical_ids.iter().enumerate() {
-        let rr = w_lex / (kf + (rank + 1) as f32);
+        let rr = weights.lexical / (kf + (rank + 1) as f32);
         *scores.entry(id.clone()).or_insert(0.0) += rr;
     }
     for (rank, id) in vector_ids.iter().enumerate() {
-        let rr = w_vec / (kf + (rank + 1) as f32);
+        let rr = weights.vector / (kf + (rank + 1) as f32);
         *scores.entry(id.clone()).or_insert(0.0) += rr;
     }
+    if let Some(id) = exact_symbol_id {
+        *scores.entry(id.to_string()).or_insert(0.0) += weights.exact_symbol / (kf + 1.0);
+    }
 
     let mut fused = scores
         .into_iter()
         .map(|(id, score)| ScoredId { id, score })
         .collect::<Vec<_>>();
-    fused.sort_by(|a, b| b.score.total_cmp(&a.score));
+    fused.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
     fused
 }
 
 #[cfg(test)]
 mod tests {
-    use super::rrf_fuse;
+    use std::collections::HashMap;
+
+    use super::{FusionWeights, apply_boosts, fuse_ranked, rrf_fuse};
 
     #[test]
     fn rrf_boosts_items_present_in_both_lists() {
@@ -44,4 +124,31 @@ mod tests {
         let fused = rrf_fuse(&lex, &vecs, 60, 1.0, 0.7);
         assert_eq!(fused[0].id, "b");
     }
+
+    #[test]
+    fn default_exact_symbol_weight_outranks_lexical_and_vector_leaders() {
+        let lex = vec!["a".to_string(), "s".to_string()];
+        let vecs = vec!["a".to_string(), "b".to_string()];
+        let fused = fuse_ranked(&lex, &vecs, Some("s"), &FusionWeights::default());
+        assert_eq!(fused[0].id, "s");
+
+        let no_boost = FusionWeights {
+            exact_symbol: 0.0,
+            ..FusionWeights::default()
+        };
+        let fused = fuse_ranked(&lex, &vecs, Some("s"), &no_boost);
+        assert_eq!(fused[0].id, "a");
+    }
+
+    #[test]
+    fn boosts_reorder_fused_results_and_report_deltas() {
+        let lex = vec!["a".to_string(), "b".to_string()];
+        let weights = FusionWeights::default();
+        let mut fused = fuse_ranked(&lex, &[], None, &weights);
+        let boosts = HashMap::from([("b".to_string(), 1.0f32), ("z".to_string(), 5.0)]);
+        let applied = apply_boosts(&mut fused, &boosts, &weights);
+        assert_eq!(fused[0].id, "b");
+        assert_eq!(applied.len(), 1);
+        assert!((applied["b"] - 1.0 / 61.0).abs() < 1e-6);
+    }
 }
@@ -0,0 +1,333 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightSpan {
+    pub start: usize,
+    pub end: usize,
+    pub terms: Vec<String>,
+}
+
+pub struct LineOffsets {
+    starts: Vec<usize>,
+}
+
+impl LineOffsets {
+    pub fn new(content: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        Self { starts }
+    }
+
+    pub fn line_of(&self, byte_offset: usize) -> usize {
+        self.starts.partition_point(|start| *start <= byte_offset) - 1
+    }
+}
+
+pub fn spans_from_line_terms(line_terms: &BTreeMap<usize, BTreeSet<String>>) -> Vec<HighlightSpan> {
+    let mut spans: Vec<HighlightSpan> = Vec::new();
+    for (line, terms) in line_terms {
+        if let Some(last) = spans.last_mut()
+            && *line <= last.end + 1
+        {
+            last.end = *line;
+            for term in terms {
+                if !last.terms.contains(term) {
+                    last.terms.push(term.clone());
+                }
+            }
+            continue;
+        }
+        spans.push(HighlightSpan {
+            start: *line,
+            end: *line,
+            terms: terms.iter().cloned().collect(),
+        });
+    }
+    spans
+}
+
+pub fn matched_query_terms(text: &str, query: &str) -> Vec<String> {
+    let haystack = text.to_lowercase();
+    let mut out = Vec::new();
+    for term in query_words(query) {
+        if haystack.contains(&term) && !out.contains(&term) {
+            out.push(term);
+        }
+    }
+    out
+}
+
+pub fn line_windows(
+    content: &str,
+    window_lines: usize,
+    stride: usize,
+) -> Vec<(usize, usize, String)> {
+    let lines = content.lines().collect::<Vec<_>>();
+    let window_lines = window_lines.max(1);
+    let stride = stride.max(1);
+    let mut out = Vec::new();
+    let mut start = 0usize;
+    while start < lines.len() {
+        let end = (start + window_lines).min(lines.len());
+        out.push((start, end - 1, lines[start..end].join("\n")));
+        if end == lines.len() {
+            break;
+        }
+        start += stride;
+    }
+    out
+}
+
+pub fn focused_snippet(
+    content: &str,
+    spans: &[HighlightSpan],
+    max_lines: usize,
+    max_chars: usize,
+) -> String {
+    let lines = content.lines().collect::<Vec<_>>();
+    let max_lines = max_lines.max(4);
+    let Some(focus) = densest_span(spans) else {
+        return trim_snippet(content, max_lines, max_chars);
+    };
+    let head_chars = lines
+        .iter()
+        .take(focus.end + 1)
+        .map(|l| l.len() + 1)
+        .sum::<usize>();
+    if focus.end < max_lines && head_chars <= max_chars {
+        return trim_snippet(content, max_lines, max_chars);
+    }
+
+    let signature = signature_line(&lines);
+    let mut budget = max_lines.saturating_sub(signature + 2).max(1);
+    loop {
+        let span_len = focus.end - focus.start + 1;
+        let lead = budget.saturating_sub(span_len) / 2;
+        let mut start = focus.start.saturating_sub(lead).max(signature + 1);
+        let end = (start + budget).min(lines.len());
+        start = end.saturating_sub(budget).max(signature + 1);
+
+        let mut out = lines[..=signature].join("\n");
+        if start > signature + 1 {
+            out.push_str(&format!("\n... ({} lines omitted)", start - signature - 1));
+        }
+        for line in &lines[start..end] {
+            out.push('\n');
+            out.push_str(line);
+        }
+        if end < lines.len() {
+            out.push_str("\n... (truncated)");
+        }
+        if out.len() <= max_chars || budget <= 1 {
+            if out.len() > max_chars {
+                let mut cut = max_chars;
+                while !out.is_char_boundary(cut) {
+                    cut -= 1;
+                }
+                out.truncate(cut);
+                out.push_str("\n... (truncated)");
+            }
+            return out;
+        }
+        budget /= 2;
+    }
+}
+
+pub fn trim_snippet(content: &str, max_lines: usize, max_chars: usize) -> String {
+    let mut out = String::new();
+    for (idx, line) in content.lines().enumerate() {
+        if idx >= max_lines {
+            out.push_str("\n... (truncated)");
+            break;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(line);
+        if out.len() > max_chars {
+            let mut cut = max_chars;
+            while !out.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            out.truncate(cut);
+            out.push_str("\n... (truncated)");
+            break;
+        }
+    }
+    if out.is_empty() {
+        content.chars().take(max_chars).collect()
+    } else {
+        out
+    }
+}
+
+pub fn signature_snippet(snippet: &str) -> String {
+    let lines = snippet.lines().collect::<Vec<_>>();
+    lines
+        .get(signature_line(&lines))
+        .map(|line| line.to_string())
+        .unwrap_or_default()
+}
+
+pub fn matched_lines_snippet(snippet: &str, terms: &[String]) -> Option<String> {
+    let terms = terms.iter().map(|t| t.to_lowercase()).collect::<Vec<_>>();
+    if terms.is_empty() {
+        return None;
+    }
+    let lines = snippet.lines().collect::<Vec<_>>();
+    let signature = signature_line(&lines);
+    let mut out = lines.get(signature)?.to_string();
+    let mut last = signature;
+    let mut matched = false;
+    for (idx, line) in lines.iter().enumerate().skip(signature + 1) {
+        if line.starts_with("... (") {
+            continue;
+        }
+        let lowered = line.to_lowercase();
+        if !terms.iter().any(|t| lowered.contains(t)) {
+            continue;
+        }
+        if idx > last + 1 {
+            out.push_str("\n...");
+        }
+        out.push('\n');
+        out.push_str(line);
+        last = idx;
+        matched = true;
+    }
+    if !matched {
+        return None;
+    }
+    if last + 1 < lines.len() {
+        out.push_str("\n...");
+    }
+    Some(out)
+}
+
+fn densest_span(spans: &[HighlightSpan]) -> Option<&HighlightSpan> {
+    spans.iter().max_by(|a, b| {
+        a.terms
+            .len()
+            .cmp(&b.terms.len())
+            .then_with(|| (b.end - b.start).cmp(&(a.end - a.start)))
+            .then_with(|| b.start.cmp(&a.start))
+    })
+}
+
+fn signature_line(lines: &[&str]) -> usize {
+    lines
+        .iter()
+        .take(12)
+        .position(|line| {
+            let t = line.trim_start();
+            !(t.is_empty()
+                || t.starts_with("//")
+                || t.starts_with('#')
+                || t.starts_with("/*")
+                || t.starts_with('*')
+                || t.starts_with("--")
+                || t.starts_with('@'))
+        })
+        .unwrap_or(0)
+}
+
+fn query_words(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() >= 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::{
+        HighlightSpan, LineOffsets, focused_snippet, line_windows, matched_lines_snippet,
+        matched_query_terms, signature_snippet, spans_from_line_terms,
+    };
+
+    fn long_function(body_lines: usize, hit_line: usize) -> String {
+        let mut out = vec!["/// docs".to_string(), "fn big_one() {".to_string()];
+        for idx in 0..body_lines {
+            if idx == hit_line {
+                out.push("    let parsed = iso_to_date(raw);".to_string());
+            } else {
+                out.push(format!("    let v{idx} = {idx};"));
+            }
+        }
+        out.push("}".to_string());
+        out.join("\n")
+    }
+
+    #[test]
+    fn adjacent_lines_merge_into_one_span() {
+        let mut map = BTreeMap::new();
+        map.insert(3, BTreeSet::from(["iso".to_string()]));
+        map.insert(4, BTreeSet::from(["date".to_string()]));
+        map.insert(9, BTreeSet::from(["iso".to_string()]));
+        let spans = spans_from_line_terms(&map);
+        assert_eq!(spans.len(), 2);
+        assert_eq!((spans[0].start, spans[0].end), (3, 4));
+        assert_eq!(spans[0].terms, vec!["iso".to_string(), "date".to_string()]);
+    }
+
+    #[test]
+    fn focused_snippet_keeps_signature_and_far_match() {
+        let content = long_function(400, 300);
+        let hit = 302;
+        let snippet = focused_snippet(
+            &content,
+            &[HighlightSpan {
+                start: hit,
+                end: hit,
+                terms: vec!["iso".to_string()],
+            }],
+            40,
+            6000,
+        );
+        assert!(snippet.starts_with("/// docs\nfn big_one() {"));
+        assert!(snippet.contains("iso_to_date(raw)"));
+        assert!(snippet.contains("lines omitted"));
+        assert!(snippet.lines().count() <= 41);
+    }
+
+    #[test]
+    fn focused_snippet_without_highlights_is_head_trim() {
+        let content = long_function(200, 0);
+        let snippet = focused_snippet(&content, &[], 10, 6000);
+        assert_eq!(snippet.lines().count(), 11);
+        assert!(snippet.ends_with("... (truncated)"));
+    }
+
+    #[test]
+    fn condensed_snippets_keep_signature_and_matched_lines() {
+        let content = long_function(10, 6);
+        assert_eq!(signature_snippet(&content), "fn big_one() {");
+        let condensed =
+            matched_lines_snippet(&content, &["ISO".to_string()]).expect("matched lines");
+        assert_eq!(
+            condensed,
+            "fn big_one() {\n...\n    let parsed = iso_to_date(raw);\n..."
+        );
+        assert!(matched_lines_snippet(&content, &["absent".to_string()]).is_none());
+    }
+
+    #[test]
+    fn line_helpers_cover_offsets_windows_and_terms() {
+        let offsets = LineOffsets::new("a\nb\nc");
+        assert_eq!(
+            [0, 1, 2, 4, 99].map(|offset| offsets.line_of(offset)),
+            [0, 0, 1, 2, 2]
+        );
+        let windows = line_windows("1\n2\n3\n4\n5", 2, 2);
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[2], (4, 4, "5".to_string()));
+        assert_eq!(
+            matched_query_terms("fn ISO_to_date()", "convert iso date"),
+            vec!["iso".to_string(), "date".to_string()]
+        );
+    }
+}
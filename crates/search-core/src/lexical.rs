@@ -1,14 +1,18 @@
 use anyhow::Result;
 use common::CodeChunk;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
 use tantivy::schema::Value;
 use tantivy::{
     Index, IndexReader, IndexWriter, TantivyDocument,
     collector::TopDocs,
-    query::QueryParser,
-    schema::{Field, STORED, STRING, Schema, TEXT},
+    query::{MoreLikeThisQuery, QueryParser},
+    schema::{Field, OwnedValue, STORED, STRING, Schema, TEXT},
+    tokenizer::TextAnalyzer,
 };
 
+use crate::highlight::{HighlightSpan, LineOffsets, spans_from_line_terms};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LexicalSearchConfig {
     pub default_top_k: usize,
@@ -20,6 +24,13 @@ impl Default for LexicalSearchConfig {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexicalHit {
+    pub id: String,
+    pub score: f32,
+    pub highlights: Vec<HighlightSpan>,
+}
+
 pub struct TantivyLexicalIndex {
     index: Index,
     reader: IndexReader,
@@ -78,6 +89,14 @@ impl TantivyLexicalIndex {
     }
 
     pub fn search_ids(&self, query: &str, top_k: usize) -> Result<Vec<String>> {
+        Ok(self
+            .search_hits(query, top_k)?
+            .into_iter()
+            .map(|hit| hit.id)
+            .collect())
+    }
+
+    pub fn search_hits(&self, query: &str, top_k: usize) -> Result<Vec<LexicalHit>> {
         let searcher = self.reader.searcher();
         let parser = QueryParser::for_index(
             &self.index,
@@ -86,20 +105,101 @@ impl TantivyLexicalIndex {
         let parsed = parser.parse_query(query)?;
         let docs = searcher.search(&parsed, &TopDocs::with_limit(top_k))?;
 
+        let mut terms = BTreeSet::new();
+        parsed.query_terms(&mut |term, _| {
+            if term.field() == self.fields.content
+                && let Some(text) = term.value().as_str()
+            {
+                terms.insert(text.to_string());
+            }
+        });
+        let mut analyzer = self.index.tokenizer_for_field(self.fields.content)?;
+
+        let mut out = Vec::new();
+        for (score, address) in docs {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let Some(id) = doc
+                .get_first(self.fields.id)
+                .and_then(|v| v.as_value().as_str())
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+            else {
+                continue;
+            };
+            let content = doc
+                .get_first(self.fields.content)
+                .and_then(|v| v.as_value().as_str())
+                .unwrap_or_default();
+            out.push(LexicalHit {
+                id,
+                score,
+                highlights: matched_line_spans(&mut analyzer, content, &terms),
+            });
+        }
+        Ok(out)
+    }
+
+    pub fn similar_hits(&self, chunk: &CodeChunk, top_k: usize) -> Result<Vec<LexicalHit>> {
+        let mut fields = vec![(
+            self.fields.content,
+            vec![OwnedValue::Str(chunk.content.clone())],
+        )];
+        if let Some(symbol) = chunk.symbol.as_deref().filter(|s| !s.is_empty()) {
+            fields.push((
+                self.fields.symbol,
+                vec![OwnedValue::Str(symbol.to_string())],
+            ));
+        }
+        let query = MoreLikeThisQuery::builder()
+            .with_min_doc_frequency(1)
+            .with_min_term_frequency(1)
+            .with_min_word_length(3)
+            .with_max_query_terms(32)
+            .with_document_fields(fields);
+        let searcher = self.reader.searcher();
+        let docs = searcher.search(&query, &TopDocs::with_limit(top_k))?;
+
         let mut out = Vec::new();
-        for (_, address) in docs {
+        for (score, address) in docs {
             let doc: TantivyDocument = searcher.doc(address)?;
-            if let Some(id_field) = doc.get_first(self.fields.id) {
-                let owned = id_field.as_value().as_str().unwrap_or_default().to_string();
-                if !owned.is_empty() {
-                    out.push(owned);
-                }
+            if let Some(id) = doc
+                .get_first(self.fields.id)
+                .and_then(|v| v.as_value().as_str())
+                .filter(|v| !v.is_empty())
+            {
+                out.push(LexicalHit {
+                    id: id.to_string(),
+                    score,
+                    highlights: Vec::new(),
+                });
             }
         }
         Ok(out)
     }
 }
 
+fn matched_line_spans(
+    analyzer: &mut TextAnalyzer,
+    content: &str,
+    terms: &BTreeSet<String>,
+) -> Vec<HighlightSpan> {
+    if terms.is_empty() || content.is_empty() {
+        return Vec::new();
+    }
+    let offsets = LineOffsets::new(content);
+    let mut line_terms = BTreeMap::<usize, BTreeSet<String>>::new();
+    let mut stream = analyzer.token_stream(content);
+    stream.process(&mut |token| {
+        if terms.contains(&token.text) {
+            line_terms
+                .entry(offsets.line_of(token.offset_from))
+                .or_default()
+                .insert(token.text.clone());
+        }
+    });
+    spans_from_line_terms(&line_terms)
+}
+
 fn build_schema() -> Schema {
     let mut schema_builder = Schema::builder();
     let _ = schema_builder.add_text_field("id", STRING | STORED);
@@ -159,4 +259,74 @@ mod tests {
         let ids = index.search_ids("iso_to_date", 5).expect("search");
         assert_eq!(ids, vec!["c1".to_string()]);
     }
+
+    #[test]
+    fn lexical_hits_report_matched_lines() {
+        let mut index = TantivyLexicalIndex::new_in_memory().expect("index");
+        index
+            .add_chunk(&CodeChunk {
+                id: "c1".to_string(),
+                fingerprint: "fp".to_string(),
+                file_path: "src/date.rs".to_string(),
+                language: "rust".to_string(),
+                symbol: Some("convert".to_string()),
+                start_line: 10,
+                end_line: 14,
+                start_char: 0,
+                end_char: 80,
+                content: "fn convert() {\n    let a = 1;\n    parse_timestamp(a)\n}".to_string(),
+            })
+            .expect("add");
+        index.commit().expect("commit");
+
+        let hits = index.search_hits("timestamp", 5).expect("search");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].highlights.len(), 1);
+        assert_eq!(hits[0].highlights[0].start, 2);
+        assert_eq!(hits[0].highlights[0].terms, vec!["timestamp".to_string()]);
+    }
+
+    #[test]
+    fn similar_hits_rank_chunks_sharing_terms_first() {
+        let chunk = |id: &str, content: &str| CodeChunk {
+            id: id.to_string(),
+            fingerprint: id.to_string(),
+            file_path: format!("src/{id}.rs"),
+            language: "rust".to_string(),
+            symbol: None,
+            start_line: 1,
+            end_line: 3,
+            start_char: 0,
+            end_char: content.len(),
+            content: content.to_string(),
+        };
+        let mut index = TantivyLexicalIndex::new_in_memory().expect("index");
+        for c in [
+            chunk(
+                "a",
+                "fn retry_request(client) { backoff delay retry attempts }",
+            ),
+            chunk(
+                "b",
+                "fn retry_upload(store) { backoff delay retry attempts }",
+            ),
+            chunk("c", "fn render_page(html) { template layout }"),
+        ] {
+            index.add_chunk(&c).expect("add");
+        }
+        index.commit().expect("commit");
+
+        let source = chunk(
+            "a",
+            "fn retry_request(client) { backoff delay retry attempts }",
+        );
+        let ids = index
+            .similar_hits(&source, 5)
+            .expect("similar")
+            .into_iter()
+            .map(|hit| hit.id)
+            .collect::<Vec<_>>();
+        assert_eq!(&ids[..2], &["a".to_string(), "b".to_string()]);
+        assert!(!ids.contains(&"c".to_string()));
+    }
 }
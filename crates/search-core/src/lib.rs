@@ -1,9 +1,29 @@
+pub mod diversify;
+pub mod embedding_text;
+pub mod feedback;
 pub mod fusion;
+pub mod highlight;
 pub mod lexical;
+pub mod local_vectors;
+pub mod multivector;
+pub mod pins;
+pub mod priors;
+pub mod proximity;
 pub mod retrieval;
 pub mod vector;
 
-pub use fusion::{ScoredId, rrf_fuse};
-pub use lexical::LexicalSearchConfig;
+pub use diversify::{DiversifiedId, DiversityCandidate, DiversityConfig, mmr_select};
+pub use embedding_text::EmbeddingTemplate;
+pub use fusion::{FusionWeights, ScoredId, apply_boosts, fuse_ranked, rrf_fuse};
+pub use highlight::HighlightSpan;
+pub use lexical::{LexicalHit, LexicalSearchConfig};
+pub use local_vectors::{LocalQuantization, LocalVectorConfig, LocalVectorIndex};
+pub use multivector::{
+    ChunkVectors, ChunkViews, MultiVectorWeights, VectorField, chunk_views, embed_chunk_views,
+    fuse_field_rankings, render_chunk_views,
+};
+pub use pins::{ResolvedPin, pin_matches, resolve_pin, same_file};
+pub use priors::{ChunkRole, PriorSubject, RankingPriors};
+pub use proximity::{PackageResolver, ProximityContext, ProximityWeights};
 pub use retrieval::RetrievalDefaults;
 pub use vector::VectorSearchConfig;
@@ -0,0 +1,424 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+
+use crate::multivector::{ChunkVectors, VectorField};
+
+const BINARY_OVERSAMPLE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalQuantization {
+    None,
+    Int8,
+    Binary,
+}
+
+impl LocalQuantization {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "none" | "f32" => Some(Self::None),
+            "int8" | "scalar" => Some(Self::Int8),
+            "binary" | "bit" => Some(Self::Binary),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Int8 => "int8",
+            Self::Binary => "binary",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalVectorConfig {
+    pub quantization: LocalQuantization,
+    pub rescore_factor: usize,
+}
+
+impl Default for LocalVectorConfig {
+    fn default() -> Self {
+        Self {
+            quantization: LocalQuantization::Int8,
+            rescore_factor: 4,
+        }
+    }
+}
+
+impl LocalVectorConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            quantization: std::env::var("MCP_LOCAL_VECTOR_QUANTIZATION")
+                .ok()
+                .and_then(|v| LocalQuantization::parse(&v))
+                .unwrap_or(defaults.quantization),
+            rescore_factor: std::env::var("MCP_LOCAL_RESCORE_FACTOR")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .map(|v| v.max(1))
+                .unwrap_or(defaults.rescore_factor),
+        }
+    }
+}
+
+enum Codes {
+    Full(Vec<f32>),
+    Int8 { codes: Vec<i8>, scales: Vec<f32> },
+    Binary { words: usize, bits: Vec<u64> },
+}
+
+struct FieldIndex {
+    rows: Vec<u32>,
+    offsets: Vec<u64>,
+    codes: Codes,
+}
+
+pub struct LocalVectorIndex {
+    path: PathBuf,
+    dim: usize,
+    config: LocalVectorConfig,
+    ids: Vec<String>,
+    fields: [FieldIndex; 3],
+}
+
+impl LocalVectorIndex {
+    pub fn build(
+        path: &Path,
+        config: LocalVectorConfig,
+        ids: Vec<String>,
+        vectors: &[ChunkVectors],
+    ) -> Result<Self> {
+        if ids.len() != vectors.len() {
+            return Err(anyhow!(
+                "{} ids for {} chunk vectors",
+                ids.len(),
+                vectors.len()
+            ));
+        }
+        let dim = vectors.first().map_or(0, |v| v.code.len());
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        let mut offset = 0u64;
+        let mut fields = VectorField::ALL.map(|_| FieldIndex {
+            rows: Vec::new(),
+            offsets: Vec::new(),
+            codes: match config.quantization {
+                LocalQuantization::None => Codes::Full(Vec::new()),
+                LocalQuantization::Int8 => Codes::Int8 {
+                    codes: Vec::new(),
+                    scales: Vec::new(),
+                },
+                LocalQuantization::Binary => Codes::Binary {
+                    words: dim.div_ceil(64),
+                    bits: Vec::new(),
+                },
+            },
+        });
+
+        for (row, chunk) in vectors.iter().enumerate() {
+            for (field, index) in VectorField::ALL.iter().zip(fields.iter_mut()) {
+                let Some(vector) = chunk.get(*field) else {
+                    continue;
+                };
+                if vector.len() != dim {
+                    return Err(anyhow!(
+                        "vector for {} has dim {}, expected {dim}",
+                        ids[row],
+                        vector.len()
+                    ));
+                }
+                for value in vector {
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+                index.rows.push(row as u32);
+                index.offsets.push(offset);
+                offset += (dim * 4) as u64;
+                index.codes.push(vector);
+            }
+        }
+        writer.flush()?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            dim,
+            config,
+            ids,
+            fields,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn quantization(&self) -> LocalQuantization {
+        self.config.quantization
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|index| {
+                index.rows.len() * 4
+                    + match &index.codes {
+                        Codes::Full(values) => values.len() * 4,
+                        Codes::Int8 { codes, scales } => codes.len() + scales.len() * 4,
+                        Codes::Binary { bits, .. } => bits.len() * 8,
+                    }
+            })
+            .sum()
+    }
+
+    pub fn full_precision_bytes(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|index| index.rows.len() * (4 + self.dim * 4))
+            .sum()
+    }
+
+    pub fn search(
+        &self,
+        field: VectorField,
+        query: &[f32],
+        top_k: usize,
+    ) -> Result<Vec<(String, f32)>> {
+        let index = &self.fields[field_slot(field)];
+        if top_k == 0 || index.rows.is_empty() || query.len() != self.dim {
+            return Ok(Vec::new());
+        }
+        let query_norm = norm(query);
+        let shortlist = match index.codes {
+            Codes::Full(_) => top_k,
+            Codes::Int8 { .. } => top_k.saturating_mul(self.config.rescore_factor),
+            Codes::Binary { .. } => top_k
+                .saturating_mul(self.config.rescore_factor)
+                .saturating_mul(BINARY_OVERSAMPLE),
+        };
+        let rescore = !matches!(index.codes, Codes::Full(_));
+        let mut scored = index.codes.approximate_scores(query, query_norm, self.dim);
+        if scored.len() > shortlist {
+            scored.select_nth_unstable_by(shortlist, |a, b| b.1.total_cmp(&a.1));
+            scored.truncate(shortlist);
+        }
+        if rescore {
+            let mut file = File::open(&self.path)?;
+            let mut raw = vec![0u8; self.dim * 4];
+            for (entry, score) in scored.iter_mut() {
+                file.seek(SeekFrom::Start(index.offsets[*entry]))?;
+                file.read_exact(&mut raw)?;
+                let full = raw
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect::<Vec<_>>();
+                *score = cosine(query, query_norm, &full);
+            }
+        }
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(scored
+            .into_iter()
+            .take(top_k)
+            .map(|(entry, score)| (self.ids[index.rows[entry] as usize].clone(), score))
+            .collect())
+    }
+}
+
+impl Drop for LocalVectorIndex {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl Codes {
+    fn push(&mut self, vector: &[f32]) {
+        match self {
+            Self::Full(values) => values.extend_from_slice(vector),
+            Self::Int8 { codes, scales } => {
+                let max = vector.iter().fold(0.0f32, |acc, v| acc.max(v.abs()));
+                let scale = if max > f32::EPSILON { max / 127.0 } else { 1.0 };
+                codes.extend(
+                    vector
+                        .iter()
+                        .map(|v| (v / scale).round().clamp(-127.0, 127.0) as i8),
+                );
+                scales.push(scale);
+            }
+            Self::Binary { bits, .. } => bits.extend(sign_bits(vector)),
+        }
+    }
+
+    fn approximate_scores(&self, query: &[f32], query_norm: f32, dim: usize) -> Vec<(usize, f32)> {
+        match self {
+            Self::Full(values) => values
+                .chunks_exact(dim)
+                .map(|row| cosine(query, query_norm, row))
+                .enumerate()
+                .collect(),
+            Self::Int8 { codes, scales } => codes
+                .chunks_exact(dim)
+                .zip(scales)
+                .map(|(row, scale)| {
+                    let mut dot = 0.0f32;
+                    let mut sq = 0i64;
+                    for (q, c) in query.iter().zip(row) {
+                        dot += q * f32::from(*c);
+                        sq += i64::from(*c) * i64::from(*c);
+                    }
+                    let row_norm = (sq as f32).sqrt() * scale;
+                    if query_norm <= f32::EPSILON || row_norm <= f32::EPSILON {
+                        0.0
+                    } else {
+                        dot * scale / (query_norm * row_norm)
+                    }
+                })
+                .enumerate()
+                .collect(),
+            Self::Binary { words, bits } => bits
+                .chunks_exact(*words)
+                .map(|row| {
+                    let dot = query
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, q)| {
+                            if row[idx / 64] & (1 << (idx % 64)) != 0 {
+                                *q
+                            } else {
+                                -q
+                            }
+                        })
+                        .sum::<f32>();
+                    dot / (query_norm.max(f32::EPSILON) * (dim.max(1) as f32).sqrt())
+                })
+                .enumerate()
+                .collect(),
+        }
+    }
+}
+
+fn field_slot(field: VectorField) -> usize {
+    match field {
+        VectorField::Code => 0,
+        VectorField::Doc => 1,
+        VectorField::Signature => 2,
+    }
+}
+
+fn sign_bits(vector: &[f32]) -> Vec<u64> {
+    let mut bits = vec![0u64; vector.len().div_ceil(64)];
+    for (idx, value) in vector.iter().enumerate() {
+        if *value > 0.0 {
+            bits[idx / 64] |= 1 << (idx % 64);
+        }
+    }
+    bits
+}
+
+fn norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|v| v * v).sum::<f32>().sqrt()
+}
+
+fn cosine(query: &[f32], query_norm: f32, row: &[f32]) -> f32 {
+    let row_norm = norm(row);
+    if query_norm <= f32::EPSILON || row_norm <= f32::EPSILON {
+        return 0.0;
+    }
+    query.iter().zip(row).map(|(a, b)| a * b).sum::<f32>() / (query_norm * row_norm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LocalQuantization, LocalVectorConfig, LocalVectorIndex};
+    use crate::multivector::{ChunkVectors, VectorField};
+
+    fn vector(seed: u64, dim: usize) -> Vec<f32> {
+        let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+        (0..dim)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state % 2001) as f32 / 1000.0 - 1.0
+            })
+            .collect()
+    }
+
+    fn corpus(rows: usize, dim: usize) -> (Vec<String>, Vec<ChunkVectors>) {
+        (0..rows)
+            .map(|row| {
+                (
+                    format!("chunk-{row}"),
+                    ChunkVectors {
+                        code: vector(row as u64 + 1, dim),
+                        doc: (row % 2 == 0).then(|| vector(row as u64 + 10_000, dim)),
+                        signature: vector(row as u64 + 20_000, dim),
+                    },
+                )
+            })
+            .unzip()
+    }
+
+    fn build(quantization: LocalQuantization, tag: &str) -> LocalVectorIndex {
+        let (ids, vectors) = corpus(200, 64);
+        let path = std::env::temp_dir().join(format!(
+            "codivex-local-vectors-{tag}-{}.f32",
+            std::process::id()
+        ));
+        LocalVectorIndex::build(
+            &path,
+            LocalVectorConfig {
+                quantization,
+                rescore_factor: 4,
+            },
+            ids,
+            &vectors,
+        )
+        .expect("index")
+    }
+
+    #[test]
+    fn quantized_search_rescores_to_exact_neighbors() {
+        let exact = build(LocalQuantization::None, "exact");
+        let int8 = build(LocalQuantization::Int8, "int8");
+        let binary = build(LocalQuantization::Binary, "binary");
+        let mut query = vector(42, 64);
+        query[0] += 0.05;
+
+        let expected = exact.search(VectorField::Code, &query, 5).expect("exact");
+        assert_eq!(expected[0].0, "chunk-41");
+        for index in [&int8, &binary] {
+            let got = index.search(VectorField::Code, &query, 5).expect("search");
+            assert_eq!(got[0].0, "chunk-41", "{:?}", index.quantization());
+            assert!((got[0].1 - expected[0].1).abs() < 1e-5);
+        }
+        let docs = int8.search(VectorField::Doc, &query, 500).expect("docs");
+        assert_eq!(docs.len(), 100);
+
+        assert!(exact.memory_bytes() as f32 / int8.memory_bytes() as f32 > 3.0);
+        assert!(exact.memory_bytes() as f32 / binary.memory_bytes() as f32 > 10.0);
+        assert_eq!(exact.memory_bytes(), exact.full_precision_bytes());
+    }
+
+    #[test]
+    fn dropping_index_removes_full_precision_file() {
+        let index = build(LocalQuantization::Int8, "drop");
+        let path = index.path.clone();
+        assert!(path.exists());
+        drop(index);
+        assert!(!path.exists());
+        assert_eq!(
+            LocalQuantization::parse("BIT"),
+            Some(LocalQuantization::Binary)
+        );
+    }
+}
@@ -0,0 +1,416 @@
+use std::path::Path;
+
+use ahash::AHashMap;
+use anyhow::{Result, anyhow};
+use common::CodeChunk;
+
+use crate::embedding_text::{EmbeddingTemplate, read_source};
+
+const SIGNATURE_MAX_LINES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VectorField {
+    Code,
+    Doc,
+    Signature,
+}
+
+impl VectorField {
+    pub const ALL: [VectorField; 3] = [Self::Code, Self::Doc, Self::Signature];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Code => "code",
+            Self::Doc => "doc",
+            Self::Signature => "signature",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkViews {
+    pub code: String,
+    pub doc: Option<String>,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkVectors {
+    pub code: Vec<f32>,
+    pub doc: Option<Vec<f32>>,
+    pub signature: Vec<f32>,
+}
+
+impl ChunkVectors {
+    pub fn get(&self, field: VectorField) -> Option<&[f32]> {
+        match field {
+            VectorField::Code => Some(&self.code),
+            VectorField::Doc => self.doc.as_deref(),
+            VectorField::Signature => Some(&self.signature),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MultiVectorWeights {
+    pub code: f32,
+    pub doc: f32,
+    pub signature: f32,
+}
+
+impl Default for MultiVectorWeights {
+    fn default() -> Self {
+        Self {
+            code: 1.0,
+            doc: 1.0,
+            signature: 1.0,
+        }
+    }
+}
+
+impl MultiVectorWeights {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            code: env_f32("MCP_VECTOR_WEIGHT_CODE", defaults.code),
+            doc: env_f32("MCP_VECTOR_WEIGHT_DOC", defaults.doc),
+            signature: env_f32("MCP_VECTOR_WEIGHT_SIGNATURE", defaults.signature),
+        }
+    }
+
+    pub fn weight(&self, field: VectorField) -> f32 {
+        match field {
+            VectorField::Code => self.code,
+            VectorField::Doc => self.doc,
+            VectorField::Signature => self.signature,
+        }
+    }
+}
+
+pub fn chunk_views(chunk: &CodeChunk) -> ChunkViews {
+    let lines = chunk.content.lines().collect::<Vec<_>>();
+    let code_start = lines
+        .iter()
+        .position(|line| !is_preamble_line(line))
+        .unwrap_or(lines.len());
+
+    let mut doc = lines[..code_start]
+        .iter()
+        .filter_map(|line| strip_comment_marker(line))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    doc.extend(docstring_lines(&lines, code_start));
+
+    let signature = lines[code_start..]
+        .iter()
+        .map(|line| line.trim())
+        .take(SIGNATURE_MAX_LINES)
+        .scan(false, |done, line| {
+            if *done {
+                return None;
+            }
+            *done = line.contains('{') || line.ends_with(':') || line.ends_with(';');
+            Some(
+                line.split('{')
+                    .next()
+                    .unwrap_or(line)
+                    .trim_end()
+                    .to_string(),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut header = vec![qualified_name(&chunk.file_path, chunk.symbol.as_deref())];
+    if let Some(symbol) = chunk.symbol.as_deref() {
+        header.push(identifier_words(symbol));
+    }
+    header.push(signature);
+
+    ChunkViews {
+        code: chunk.content.clone(),
+        doc: Some(doc.join("\n")).filter(|d| !d.trim().is_empty()),
+        signature: header
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+pub fn render_chunk_views(chunks: &[CodeChunk], template: &EmbeddingTemplate) -> Vec<ChunkViews> {
+    let mut sources = AHashMap::<&str, Option<String>>::new();
+    chunks
+        .iter()
+        .map(|chunk| {
+            let source = sources
+                .entry(chunk.file_path.as_str())
+                .or_insert_with(|| read_source(&chunk.file_path));
+            ChunkViews {
+                code: template.render(chunk, source.as_deref()),
+                ..chunk_views(chunk)
+            }
+        })
+        .collect()
+}
+
+pub fn embed_chunk_views<F>(
+    chunks: &[CodeChunk],
+    template: &EmbeddingTemplate,
+    mut embed: F,
+) -> Result<Vec<ChunkVectors>>
+where
+    F: FnMut(&[String]) -> Result<Vec<Vec<f32>>>,
+{
+    let views = render_chunk_views(chunks, template);
+    let mut texts = Vec::with_capacity(views.len() * 3);
+    for view in &views {
+        texts.push(view.code.clone());
+        texts.push(view.signature.clone());
+        if let Some(doc) = &view.doc {
+            texts.push(doc.clone());
+        }
+    }
+    let mut vectors = embed(&texts)?.into_iter();
+
+    views
+        .iter()
+        .map(|view| {
+            let code = vectors.next();
+            let signature = vectors.next();
+            let doc = if view.doc.is_some() {
+                Some(
+                    vectors
+                        .next()
+                        .ok_or_else(|| anyhow!("missing doc vector"))?,
+                )
+            } else {
+                None
+            };
+            Ok(ChunkVectors {
+                code: code.ok_or_else(|| anyhow!("missing code vector"))?,
+                doc,
+                signature: signature.ok_or_else(|| anyhow!("missing signature vector"))?,
+            })
+        })
+        .collect()
+}
+
+pub fn fuse_field_rankings(
+    rankings: &[(VectorField, Vec<String>)],
+    weights: &MultiVectorWeights,
+    k: usize,
+) -> Vec<String> {
+    let mut scores = AHashMap::<&str, f32>::new();
+    for (field, ids) in rankings {
+        let weight = weights.weight(*field);
+        for (rank, id) in ids.iter().enumerate() {
+            *scores.entry(id.as_str()).or_insert(0.0) += weight / (k as f32 + (rank + 1) as f32);
+        }
+    }
+    let mut fused = scores.into_iter().collect::<Vec<_>>();
+    fused.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    fused.into_iter().map(|(id, _)| id.to_string()).collect()
+}
+
+fn is_preamble_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty()
+        || trimmed.starts_with("#[")
+        || trimmed.starts_with('@')
+        || strip_comment_marker(line).is_some()
+}
+
+fn strip_comment_marker(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    if trimmed.starts_with("#[") || trimmed.starts_with("#!") {
+        return None;
+    }
+    let rest = [
+        "///", "//!", "//", "/**", "/*", "*/", "-- |", "--", "*", "#",
+    ]
+    .iter()
+    .find_map(|marker| trimmed.strip_prefix(marker))?;
+    Some(rest.trim().trim_end_matches("*/").trim())
+}
+
+fn docstring_lines<'a>(lines: &[&'a str], code_start: usize) -> Vec<&'a str> {
+    let Some(body_start) = lines[code_start..]
+        .iter()
+        .position(|line| line.trim_end().ends_with(':'))
+        .map(|offset| code_start + offset + 1)
+    else {
+        return Vec::new();
+    };
+    let Some(first) = lines.get(body_start).map(|line| line.trim()) else {
+        return Vec::new();
+    };
+    let Some(quote) = ["\"\"\"", "'''"]
+        .into_iter()
+        .find(|quote| first.starts_with(quote))
+    else {
+        return Vec::new();
+    };
+
+    let mut out = Vec::new();
+    for (offset, line) in lines[body_start..].iter().enumerate() {
+        let mut text = line.trim();
+        if offset == 0 {
+            text = &text[quote.len()..];
+        }
+        let closes = text.contains(quote);
+        let text = text.split(quote).next().unwrap_or_default().trim();
+        if !text.is_empty() {
+            out.push(text);
+        }
+        if closes {
+            break;
+        }
+    }
+    out
+}
+
+pub(crate) fn qualified_name(file_path: &str, symbol: Option<&str>) -> String {
+    let path = Path::new(file_path);
+    let dirs = path
+        .parent()
+        .into_iter()
+        .flat_map(|parent| parent.components())
+        .filter_map(|c| c.as_os_str().to_str())
+        .filter(|c| !matches!(*c, "/" | "." | ".."))
+        .collect::<Vec<_>>();
+    let after_root = dirs
+        .iter()
+        .rposition(|c| matches!(*c, "src" | "lib"))
+        .map(|idx| idx + 1)
+        .unwrap_or(0);
+    let mut parts = dirs[after_root.max(dirs.len().saturating_sub(2))..].to_vec();
+    if let Some(stem) = path.file_stem().and_then(|s| s.to_str())
+        && !matches!(stem, "mod" | "lib" | "main" | "index" | "__init__")
+    {
+        parts.push(stem);
+    }
+    if let Some(symbol) = symbol {
+        parts.push(symbol);
+    }
+    parts.join("::")
+}
+
+fn identifier_words(symbol: &str) -> String {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for ch in symbol.chars() {
+        if ch == '_' || ch == '-' || ch == '.' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if ch.is_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = ch.is_lowercase() || ch.is_ascii_digit();
+        current.extend(ch.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words.join(" ")
+}
+
+fn env_f32(key: &str, default: f32) -> f32 {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse::<f32>().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use common::CodeChunk;
+
+    use super::{
+        MultiVectorWeights, VectorField, chunk_views, embed_chunk_views, fuse_field_rankings,
+    };
+    use crate::embedding_text::EmbeddingTemplate;
+
+    fn chunk(file: &str, symbol: &str, content: &str) -> CodeChunk {
+        CodeChunk {
+            id: format!("{file}:1:1:{symbol}"),
+            fingerprint: String::new(),
+            file_path: file.to_string(),
+            language: String::new(),
+            symbol: Some(symbol.to_string()),
+            start_line: 1,
+            end_line: 1,
+            start_char: 0,
+            end_char: content.len(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn views_split_doc_comments_signature_and_qualified_name() {
+        let rust = chunk(
+            "/repo/src/time/parse.rs",
+            "isoToDate",
+            "/// Convert an ISO-8601 string to a date.\n#[inline]\npub fn isoToDate(input: &str)\n    -> Date {\n    todo!()\n}",
+        );
+        let views = chunk_views(&rust);
+        assert_eq!(
+            views.doc.as_deref(),
+            Some("Convert an ISO-8601 string to a date.")
+        );
+        assert_eq!(
+            views.signature,
+            "time::parse::isoToDate\niso to date\npub fn isoToDate(input: &str) -> Date"
+        );
+
+        let python = chunk(
+            "pkg/dates.py",
+            "iso_to_date",
+            "def iso_to_date(value):\n    \"\"\"Parse an ISO string\n    into a date.\"\"\"\n    return value",
+        );
+        let views = chunk_views(&python);
+        assert_eq!(
+            views.doc.as_deref(),
+            Some("Parse an ISO string\ninto a date.")
+        );
+        assert!(
+            views
+                .signature
+                .starts_with("pkg::dates::iso_to_date\niso to date\n")
+        );
+
+        assert_eq!(chunk_views(&chunk("a.rs", "f", "fn f() {}")).doc, None);
+    }
+
+    #[test]
+    fn embeds_views_in_one_batch_and_fuses_field_rankings() {
+        let chunks = vec![
+            chunk("a.rs", "a", "/// docs\nfn a() {}"),
+            chunk("b.rs", "b", "fn b() {}"),
+        ];
+        let mut calls = 0;
+        let vectors = embed_chunk_views(&chunks, &EmbeddingTemplate::new("{code}"), |texts| {
+            calls += 1;
+            Ok(texts.iter().map(|t| vec![t.len() as f32]).collect())
+        })
+        .expect("vectors");
+        assert_eq!(calls, 1);
+        assert_eq!(vectors[0].get(VectorField::Doc), Some(&[4.0][..]));
+        assert_eq!(vectors[1].get(VectorField::Doc), None);
+
+        let fused = fuse_field_rankings(
+            &[
+                (VectorField::Code, vec!["x".to_string(), "y".to_string()]),
+                (VectorField::Doc, vec!["y".to_string()]),
+            ],
+            &MultiVectorWeights::default(),
+            60,
+        );
+        assert_eq!(fused, vec!["y".to_string(), "x".to_string()]);
+    }
+}
@@ -0,0 +1,146 @@
+use common::{CodeChunk, projects::PinnedResult};
+
+use crate::feedback::query_terms;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedPin {
+    pub pin_id: String,
+    pub file: String,
+    pub symbol: Option<String>,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub content: Option<String>,
+}
+
+pub fn pin_matches(pattern: &str, query: &str) -> bool {
+    let pattern = query_terms(pattern);
+    !pattern.is_empty() && pattern.is_subset(&query_terms(query))
+}
+
+pub fn same_file(indexed: &str, pinned: &str) -> bool {
+    let indexed = indexed.replace('\\', "/");
+    let pinned = pinned.replace('\\', "/");
+    let pinned = pinned.trim_start_matches("./");
+    indexed == pinned
+        || indexed.ends_with(&format!("/{pinned}"))
+        || pinned.ends_with(&format!("/{indexed}"))
+}
+
+pub fn resolve_pin(pin: &PinnedResult, chunks: &[CodeChunk]) -> Option<ResolvedPin> {
+    let Some(symbol) = pin.symbol.as_deref() else {
+        let in_file = chunks
+            .iter()
+            .filter(|c| same_file(&c.file_path, &pin.file))
+            .collect::<Vec<_>>();
+        let content = in_file
+            .iter()
+            .find(|c| c.start_line == pin.start_line && c.end_line == pin.end_line)
+            .map(|c| c.content.clone());
+        // Report range pins under the indexed path, like symbol pins, so clients see one
+        // spelling of a file whichever way it was pinned.
+        let file = in_file
+            .first()
+            .map_or_else(|| pin.file.clone(), |c| c.file_path.clone());
+        return Some(ResolvedPin {
+            pin_id: pin.id.clone(),
+            file,
+            symbol: None,
+            start_line: pin.start_line,
+            end_line: pin.end_line,
+            content,
+        });
+    };
+
+    let same_symbol = |c: &&CodeChunk| c.symbol.as_deref() == Some(symbol);
+    let chunk = chunks
+        .iter()
+        .filter(same_symbol)
+        .filter(|c| same_file(&c.file_path, &pin.file))
+        .min_by_key(|c| c.start_line.abs_diff(pin.start_line))
+        .or_else(|| {
+            let mut moved = chunks.iter().filter(same_symbol);
+            match (moved.next(), moved.next()) {
+                (Some(only), None) => Some(only),
+                _ => None,
+            }
+        })?;
+    Some(ResolvedPin {
+        pin_id: pin.id.clone(),
+        file: chunk.file_path.clone(),
+        symbol: Some(symbol.to_string()),
+        start_line: chunk.start_line,
+        end_line: chunk.end_line,
+        content: Some(chunk.content.clone()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{CodeChunk, projects::PinnedResult};
+
+    use super::{pin_matches, resolve_pin, same_file};
+
+    fn chunk(file: &str, symbol: &str, start_line: usize) -> CodeChunk {
+        CodeChunk {
+            id: format!("{file}:{start_line}"),
+            fingerprint: String::new(),
+            file_path: file.to_string(),
+            language: "rust".to_string(),
+            symbol: Some(symbol.to_string()),
+            start_line,
+            end_line: start_line + 5,
+            start_char: 0,
+            end_char: 0,
+            content: format!("fn {symbol}() {{}}"),
+        }
+    }
+
+    fn pin(symbol: Option<&str>) -> PinnedResult {
+        PinnedResult {
+            id: "p1".to_string(),
+            query_pattern: "auth checked".to_string(),
+            file: "src/auth.rs".to_string(),
+            symbol: symbol.map(str::to_string),
+            start_line: 10,
+            end_line: 15,
+            created_at_unix: 0,
+        }
+    }
+
+    #[test]
+    fn pattern_terms_must_all_appear_in_query() {
+        assert!(pin_matches("auth checked", "where is auth checked?"));
+        assert!(!pin_matches("auth checked", "where is auth"));
+        assert!(!pin_matches("", "anything"));
+    }
+
+    #[test]
+    fn relative_pins_match_absolute_index_paths() {
+        assert!(same_file("/work/repo/src/auth.rs", "src/auth.rs"));
+        assert!(same_file("src/auth.rs", "./src/auth.rs"));
+        assert!(!same_file("/work/repo/src/oauth.rs", "auth.rs"));
+    }
+
+    #[test]
+    fn symbol_pins_follow_edits_and_unique_moves() {
+        let edited = vec![chunk("src/auth.rs", "check_auth", 42)];
+        let resolved = resolve_pin(&pin(Some("check_auth")), &edited).expect("resolved");
+        assert_eq!((resolved.start_line, resolved.end_line), (42, 47));
+
+        let moved = vec![chunk("src/security/auth.rs", "check_auth", 3)];
+        let resolved = resolve_pin(&pin(Some("check_auth")), &moved).expect("resolved");
+        assert_eq!(resolved.file, "src/security/auth.rs");
+
+        let ambiguous = vec![
+            chunk("a.rs", "check_auth", 1),
+            chunk("b.rs", "check_auth", 1),
+        ];
+        assert!(resolve_pin(&pin(Some("check_auth")), &ambiguous).is_none());
+
+        let range = resolve_pin(&pin(None), &[]).expect("range pin");
+        assert_eq!((range.start_line, range.content), (10, None));
+        let indexed = vec![chunk("/work/repo/src/auth.rs", "other", 1)];
+        let range = resolve_pin(&pin(None), &indexed).expect("range pin");
+        assert_eq!(range.file, "/work/repo/src/auth.rs");
+    }
+}
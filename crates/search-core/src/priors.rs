@@ -0,0 +1,398 @@
+use globset::{Glob, GlobMatcher};
+
+const HEADER_SCAN_LINES: usize = 10;
+const MINIFIED_MAX_LINE_CHARS: usize = 500;
+const MINIFIED_AVG_LINE_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChunkRole {
+    Test,
+    Generated,
+    Vendored,
+    Example,
+}
+
+impl ChunkRole {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Test => "test",
+            Self::Generated => "generated",
+            Self::Vendored => "vendored",
+            Self::Example => "example",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PathPrior {
+    pub pattern: String,
+    pub weight: f32,
+    matcher: GlobMatcher,
+}
+
+impl PathPrior {
+    pub fn new(pattern: &str, weight: f32) -> anyhow::Result<Self> {
+        let matcher = Glob::new(pattern)?.compile_matcher();
+        Ok(Self {
+            pattern: pattern.to_string(),
+            weight,
+            matcher,
+        })
+    }
+
+    pub fn matches(&self, file: &str) -> bool {
+        self.matcher.is_match(file)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RankingPriors {
+    pub test: f32,
+    pub generated: f32,
+    pub vendored: f32,
+    pub example: f32,
+    pub paths: Vec<PathPrior>,
+}
+
+impl Default for RankingPriors {
+    fn default() -> Self {
+        Self {
+            test: -0.5,
+            generated: -1.0,
+            vendored: -1.0,
+            example: -0.3,
+            paths: Vec::new(),
+        }
+    }
+}
+
+pub struct PriorSubject<'a> {
+    pub file: &'a str,
+    pub symbol: Option<&'a str>,
+    pub language: &'a str,
+    pub content: &'a str,
+    pub file_header: Option<&'a str>,
+}
+
+impl RankingPriors {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            test: env_f32("MCP_PRIOR_TEST", defaults.test),
+            generated: env_f32("MCP_PRIOR_GENERATED", defaults.generated),
+            vendored: env_f32("MCP_PRIOR_VENDORED", defaults.vendored),
+            example: env_f32("MCP_PRIOR_EXAMPLE", defaults.example),
+            paths: std::env::var("MCP_PATH_PRIORS")
+                .map(|spec| parse_path_priors(&spec))
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn role_weight(&self, role: ChunkRole, prefer_tests: bool) -> f32 {
+        match role {
+            ChunkRole::Test if prefer_tests => -self.test,
+            ChunkRole::Test => self.test,
+            ChunkRole::Generated => self.generated,
+            ChunkRole::Vendored => self.vendored,
+            ChunkRole::Example => self.example,
+        }
+    }
+
+    pub fn contributions(
+        &self,
+        subject: &PriorSubject<'_>,
+        prefer_tests: bool,
+    ) -> Vec<(String, f32)> {
+        let mut out = detect_roles(subject)
+            .into_iter()
+            .map(|role| {
+                (
+                    format!("role:{}", role.name()),
+                    self.role_weight(role, prefer_tests),
+                )
+            })
+            .collect::<Vec<_>>();
+        out.extend(
+            self.paths
+                .iter()
+                .filter(|prior| prior.matches(subject.file))
+                .map(|prior| (format!("path:{}", prior.pattern), prior.weight)),
+        );
+        out.retain(|(_, weight)| *weight != 0.0);
+        out
+    }
+}
+
+pub fn parse_path_priors(spec: &str) -> Vec<PathPrior> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.rsplit_once('=').and_then(|(pattern, weight)| {
+                let weight = weight.trim().parse::<f32>().ok()?;
+                PathPrior::new(pattern.trim(), weight).ok()
+            });
+            if parsed.is_none() {
+                tracing::warn!(
+                    entry,
+                    "ignoring invalid path prior; expected <glob>=<weight>"
+                );
+            }
+            parsed
+        })
+        .collect()
+}
+
+pub fn detect_roles(subject: &PriorSubject<'_>) -> Vec<ChunkRole> {
+    let mut roles = Vec::new();
+    if is_test_path(subject.file) || is_test_function(subject) {
+        roles.push(ChunkRole::Test);
+    }
+    if is_generated(subject) {
+        roles.push(ChunkRole::Generated);
+    }
+    let dirs = directories(subject.file);
+    if dirs.iter().any(|d| {
+        matches!(
+            d.as_str(),
+            "vendor" | "third_party" | "third-party" | "node_modules"
+        )
+    }) {
+        roles.push(ChunkRole::Vendored);
+    }
+    if dirs.iter().any(|d| {
+        matches!(
+            d.as_str(),
+            "examples" | "example" | "samples" | "demo" | "demos"
+        )
+    }) {
+        roles.push(ChunkRole::Example);
+    }
+    roles
+}
+
+fn directories(file: &str) -> Vec<String> {
+    let normalized = file.replace('\\', "/");
+    let mut parts = normalized
+        .split('/')
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>();
+    parts.pop();
+    parts
+}
+
+fn is_test_path(file: &str) -> bool {
+    if directories(file).iter().any(|d| {
+        matches!(
+            d.as_str(),
+            "test"
+                | "tests"
+                | "__tests__"
+                | "spec"
+                | "specs"
+                | "testdata"
+                | "fixtures"
+                | "__mocks__"
+        )
+    }) {
+        return true;
+    }
+    let name = file.rsplit(['/', '\\']).next().unwrap_or(file);
+    let stem = name.split('.').next().unwrap_or(name);
+    let lower = name.to_ascii_lowercase();
+    stem.ends_with("_test")
+        || stem.starts_with("test_")
+        || stem.ends_with("_spec")
+        || stem.ends_with("Test")
+        || stem.ends_with("Tests")
+        || lower == "conftest.py"
+        || lower.contains(".test.")
+        || lower.contains(".spec.")
+}
+
+fn is_test_function(subject: &PriorSubject<'_>) -> bool {
+    let symbol = subject.symbol.unwrap_or_default();
+    let content = subject.content;
+    match subject.language {
+        "rust" => {
+            content.contains("#[test]")
+                || content.contains("#[tokio::test")
+                || content.contains("#[cfg(test)]")
+        }
+        "python" => symbol.starts_with("test_") || symbol.starts_with("Test"),
+        "go" => ["Test", "Benchmark", "Fuzz", "Example"]
+            .iter()
+            .any(|prefix| {
+                symbol.strip_prefix(prefix).is_some_and(|rest| {
+                    rest.is_empty() || rest.starts_with(|c: char| c.is_uppercase() || c == '_')
+                })
+            }),
+        "java" | "kotlin" => content.contains("@Test") || content.contains("@ParameterizedTest"),
+        "csharp" => {
+            content.contains("[Test]")
+                || content.contains("[Fact]")
+                || content.contains("[TestMethod]")
+        }
+        "javascript" | "typescript" => {
+            let trimmed = content.trim_start();
+            ["describe(", "it(", "test("]
+                .iter()
+                .any(|prefix| trimmed.starts_with(prefix))
+        }
+        _ => false,
+    }
+}
+
+fn is_generated(subject: &PriorSubject<'_>) -> bool {
+    let lower = subject.file.to_ascii_lowercase();
+    if lower.contains(".min.")
+        || lower.contains(".generated.")
+        || lower.ends_with(".pb.go")
+        || lower.ends_with("_pb2.py")
+        || lower.ends_with(".g.dart")
+    {
+        return true;
+    }
+    let has_marker = |text: &str| {
+        text.lines().take(HEADER_SCAN_LINES).any(|line| {
+            line.contains("@generated")
+                || line.contains("DO NOT EDIT")
+                || line.contains("Code generated")
+                || line.to_ascii_lowercase().contains("auto-generated")
+        })
+    };
+    if has_marker(subject.content) || subject.file_header.is_some_and(has_marker) {
+        return true;
+    }
+    is_minified(subject.content)
+}
+
+fn is_minified(content: &str) -> bool {
+    let lines = content.lines().collect::<Vec<_>>();
+    if lines.is_empty() {
+        return false;
+    }
+    let longest = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+    let average = content.len() / lines.len();
+    longest >= MINIFIED_MAX_LINE_CHARS && average >= MINIFIED_AVG_LINE_CHARS
+}
+
+fn env_f32(key: &str, default: f32) -> f32 {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse::<f32>().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkRole, PriorSubject, RankingPriors, detect_roles, parse_path_priors};
+
+    fn subject<'a>(
+        file: &'a str,
+        symbol: &'a str,
+        language: &'a str,
+        content: &'a str,
+    ) -> PriorSubject<'a> {
+        PriorSubject {
+            file,
+            symbol: Some(symbol),
+            language,
+            content,
+            file_header: None,
+        }
+    }
+
+    #[test]
+    fn detects_tests_across_languages() {
+        let cases = [
+            subject(
+                "src/date.rs",
+                "parses_iso",
+                "rust",
+                "#[test]\nfn parses_iso() {}",
+            ),
+            subject(
+                "pkg/date_test.go",
+                "TestParse",
+                "go",
+                "func TestParse(t *testing.T) {}",
+            ),
+            subject("app/util.py", "test_parse", "python", "def test_parse():"),
+            subject(
+                "web/date.spec.ts",
+                "chunk",
+                "typescript",
+                "describe('x', () => {})",
+            ),
+            subject(
+                "src/test/java/DateTest.java",
+                "parses",
+                "java",
+                "@Test void parses() {}",
+            ),
+        ];
+        for case in &cases {
+            assert_eq!(detect_roles(case), vec![ChunkRole::Test], "{}", case.file);
+        }
+        let real = subject("src/date.rs", "iso_to_date", "rust", "fn iso_to_date() {}");
+        assert!(detect_roles(&real).is_empty());
+        let go_helper = subject("pkg/date.go", "Testify", "go", "func Testify() {}");
+        assert!(detect_roles(&go_helper).is_empty());
+    }
+
+    #[test]
+    fn detects_generated_vendored_and_examples() {
+        let generated = PriorSubject {
+            file_header: Some("// Code generated by protoc. DO NOT EDIT."),
+            ..subject("api/date.go", "Parse", "go", "func Parse() {}")
+        };
+        assert_eq!(detect_roles(&generated), vec![ChunkRole::Generated]);
+        let minified = "x".repeat(800);
+        assert_eq!(
+            detect_roles(&subject("static/app.js", "chunk", "javascript", &minified)),
+            vec![ChunkRole::Generated]
+        );
+        assert_eq!(
+            detect_roles(&subject(
+                "vendor/lib/date.go",
+                "Parse",
+                "go",
+                "func Parse() {}"
+            )),
+            vec![ChunkRole::Vendored]
+        );
+        assert_eq!(
+            detect_roles(&subject(
+                "examples/basic.rs",
+                "main",
+                "rust",
+                "fn main() {}"
+            )),
+            vec![ChunkRole::Example]
+        );
+    }
+
+    #[test]
+    fn prefer_tests_flips_test_penalty_and_globs_apply() {
+        let priors = RankingPriors {
+            paths: parse_path_priors("src/core/**=0.8, bad-entry, legacy/*.rs=-0.4"),
+            ..RankingPriors::default()
+        };
+        assert_eq!(priors.paths.len(), 2);
+        let test = subject("tests/date.rs", "parses", "rust", "fn parses() {}");
+        assert_eq!(
+            priors.contributions(&test, false),
+            vec![("role:test".to_string(), -0.5)]
+        );
+        assert_eq!(
+            priors.contributions(&test, true),
+            vec![("role:test".to_string(), 0.5)]
+        );
+        let core = subject("src/core/date.rs", "parse", "rust", "fn parse() {}");
+        assert_eq!(
+            priors.contributions(&core, false),
+            vec![("path:src/core/**".to_string(), 0.8)]
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+const PACKAGE_MANIFESTS: [&str; 9] = [
+    "Cargo.toml",
+    "package.json",
+    "go.mod",
+    "pyproject.toml",
+    "setup.py",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "Package.swift",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProximityWeights {
+    pub same_package: f32,
+    pub distance: f32,
+    pub symbol: f32,
+}
+
+impl Default for ProximityWeights {
+    fn default() -> Self {
+        Self {
+            same_package: 1.0,
+            distance: 0.5,
+            symbol: 0.5,
+        }
+    }
+}
+
+impl ProximityWeights {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            same_package: env_f32("MCP_PROXIMITY_PACKAGE_WEIGHT", defaults.same_package),
+            distance: env_f32("MCP_PROXIMITY_DISTANCE_WEIGHT", defaults.distance),
+            symbol: env_f32("MCP_PROXIMITY_SYMBOL_WEIGHT", defaults.symbol),
+        }
+    }
+}
+
+pub struct PackageResolver {
+    root: PathBuf,
+    cache: HashMap<String, Option<String>>,
+}
+
+impl PackageResolver {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn package_of(&mut self, file: &str) -> Option<String> {
+        let mut dir = parent_dir(&normalize_relative(file));
+        loop {
+            if let Some(cached) = self.cache.get(&dir) {
+                return cached.clone();
+            }
+            let has_manifest = PACKAGE_MANIFESTS
+                .iter()
+                .any(|name| self.root.join(&dir).join(name).is_file())
+                || has_csproj(&self.root.join(&dir));
+            if has_manifest {
+                self.cache.insert(dir.clone(), Some(dir.clone()));
+                return Some(dir);
+            }
+            if dir.is_empty() {
+                self.cache.insert(dir, None);
+                return None;
+            }
+            dir = parent_dir(&dir);
+        }
+    }
+}
+
+pub struct ProximityContext {
+    pub path: Option<String>,
+    pub package: Option<String>,
+    pub symbol: Option<String>,
+}
+
+impl ProximityContext {
+    pub fn new(
+        resolver: &mut PackageResolver,
+        context_path: Option<&str>,
+        context_symbol: Option<&str>,
+    ) -> Self {
+        let path = context_path
+            .map(|p| relative_to(&resolver.root, p))
+            .filter(|p| !p.is_empty());
+        let package = path.as_deref().and_then(|p| resolver.package_of(p));
+        Self {
+            path,
+            package,
+            symbol: context_symbol
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.path.is_none() && self.symbol.is_none()
+    }
+
+    pub fn contributions(
+        &self,
+        weights: &ProximityWeights,
+        resolver: &mut PackageResolver,
+        file: &str,
+        content: &str,
+    ) -> Vec<(String, f32)> {
+        let mut out = Vec::new();
+        if let Some(context) = self.path.as_deref() {
+            let file = relative_to(&resolver.root, file);
+            if self
+                .package
+                .as_ref()
+                .is_some_and(|package| resolver.package_of(&file).as_ref() == Some(package))
+            {
+                out.push(("proximity:same_package".to_string(), weights.same_package));
+            }
+            let distance = directory_distance(context, &file);
+            out.push((
+                "proximity:directory".to_string(),
+                weights.distance / (1.0 + distance as f32),
+            ));
+        }
+        if let Some(symbol) = self.symbol.as_deref()
+            && mentions_identifier(content, symbol)
+        {
+            out.push(("proximity:symbol".to_string(), weights.symbol));
+        }
+        out.retain(|(_, weight)| *weight != 0.0);
+        out
+    }
+}
+
+pub fn directory_distance(a: &str, b: &str) -> usize {
+    let a = parent_dir(&normalize_relative(a));
+    let b = parent_dir(&normalize_relative(b));
+    let a_parts = a.split('/').filter(|p| !p.is_empty()).collect::<Vec<_>>();
+    let b_parts = b.split('/').filter(|p| !p.is_empty()).collect::<Vec<_>>();
+    let common = a_parts
+        .iter()
+        .zip(&b_parts)
+        .take_while(|(x, y)| x == y)
+        .count();
+    (a_parts.len() - common) + (b_parts.len() - common)
+}
+
+fn mentions_identifier(content: &str, symbol: &str) -> bool {
+    content
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|word| word == symbol)
+}
+
+fn relative_to(root: &Path, path: &str) -> String {
+    let normalized = path.replace('\\', "/");
+    let root = root.display().to_string().replace('\\', "/");
+    let stripped = normalized
+        .strip_prefix(root.trim_end_matches('/'))
+        .unwrap_or(&normalized);
+    normalize_relative(stripped)
+}
+
+fn normalize_relative(path: &str) -> String {
+    path.replace('\\', "/")
+        .trim_start_matches("./")
+        .trim_start_matches('/')
+        .to_string()
+}
+
+fn parent_dir(path: &str) -> String {
+    path.rsplit_once('/')
+        .map(|(dir, _)| dir.to_string())
+        .unwrap_or_default()
+}
+
+fn has_csproj(dir: &Path) -> bool {
+    std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .any(|e| e.path().extension().is_some_and(|ext| ext == "csproj"))
+    })
+}
+
+fn env_f32(key: &str, default: f32) -> f32 {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse::<f32>().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PackageResolver, ProximityContext, ProximityWeights, directory_distance};
+
+    #[test]
+    fn directory_distance_counts_tree_edges() {
+        assert_eq!(directory_distance("a/b/c.rs", "a/b/d.rs"), 0);
+        assert_eq!(directory_distance("a/b/c.rs", "a/e/d.rs"), 2);
+        assert_eq!(directory_distance("a/b/c.rs", "x.rs"), 2);
+    }
+
+    #[test]
+    fn same_package_and_symbol_mentions_are_boosted() {
+        let root = std::env::temp_dir().join(format!("codivex-proximity-{}", std::process::id()));
+        let _ = std::fs::create_dir_all(root.join("crates/indexer/src"));
+        let _ = std::fs::create_dir_all(root.join("crates/ui/src"));
+        let _ = std::fs::write(root.join("Cargo.toml"), "[workspace]");
+        let _ = std::fs::write(root.join("crates/indexer/Cargo.toml"), "[package]");
+        let _ = std::fs::write(root.join("crates/ui/Cargo.toml"), "[package]");
+
+        let mut resolver = PackageResolver::new(&root);
+        let context_path = root.join("crates/indexer/src/chunking.rs");
+        let context = ProximityContext::new(
+            &mut resolver,
+            Some(&context_path.display().to_string()),
+            Some("ParserRegistry"),
+        );
+        assert_eq!(context.package.as_deref(), Some("crates/indexer"));
+        let weights = ProximityWeights::default();
+
+        let near = context.contributions(
+            &weights,
+            &mut resolver,
+            &root
+                .join("crates/indexer/src/parsers.rs")
+                .display()
+                .to_string(),
+            "pub struct ParserRegistry;",
+        );
+        let names = near.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "proximity:same_package",
+                "proximity:directory",
+                "proximity:symbol"
+            ]
+        );
+
+        let far = context.contributions(
+            &weights,
+            &mut resolver,
+            &root.join("crates/ui/src/parsers.rs").display().to_string(),
+            "struct ParserRegistryView;",
+        );
+        assert_eq!(far.len(), 1);
+        assert!(far[0].1 < near[1].1);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1,14 +1,19 @@
 use ahash::AHasher;
-use anyhow::Result;
+use anyhow::{Result, anyhow, bail};
 use common::CodeChunk;
 use qdrant_client::{
     Qdrant,
     qdrant::{
-        CreateCollectionBuilder, DeletePointsBuilder, Distance, PointStruct, PointsIdsList,
-        QuantizationType, QueryPointsBuilder, ScalarQuantizationBuilder, UpsertPointsBuilder,
-        VectorParamsBuilder, value::Kind,
+        CollectionInfo, CreateCollectionBuilder, DeletePointsBuilder, Distance, GetPointsBuilder,
+        PointStruct, PointsIdsList, QuantizationType, QueryPointsBuilder,
+        ScalarQuantizationBuilder, UpsertPointsBuilder, VectorParamsBuilder, VectorsConfigBuilder,
+        value::Kind, vector_output, vectors_config,
     },
 };
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::multivector::{ChunkVectors, VectorField};
 use std::hash::{Hash, Hasher};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -19,6 +24,7 @@ pub struct VectorSearchConfig {
     pub hnsw_ef_construct: u64,
     pub vector_dim: usize,
     pub quantization: QuantizationMode,
+    pub model_id: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -37,6 +43,120 @@ impl Default for VectorSearchConfig {
             hnsw_ef_construct: 100,
             vector_dim: 384,
             quantization: QuantizationMode::Int8,
+            model_id: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionMetadata {
+    pub model_id: String,
+    pub vector_dim: usize,
+    pub distance: String,
+    #[serde(default = "named_vectors_default")]
+    pub named_vectors: bool,
+}
+
+fn named_vectors_default() -> bool {
+    true
+}
+
+impl CollectionMetadata {
+    pub fn from_config(config: &VectorSearchConfig) -> Self {
+        Self {
+            model_id: config.model_id.clone(),
+            vector_dim: config.vector_dim,
+            distance: config.distance.as_str_name().to_string(),
+            named_vectors: true,
+        }
+    }
+
+    // Collections created before metadata was recorded carry no model id; their
+    // dimension, distance and vector names still come from the live params, so
+    // single-vector collections from before named vectors always need a migration.
+    pub fn accepts(&self, expected: &Self) -> bool {
+        self.named_vectors == expected.named_vectors
+            && self.vector_dim == expected.vector_dim
+            && self.distance == expected.distance
+            && (self.model_id.is_empty()
+                || expected.model_id.is_empty()
+                || self.model_id == expected.model_id)
+    }
+
+    fn from_collection_info(info: &CollectionInfo) -> Option<Self> {
+        let config = info.config.as_ref()?;
+        let (params, named_vectors) = match config
+            .params
+            .as_ref()?
+            .vectors_config
+            .as_ref()?
+            .config
+            .as_ref()?
+        {
+            vectors_config::Config::Params(params) => (params, false),
+            vectors_config::Config::ParamsMap(map) => (
+                map.map
+                    .get(VectorField::Code.name())
+                    .or_else(|| map.map.values().next())?,
+                VectorField::ALL
+                    .iter()
+                    .all(|field| map.map.contains_key(field.name())),
+            ),
+        };
+        let model_id = match config
+            .metadata
+            .get("model_id")
+            .and_then(|value| value.kind.as_ref())
+        {
+            Some(Kind::StringValue(model_id)) => model_id.clone(),
+            _ => String::new(),
+        };
+        Some(Self {
+            model_id,
+            vector_dim: params.size as usize,
+            distance: Distance::try_from(params.distance)
+                .unwrap_or(Distance::UnknownDistance)
+                .as_str_name()
+                .to_string(),
+            named_vectors,
+        })
+    }
+
+    fn to_payload(&self) -> HashMap<String, serde_json::Value> {
+        HashMap::from([
+            ("model_id".to_string(), self.model_id.clone().into()),
+            ("vector_dim".to_string(), self.vector_dim.into()),
+            ("distance".to_string(), self.distance.clone().into()),
+        ])
+    }
+}
+
+impl std::fmt::Display for CollectionMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let model = if self.model_id.is_empty() {
+            "unknown model"
+        } else {
+            self.model_id.as_str()
+        };
+        write!(f, "{model} ({}d, {}", self.vector_dim, self.distance)?;
+        if !self.named_vectors {
+            write!(f, ", missing named vectors")?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CollectionStatus {
+    Created,
+    Existing(CollectionMetadata),
+}
+
+impl CollectionStatus {
+    pub fn is_compatible(&self, expected: &CollectionMetadata) -> bool {
+        match self {
+            Self::Created => true,
+            Self::Existing(found) => found.accepts(expected),
         }
     }
 }
@@ -50,11 +170,32 @@ impl QdrantVectorStore {
         Self { config }
     }
 
-    pub async fn ensure_collection(&self, client: &Qdrant) -> Result<()> {
-        let mut builder =
-            CreateCollectionBuilder::new(self.config.collection.clone()).vectors_config(
+    pub fn collection(&self) -> &str {
+        &self.config.collection
+    }
+
+    pub fn metadata(&self) -> CollectionMetadata {
+        CollectionMetadata::from_config(&self.config)
+    }
+
+    pub async fn open_collection(&self, client: &Qdrant) -> Result<CollectionStatus> {
+        if client
+            .collection_exists(self.config.collection.clone())
+            .await?
+        {
+            return self.existing_metadata(client).await;
+        }
+
+        let mut vectors_config = VectorsConfigBuilder::default();
+        for field in VectorField::ALL {
+            vectors_config.add_named_vector_params(
+                field.name(),
                 VectorParamsBuilder::new(self.config.vector_dim as u64, self.config.distance),
             );
+        }
+        let mut builder = CreateCollectionBuilder::new(self.config.collection.clone())
+            .vectors_config(vectors_config)
+            .metadata(self.metadata().to_payload());
         builder = match self.config.quantization {
             QuantizationMode::None => builder,
             QuantizationMode::Int8 => {
@@ -65,29 +206,72 @@ impl QdrantVectorStore {
             ),
         };
 
-        let result = client.create_collection(builder).await;
-        if let Err(err) = result {
-            let msg = err.to_string().to_lowercase();
-            if !msg.contains("already exists") {
-                return Err(err.into());
+        match client.create_collection(builder).await {
+            Ok(_) => Ok(CollectionStatus::Created),
+            Err(err) if err.to_string().to_lowercase().contains("already exists") => {
+                self.existing_metadata(client).await
             }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn ensure_collection(&self, client: &Qdrant) -> Result<()> {
+        let expected = self.metadata();
+        if let CollectionStatus::Existing(found) = self.open_collection(client).await?
+            && !found.accepts(&expected)
+        {
+            bail!(
+                "vector collection {} holds {found} vectors but {expected} is configured; reindex to migrate it",
+                self.config.collection
+            );
         }
         Ok(())
     }
 
+    pub async fn drop_collection(&self, client: &Qdrant) -> Result<()> {
+        client
+            .delete_collection(self.config.collection.clone())
+            .await?;
+        Ok(())
+    }
+
+    async fn existing_metadata(&self, client: &Qdrant) -> Result<CollectionStatus> {
+        let info = client
+            .collection_info(self.config.collection.clone())
+            .await?;
+        info.result
+            .as_ref()
+            .and_then(CollectionMetadata::from_collection_info)
+            .map(CollectionStatus::Existing)
+            .ok_or_else(|| {
+                anyhow!(
+                    "vector collection {} has no readable vector params",
+                    self.config.collection
+                )
+            })
+    }
+
     pub async fn upsert_chunks(
         &self,
         client: &Qdrant,
         chunks: &[CodeChunk],
-        vectors: &[Vec<f32>],
+        vectors: &[ChunkVectors],
     ) -> Result<()> {
         let points = chunks
             .iter()
             .zip(vectors.iter())
-            .map(|(chunk, vector)| {
+            .map(|(chunk, vectors)| {
+                let named = VectorField::ALL
+                    .into_iter()
+                    .filter_map(|field| {
+                        vectors
+                            .get(field)
+                            .map(|vector| (field.name().to_string(), vector.to_vec()))
+                    })
+                    .collect::<HashMap<_, _>>();
                 PointStruct::new(
                     hash_id(&chunk.id),
-                    vector.clone(),
+                    named,
                     [
                         ("path", chunk.file_path.clone().into()),
                         ("chunk_id", chunk.id.clone().into()),
@@ -121,6 +305,7 @@ impl QdrantVectorStore {
     pub async fn search_similar_ids(
         &self,
         client: &Qdrant,
+        field: VectorField,
         query_vector: Vec<f32>,
         top_k: usize,
     ) -> Result<Vec<String>> {
@@ -128,6 +313,7 @@ impl QdrantVectorStore {
             .query(
                 QueryPointsBuilder::new(self.config.collection.clone())
                     .query(query_vector)
+                    .using(field.name())
                     .limit(top_k as u64)
                     .with_payload(true),
             )
@@ -146,6 +332,31 @@ impl QdrantVectorStore {
 
         Ok(ids)
     }
+
+    pub async fn stored_vector(
+        &self,
+        client: &Qdrant,
+        chunk_id: &str,
+        field: VectorField,
+    ) -> Result<Option<Vec<f32>>> {
+        let response = client
+            .get_points(
+                GetPointsBuilder::new(
+                    self.config.collection.clone(),
+                    vec![hash_id(chunk_id).into()],
+                )
+                .with_vectors(true),
+            )
+            .await?;
+        Ok(response
+            .result
+            .into_iter()
+            .filter_map(|pt| pt.vectors)
+            .find_map(|vectors| match vectors.get_vector_by_name(field.name()) {
+                Some(vector_output::Vector::Dense(dense)) => Some(dense.data),
+                _ => None,
+            }))
+    }
 }
 
 fn hash_id(id: &str) -> u64 {
@@ -156,9 +367,14 @@ fn hash_id(id: &str) -> u64 {
 
 #[cfg(test)]
 mod tests {
-    use qdrant_client::qdrant::Distance;
+    use std::collections::HashMap;
 
-    use super::{VectorSearchConfig, hash_id};
+    use qdrant_client::qdrant::{
+        CollectionConfig, CollectionInfo, CollectionParams, Distance, VectorParams,
+        VectorParamsMap, VectorsConfig, vectors_config,
+    };
+
+    use super::{CollectionMetadata, CollectionStatus, VectorSearchConfig, hash_id};
 
     #[test]
     fn defaults_to_cosine_and_hnsw_baseline() {
@@ -173,4 +389,95 @@ mod tests {
         assert_eq!(hash_id("chunk-1"), hash_id("chunk-1"));
         assert_ne!(hash_id("chunk-1"), hash_id("chunk-2"));
     }
+
+    fn vector_params(size: u64) -> VectorParams {
+        VectorParams {
+            size,
+            distance: Distance::Cosine.into(),
+            ..VectorParams::default()
+        }
+    }
+
+    fn named_params(size: u64, fields: &[&str]) -> vectors_config::Config {
+        vectors_config::Config::ParamsMap(VectorParamsMap {
+            map: fields
+                .iter()
+                .map(|field| (field.to_string(), vector_params(size)))
+                .collect(),
+        })
+    }
+
+    fn collection_info(vectors: vectors_config::Config, model_id: Option<&str>) -> CollectionInfo {
+        CollectionInfo {
+            config: Some(CollectionConfig {
+                params: Some(CollectionParams {
+                    vectors_config: Some(VectorsConfig {
+                        config: Some(vectors),
+                    }),
+                    ..CollectionParams::default()
+                }),
+                metadata: model_id
+                    .map(|model| HashMap::from([("model_id".to_string(), model.into())]))
+                    .unwrap_or_default(),
+                ..CollectionConfig::default()
+            }),
+            ..CollectionInfo::default()
+        }
+    }
+
+    fn stored(vectors: vectors_config::Config, model_id: Option<&str>) -> CollectionStatus {
+        CollectionStatus::Existing(
+            CollectionMetadata::from_collection_info(&collection_info(vectors, model_id))
+                .expect("metadata"),
+        )
+    }
+
+    fn expected() -> CollectionMetadata {
+        CollectionMetadata::from_config(&VectorSearchConfig {
+            model_id: "bge-small".to_string(),
+            ..VectorSearchConfig::default()
+        })
+    }
+
+    const ALL_FIELDS: [&str; 3] = ["code", "doc", "signature"];
+
+    #[test]
+    fn collection_metadata_detects_model_and_dimension_changes() {
+        let expected = expected();
+        assert!(stored(named_params(384, &ALL_FIELDS), Some("bge-small")).is_compatible(&expected));
+        assert!(stored(named_params(384, &ALL_FIELDS), None).is_compatible(&expected));
+        assert!(!stored(named_params(384, &ALL_FIELDS), Some("minilm")).is_compatible(&expected));
+        assert!(
+            !stored(named_params(768, &ALL_FIELDS), Some("bge-small")).is_compatible(&expected)
+        );
+        assert!(CollectionStatus::Created.is_compatible(&expected));
+        assert_eq!(
+            CollectionMetadata::from_collection_info(&collection_info(
+                named_params(768, &ALL_FIELDS),
+                Some("minilm")
+            ))
+            .expect("metadata")
+            .to_string(),
+            "minilm (768d, Cosine)"
+        );
+    }
+
+    #[test]
+    fn collection_missing_a_named_vector_is_incompatible() {
+        let partial = stored(named_params(384, &["code", "doc"]), None);
+        assert!(!partial.is_compatible(&expected()));
+    }
+
+    #[test]
+    fn single_vector_collection_needs_migration() {
+        let legacy = stored(vectors_config::Config::Params(vector_params(384)), None);
+        assert!(!legacy.is_compatible(&expected()));
+        let CollectionStatus::Existing(found) = legacy else {
+            panic!("existing collection");
+        };
+        assert_eq!(
+            found.to_string(),
+            "unknown model (384d, Cosine, missing named vectors)"
+        );
+    }
 }
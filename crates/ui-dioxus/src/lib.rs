@@ -1,2 +1,3 @@
+pub mod reindex;
 pub mod server;
 pub mod ui;
@@ -0,0 +1,441 @@
+use std::{
+    collections::HashSet,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Result, anyhow};
+use common::CodeChunk;
+use qdrant_client::Qdrant;
+use search_core::{ChunkVectors, EmbeddingTemplate, embed_chunk_views, vector::QdrantVectorStore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+
+const DEFAULT_UPSERT_BATCH: usize = 512;
+const IN_FLIGHT_BATCHES: usize = 2;
+
+#[derive(Debug, Clone)]
+pub struct ReindexOptions {
+    pub embed_batch_size: usize,
+    pub upsert_batch_size: usize,
+    pub checkpoint_path: PathBuf,
+}
+
+impl ReindexOptions {
+    pub fn new(embed_batch_size: usize, checkpoint_path: PathBuf) -> Self {
+        let upsert_batch_size = std::env::var("CODEVIX_REINDEX_UPSERT_BATCH")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_UPSERT_BATCH);
+        Self {
+            embed_batch_size: embed_batch_size.max(1),
+            upsert_batch_size: upsert_batch_size.max(1),
+            checkpoint_path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CheckpointHeader {
+    project_path: String,
+    model_id: String,
+    template_version: String,
+}
+
+// The checkpoint file is a header line followed by one key per upserted chunk. Keys hash the
+// chunk id with its content fingerprint, so a resumed run skips every chunk that is still
+// unchanged and re-embeds only the edited or new ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReindexCheckpoint {
+    header: CheckpointHeader,
+    committed: HashSet<String>,
+}
+
+impl ReindexCheckpoint {
+    pub fn new(project_path: &str, model_id: &str, template_version: &str) -> Self {
+        Self {
+            header: CheckpointHeader {
+                project_path: project_path.to_string(),
+                model_id: model_id.to_string(),
+                template_version: template_version.to_string(),
+            },
+            committed: HashSet::new(),
+        }
+    }
+
+    pub fn resume(mut self, path: &Path) -> Self {
+        let Ok(raw) = std::fs::read_to_string(path) else {
+            return self;
+        };
+        let mut lines = raw.lines();
+        let header = lines
+            .next()
+            .and_then(|line| serde_json::from_str::<CheckpointHeader>(line).ok());
+        if header.as_ref() == Some(&self.header) {
+            self.committed = lines.map(str::to_string).collect();
+        }
+        self
+    }
+
+    pub fn committed_chunks(&self, chunks: &[CodeChunk]) -> usize {
+        chunks.iter().filter(|chunk| self.contains(chunk)).count()
+    }
+
+    fn contains(&self, chunk: &CodeChunk) -> bool {
+        self.committed.contains(&chunk_key(chunk))
+    }
+
+    fn start(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut out = serde_json::to_string(&self.header)?;
+        out.push('\n');
+        for key in &self.committed {
+            out.push_str(key);
+            out.push('\n');
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, out)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    fn record(&mut self, path: &Path, chunks: &[CodeChunk]) -> Result<()> {
+        use std::io::Write;
+
+        let mut out = String::new();
+        for key in chunks.iter().map(chunk_key) {
+            out.push_str(&key);
+            out.push('\n');
+            self.committed.insert(key);
+        }
+        let mut file = std::fs::OpenOptions::new().append(true).open(path)?;
+        file.write_all(out.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn chunk_key(chunk: &CodeChunk) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(chunk.id.as_bytes());
+    hasher.update([0]);
+    hasher.update(chunk.fingerprint.as_bytes());
+    let digest = format!("{:x}", hasher.finalize());
+    digest[..24].to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReindexProgress {
+    pub committed_chunks: usize,
+    pub total_chunks: usize,
+    pub batches: usize,
+    pub resumed_from: usize,
+}
+
+pub trait VectorBatchSink {
+    fn upsert(
+        &mut self,
+        chunks: &[CodeChunk],
+        vectors: &[ChunkVectors],
+    ) -> impl Future<Output = Result<()>> + Send;
+}
+
+pub struct QdrantBatchSink<'a> {
+    pub store: &'a QdrantVectorStore,
+    pub client: &'a Qdrant,
+}
+
+impl<S: VectorBatchSink + Send> VectorBatchSink for &mut S {
+    fn upsert(
+        &mut self,
+        chunks: &[CodeChunk],
+        vectors: &[ChunkVectors],
+    ) -> impl Future<Output = Result<()>> + Send {
+        (**self).upsert(chunks, vectors)
+    }
+}
+
+impl VectorBatchSink for QdrantBatchSink<'_> {
+    async fn upsert(&mut self, chunks: &[CodeChunk], vectors: &[ChunkVectors]) -> Result<()> {
+        self.store.upsert_chunks(self.client, chunks, vectors).await
+    }
+}
+
+type EmbeddedBatch = Result<(Vec<CodeChunk>, Vec<ChunkVectors>)>;
+
+pub async fn stream_chunk_vectors<E, U, P>(
+    chunks: Arc<Vec<CodeChunk>>,
+    template: EmbeddingTemplate,
+    mut checkpoint: ReindexCheckpoint,
+    options: &ReindexOptions,
+    embed: E,
+    mut sink: U,
+    mut progress: P,
+) -> Result<ReindexProgress>
+where
+    E: Fn(&[String]) -> Result<Vec<Vec<f32>>> + Send + 'static,
+    U: VectorBatchSink,
+    P: FnMut(ReindexProgress),
+{
+    let pending = chunks
+        .iter()
+        .enumerate()
+        .filter(|(_, chunk)| !checkpoint.contains(chunk))
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+    let resumed_from = chunks.len() - pending.len();
+    checkpoint.start(&options.checkpoint_path)?;
+    let mut state = ReindexProgress {
+        committed_chunks: resumed_from,
+        total_chunks: chunks.len(),
+        batches: 0,
+        resumed_from,
+    };
+    let (tx, mut rx) = mpsc::channel::<EmbeddedBatch>(IN_FLIGHT_BATCHES);
+    let producer = {
+        let chunks = chunks.clone();
+        let upsert_batch = options.upsert_batch_size;
+        let embed_batch = options.embed_batch_size;
+        tokio::task::spawn_blocking(move || {
+            for group in pending.chunks(upsert_batch) {
+                let batch = group
+                    .iter()
+                    .map(|idx| chunks[*idx].clone())
+                    .collect::<Vec<_>>();
+                let embedded = embed_chunk_views(&batch, &template, |texts| {
+                    let mut out = Vec::with_capacity(texts.len());
+                    for group in texts.chunks(embed_batch) {
+                        out.extend(embed(group)?);
+                    }
+                    Ok(out)
+                })
+                .map(|vectors| (batch, vectors));
+                let failed = embedded.is_err();
+                if tx.blocking_send(embedded).is_err() || failed {
+                    return;
+                }
+            }
+        })
+    };
+
+    while let Some(batch) = rx.recv().await {
+        let (batch, vectors) = batch?;
+        sink.upsert(&batch, &vectors).await?;
+        checkpoint.record(&options.checkpoint_path, &batch)?;
+        state.committed_chunks += batch.len();
+        state.batches += 1;
+        progress(state);
+    }
+    producer
+        .await
+        .map_err(|err| anyhow!("embedding producer failed: {err}"))?;
+    if state.committed_chunks < state.total_chunks {
+        return Err(anyhow!(
+            "reindex stopped after {} of {} chunks",
+            state.committed_chunks,
+            state.total_chunks
+        ));
+    }
+    let _ = std::fs::remove_file(&options.checkpoint_path);
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use anyhow::{Result, anyhow};
+    use common::CodeChunk;
+    use search_core::{ChunkVectors, EmbeddingTemplate};
+
+    use super::{ReindexCheckpoint, ReindexOptions, VectorBatchSink, stream_chunk_vectors};
+
+    #[derive(Default)]
+    struct RecordingSink {
+        fail_after: Option<usize>,
+        upserted: Vec<String>,
+    }
+
+    impl VectorBatchSink for RecordingSink {
+        async fn upsert(&mut self, chunks: &[CodeChunk], vectors: &[ChunkVectors]) -> Result<()> {
+            assert_eq!(chunks.len(), vectors.len());
+            if self
+                .fail_after
+                .is_some_and(|limit| self.upserted.len() >= limit)
+            {
+                return Err(anyhow!("qdrant unavailable"));
+            }
+            self.upserted.extend(chunks.iter().map(|c| c.id.clone()));
+            Ok(())
+        }
+    }
+
+    fn chunks(count: usize) -> Arc<Vec<CodeChunk>> {
+        Arc::new(
+            (0..count)
+                .map(|idx| CodeChunk {
+                    id: format!("src/lib.rs:{idx}"),
+                    fingerprint: format!("fp{idx}"),
+                    file_path: "/nonexistent/src/lib.rs".to_string(),
+                    language: "rust".to_string(),
+                    symbol: Some(format!("f{idx}")),
+                    start_line: idx + 1,
+                    end_line: idx + 1,
+                    start_char: 0,
+                    end_char: 10,
+                    content: format!("fn f{idx}() {{}}"),
+                })
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn interrupted_reindex_resumes_from_last_committed_batch() {
+        let dir = std::env::temp_dir().join(format!("codivex-reindex-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let options = ReindexOptions {
+            embed_batch_size: 3,
+            upsert_batch_size: 2,
+            checkpoint_path: dir.join("checkpoint.json"),
+        };
+        let chunks = chunks(5);
+        let largest_embed = Arc::new(AtomicUsize::new(0));
+        let embed = {
+            let largest_embed = largest_embed.clone();
+            move |texts: &[String]| {
+                largest_embed.fetch_max(texts.len(), Ordering::SeqCst);
+                Ok(texts.iter().map(|_| vec![1.0, 0.0]).collect())
+            }
+        };
+        let checkpoint = || ReindexCheckpoint::new("/repo", "model", "v1");
+
+        let mut failing = RecordingSink {
+            fail_after: Some(2),
+            ..RecordingSink::default()
+        };
+        let err = stream_chunk_vectors(
+            chunks.clone(),
+            EmbeddingTemplate::default(),
+            checkpoint().resume(&options.checkpoint_path),
+            &options,
+            embed.clone(),
+            &mut failing,
+            |_| {},
+        )
+        .await
+        .expect_err("second batch fails");
+        assert!(err.to_string().contains("qdrant unavailable"));
+        assert_eq!(
+            checkpoint()
+                .resume(&options.checkpoint_path)
+                .committed_chunks(&chunks),
+            2
+        );
+
+        let mut resumed = RecordingSink::default();
+        let mut reports = Vec::new();
+        let done = stream_chunk_vectors(
+            chunks.clone(),
+            EmbeddingTemplate::default(),
+            checkpoint().resume(&options.checkpoint_path),
+            &options,
+            embed,
+            &mut resumed,
+            |progress| reports.push(progress.committed_chunks),
+        )
+        .await
+        .expect("resumed reindex");
+        assert_eq!(
+            resumed.upserted,
+            vec!["src/lib.rs:2", "src/lib.rs:3", "src/lib.rs:4"]
+        );
+        assert_eq!(reports, vec![4, 5]);
+        assert_eq!((done.resumed_from, done.batches), (2, 2));
+        assert!(largest_embed.load(Ordering::SeqCst) <= 3);
+        assert!(!options.checkpoint_path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn resumed_reindex_skips_unchanged_chunks_after_edits() {
+        let dir = std::env::temp_dir().join(format!("codivex-reindex-edit-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let options = ReindexOptions {
+            embed_batch_size: 8,
+            upsert_batch_size: 2,
+            checkpoint_path: dir.join("checkpoint.json"),
+        };
+        let embed = |texts: &[String]| Ok(texts.iter().map(|_| vec![1.0, 0.0]).collect());
+        let chunks = chunks(4);
+        let mut failing = RecordingSink {
+            fail_after: Some(2),
+            ..RecordingSink::default()
+        };
+        let _ = stream_chunk_vectors(
+            chunks.clone(),
+            EmbeddingTemplate::default(),
+            ReindexCheckpoint::new("/repo", "model", "v1").resume(&options.checkpoint_path),
+            &options,
+            embed,
+            &mut failing,
+            |_| {},
+        )
+        .await;
+
+        let mut edited = chunks.as_ref().clone();
+        edited[0].fingerprint = "fp0-edited".to_string();
+        edited.insert(1, {
+            let mut added = edited[3].clone();
+            added.id = "src/lib.rs:new".to_string();
+            added
+        });
+        let mut resumed = RecordingSink::default();
+        let done = stream_chunk_vectors(
+            Arc::new(edited),
+            EmbeddingTemplate::default(),
+            ReindexCheckpoint::new("/repo", "model", "v1").resume(&options.checkpoint_path),
+            &options,
+            embed,
+            &mut resumed,
+            |_| {},
+        )
+        .await
+        .expect("resumed reindex");
+        assert_eq!(
+            resumed.upserted,
+            vec![
+                "src/lib.rs:0",
+                "src/lib.rs:new",
+                "src/lib.rs:2",
+                "src/lib.rs:3"
+            ]
+        );
+        assert_eq!(done.resumed_from, 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn checkpoint_is_ignored_when_model_or_template_change() {
+        let dir =
+            std::env::temp_dir().join(format!("codivex-reindex-stale-{}", std::process::id()));
+        let path = dir.join("checkpoint.json");
+        let chunks = chunks(3);
+        let mut stored = ReindexCheckpoint::new("/repo", "model", "v1");
+        stored.start(&path).expect("start");
+        stored.record(&path, &chunks[..2]).expect("record");
+
+        let same = ReindexCheckpoint::new("/repo", "model", "v1").resume(&path);
+        assert_eq!(same.committed_chunks(&chunks), 2);
+        let other_model = ReindexCheckpoint::new("/repo", "model-2", "v1").resume(&path);
+        assert_eq!(other_model.committed_chunks(&chunks), 0);
+        let other_template = ReindexCheckpoint::new("/repo", "model", "v2").resume(&path);
+        assert_eq!(other_template.committed_chunks(&chunks), 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
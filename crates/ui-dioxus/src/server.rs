@@ -1,6 +1,8 @@
+use std::collections::HashSet;
 use std::convert::Infallible;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
 use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Instant;
 
 use axum::{
@@ -16,17 +18,22 @@ use common::ports::RuntimePorts;
 use common::projects::{self, IndexedChunk, IndexedProject};
 use common::{CodeChunk, OpenLocationParams, RpcRequest, SearchCodeParams};
 use dioxus::prelude::*;
-use embeddings::{EmbeddingConfig, EmbeddingEngine};
+use embeddings::{
+    EmbeddingCache, EmbeddingConfig, EmbeddingEngine, EmbeddingReadiness, hashed::base_model_id,
+};
 use qdrant_client::Qdrant;
+use search_core::EmbeddingTemplate;
 use search_core::lexical::TantivyLexicalIndex;
 use search_core::vector::{
-    QdrantVectorStore, QuantizationMode as VectorQuantizationMode, VectorSearchConfig,
+    CollectionStatus, QdrantVectorStore, QuantizationMode as VectorQuantizationMode,
+    VectorSearchConfig,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tokio_stream::StreamExt;
 use tokio_stream::wrappers::IntervalStream;
 
+use crate::reindex::{QdrantBatchSink, ReindexCheckpoint, ReindexOptions, stream_chunk_vectors};
 use crate::ui::AdminPage;
 
 #[derive(Clone)]
@@ -35,6 +42,7 @@ struct UiState {
     pid: u32,
     cwd: std::path::PathBuf,
     http: reqwest::Client,
+    embedder: Arc<EmbeddingEngine>,
 }
 
 #[derive(Debug, Serialize)]
@@ -43,6 +51,7 @@ struct UiDiagnostics {
     mcp_port: u16,
     metrics_port: Option<u16>,
     pid: u32,
+    embedding: EmbeddingReadiness,
 }
 
 pub async fn run_ui_server() -> anyhow::Result<()> {
@@ -61,9 +70,20 @@ pub async fn run_ui_server() -> anyhow::Result<()> {
     let state = UiState {
         ports: ports.clone(),
         pid: std::process::id(),
+        embedder: Arc::new(
+            EmbeddingEngine::new(EmbeddingConfig::default())
+                .with_cache(EmbeddingCache::from_env(&cwd.join(".codivex")))
+                .with_corpus_dir(Some(cwd.join(".codivex").join("corpus-stats"))),
+        ),
         cwd,
         http: reqwest::Client::new(),
     };
+    let embedder = state.embedder.clone();
+    tokio::task::spawn_blocking(move || {
+        if let Err(err) = embedder.warm_up() {
+            tracing::warn!(error = %err, "embedding engine unavailable");
+        }
+    });
 
     let app = build_router(state);
 
@@ -144,6 +164,8 @@ fn build_router(state: UiState) -> Router {
         .route("/api/project/select", post(api_project_select))
         .route("/api/index/action", post(api_index_action))
         .route("/api/agent-test", post(api_agent_test))
+        .route("/api/pins", get(api_pins_list).post(api_pins_add))
+        .route("/api/pins/remove", post(api_pins_remove))
         .with_state(state)
 }
 
@@ -172,12 +194,23 @@ folderPicker.setAttribute('webkitdirectory', '');
 folderPicker.setAttribute('directory', '');
 folderPicker.setAttribute('mozdirectory', '');
 
+function appendCell(tr, content) {{
+  const td = document.createElement('td');
+  td.style.cssText = 'border-bottom:1px solid #eee;padding:8px;';
+  if (content instanceof Node) td.appendChild(content);
+  else td.textContent = String(content);
+  tr.appendChild(td);
+}}
+
 function renderResults(items) {{
   const body = byId('result-tbody');
   body.innerHTML = '';
   for (const item of items) {{
     const tr = document.createElement('tr');
-    tr.innerHTML = `<td style="border-bottom:1px solid #eee;padding:8px;">${{item.file}}</td><td style="border-bottom:1px solid #eee;padding:8px;">${{item.function}}</td><td style="border-bottom:1px solid #eee;padding:8px;">${{item.start_line}}-${{item.end_line}}</td>`;
+    const label = item.pinned ? `${{item.function}} (pinned)` : item.function;
+    appendCell(tr, item.file);
+    appendCell(tr, label);
+    appendCell(tr, `${{item.start_line}}-${{item.end_line}}`);
     body.appendChild(tr);
   }}
 }}
@@ -187,11 +220,62 @@ function renderCatalog(projects) {{
   body.innerHTML = '';
   for (const project of projects || []) {{
     const tr = document.createElement('tr');
-    tr.innerHTML = `<td style="border-bottom:1px solid #eee;padding:8px;">${{project.project_path}}</td><td style="border-bottom:1px solid #eee;padding:8px;">${{project.files_scanned}}</td><td style="border-bottom:1px solid #eee;padding:8px;">${{project.chunks_extracted}}</td><td style="border-bottom:1px solid #eee;padding:8px;">${{project.indexed_at_unix}}</td>`;
+    appendCell(tr, project.project_path);
+    appendCell(tr, project.files_scanned);
+    appendCell(tr, project.chunks_extracted);
+    appendCell(tr, project.indexed_at_unix);
+    body.appendChild(tr);
+  }}
+}}
+
+function renderPins(data) {{
+  byId('pin-status').textContent = `Pins: ${{data.message || 'ok'}}`;
+  const body = byId('pins-tbody');
+  body.innerHTML = '';
+  for (const pin of data.pins || []) {{
+    const tr = document.createElement('tr');
+    const symbol = pin.symbol ? ` (${{pin.symbol}})` : '';
+    const remove = document.createElement('button');
+    remove.textContent = 'Remove';
+    remove.addEventListener('click', () => removePin(pin.id));
+    appendCell(tr, pin.id);
+    appendCell(tr, pin.query_pattern);
+    appendCell(tr, `${{pin.file}}:${{pin.start_line}}-${{pin.end_line}}${{symbol}}`);
+    appendCell(tr, remove);
     body.appendChild(tr);
   }}
 }}
 
+async function loadPins() {{
+  const res = await fetch('/api/pins');
+  renderPins(await res.json());
+}}
+
+async function addPin() {{
+  const [start, end] = byId('pin-lines').value.split('-').map((v) => Number(v.trim()) || null);
+  const res = await fetch('/api/pins', {{
+    method: 'POST',
+    headers: {{ 'content-type': 'application/json' }},
+    body: JSON.stringify({{
+      query_pattern: byId('pin-query').value,
+      file: byId('pin-file').value,
+      symbol: byId('pin-symbol').value || null,
+      start_line: start || null,
+      end_line: end || start || null
+    }})
+  }});
+  renderPins(await res.json());
+}}
+
+async function removePin(id) {{
+  const res = await fetch('/api/pins/remove', {{
+    method: 'POST',
+    headers: {{ 'content-type': 'application/json' }},
+    body: JSON.stringify({{ id }})
+  }});
+  renderPins(await res.json());
+}}
+
 function formatBytes(bytes) {{
   const value = Number(bytes || 0);
   if (value < 1024) return `${{value}} B`;
@@ -243,6 +327,7 @@ async function runSearch() {{
 }}
 
 byId('btn-search').addEventListener('click', runSearch);
+byId('btn-add-pin').addEventListener('click', addPin);
 
 byId('btn-select-repo').addEventListener('click', async () => {{
   if (window.showDirectoryPicker) {{
@@ -333,6 +418,10 @@ telemetryEvents.addEventListener('error', () => {{
   byId('index-action-status').textContent = 'Index status: telemetry stream disconnected';
 }});
 
+loadPins().catch(() => {{
+  byId('pin-status').textContent = 'Pins: unavailable';
+}});
+
 loadTelemetrySnapshot().catch(() => {{
   byId('index-action-status').textContent = 'Index status: telemetry unavailable';
 }});
@@ -418,6 +507,30 @@ struct AgentTestReport {
     sse_done_ok: bool,
 }
 
+#[derive(Debug, Deserialize)]
+struct PinAddRequest {
+    query_pattern: String,
+    file: String,
+    #[serde(default)]
+    symbol: Option<String>,
+    #[serde(default)]
+    start_line: Option<usize>,
+    #[serde(default)]
+    end_line: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PinRemoveRequest {
+    id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PinsResponse {
+    project: Option<String>,
+    message: String,
+    pins: Vec<projects::PinnedResult>,
+}
+
 fn default_top_k() -> usize {
     5
 }
@@ -435,6 +548,7 @@ async fn api_search(
             query: req.query,
             top_k: req.top_k.max(1),
             repo_filter: scope,
+            ..SearchCodeParams::default()
         })
         .unwrap_or_else(|_| json!({})),
     };
@@ -538,6 +652,7 @@ async fn api_smoke_test(State(state): State<UiState>) -> Json<SmokeTestResult> {
                 query: "iso_to_date".to_string(),
                 top_k: 2,
                 repo_filter: scope.clone(),
+                ..SearchCodeParams::default()
             })
             .unwrap_or_else(|_| json!({})),
         },
@@ -615,6 +730,91 @@ async fn api_project_select(Json(req): Json<ProjectSelectRequest>) -> Json<Proje
     })
 }
 
+async fn api_pins_list(State(state): State<UiState>) -> Json<PinsResponse> {
+    pins_response(&state, "ok".to_string())
+}
+
+async fn api_pins_add(
+    State(state): State<UiState>,
+    Json(req): Json<PinAddRequest>,
+) -> Json<PinsResponse> {
+    let message = match add_pin_for_selected(&state.cwd, req) {
+        Ok(pin) => format!(
+            "pinned {} ({}:{}-{})",
+            pin.id, pin.file, pin.start_line, pin.end_line
+        ),
+        Err(err) => format!("pin failed: {err}"),
+    };
+    pins_response(&state, message)
+}
+
+async fn api_pins_remove(
+    State(state): State<UiState>,
+    Json(req): Json<PinRemoveRequest>,
+) -> Json<PinsResponse> {
+    let message = match project_scope(&state) {
+        Some(project) => match projects::remove_pin(&state.cwd, &project, &req.id) {
+            Ok(true) => format!("removed {}", req.id),
+            Ok(false) => format!("pin not found: {}", req.id),
+            Err(err) => format!("remove failed: {err}"),
+        },
+        None => "no project selected".to_string(),
+    };
+    pins_response(&state, message)
+}
+
+fn pins_response(state: &UiState, message: String) -> Json<PinsResponse> {
+    let project = project_scope(state).filter(|p| !p.is_empty());
+    let pins = project
+        .as_deref()
+        .map(|p| projects::load_pins(&state.cwd, p))
+        .unwrap_or_default();
+    Json(PinsResponse {
+        project,
+        message,
+        pins,
+    })
+}
+
+fn add_pin_for_selected(cwd: &Path, req: PinAddRequest) -> anyhow::Result<projects::PinnedResult> {
+    let project = projects::read_selected_project(cwd)
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("no project selected"))?;
+    if req.query_pattern.trim().is_empty() || req.file.trim().is_empty() {
+        anyhow::bail!("query pattern and file are required");
+    }
+    let symbol = req.symbol.filter(|s| !s.trim().is_empty());
+    let (start_line, end_line) = match (req.start_line, req.end_line, symbol.as_deref()) {
+        (Some(start), end, _) if start > 0 => (start, end.unwrap_or(start).max(start)),
+        (_, _, Some(symbol)) => projects::load_project_index(cwd, &project)
+            .and_then(|indexed| {
+                indexed
+                    .chunks
+                    .iter()
+                    .find(|c| {
+                        search_core::pins::same_file(&c.file, &req.file)
+                            && c.symbol.as_deref() == Some(symbol)
+                    })
+                    .map(|c| (c.start_line, c.end_line))
+            })
+            .ok_or_else(|| anyhow::anyhow!("symbol {symbol} not found in {}", req.file))?,
+        _ => anyhow::bail!("provide a symbol or a line range"),
+    };
+    projects::add_pin(
+        cwd,
+        &project,
+        projects::PinnedResult {
+            id: String::new(),
+            query_pattern: req.query_pattern.trim().to_string(),
+            file: req.file.trim().to_string(),
+            symbol,
+            start_line,
+            end_line,
+            created_at_unix: unix_now(),
+        },
+    )
+}
+
 async fn api_index_action(
     State(state): State<UiState>,
     Json(req): Json<IndexActionRequest>,
@@ -629,9 +829,10 @@ async fn api_index_action(
 
     let started = Instant::now();
     let cwd = state.cwd.clone();
-    let (files_scanned, chunks_extracted) = run_index_action(&cwd, &action, Path::new(&repo_path))
-        .await
-        .unwrap_or((0, 0));
+    let (files_scanned, chunks_extracted) =
+        run_index_action(&cwd, &state.embedder, &action, Path::new(&repo_path))
+            .await
+            .unwrap_or((0, 0));
     Json(IndexActionResponse {
         action,
         path: repo_path,
@@ -641,19 +842,30 @@ async fn api_index_action(
     })
 }
 
-async fn run_index_action(cwd: &Path, action: &str, repo: &Path) -> anyhow::Result<(usize, usize)> {
+async fn run_index_action(
+    cwd: &Path,
+    engine: &Arc<EmbeddingEngine>,
+    action: &str,
+    repo: &Path,
+) -> anyhow::Result<(usize, usize)> {
     let repo_path = repo.display().to_string();
     if action == "clear" {
+        let vectors = projects::load_vector_collection_state(cwd, &repo_path);
         projects::remove_project_index(cwd, &repo_path)?;
         if let Some(client) = qdrant_client_from_env()? {
-            let _ = client
-                .delete_collection(projects::project_vector_collection(&repo_path))
-                .await;
+            let collections = std::iter::once(vectors.collection)
+                .chain(vectors.migration.map(|migration| migration.target));
+            for collection in collections {
+                let _ = client.delete_collection(collection).await;
+            }
         }
         return Ok((0, 0));
     }
 
+    let previous_model =
+        projects::load_project_index(cwd, &repo_path).and_then(|p| p.embedding_model);
     let repo = repo.to_path_buf();
+    let state_cwd = cwd.to_path_buf();
     let cwd = cwd.to_path_buf();
     let output = tokio::task::spawn_blocking(move || -> anyhow::Result<IndexActionOutput> {
         let files = indexer::scanner::scan_source_files(&repo);
@@ -662,21 +874,20 @@ async fn run_index_action(cwd: &Path, action: &str, repo: &Path) -> anyhow::Resu
         let mut code_chunks = Vec::new();
 
         for path in &files {
-            if let Ok(content) = std::fs::read_to_string(path) {
-                if let Ok(chunks) =
+            if let Ok(content) = std::fs::read_to_string(path)
+                && let Ok(chunks) =
                     indexer::extract_chunks_for_file(path.to_string_lossy().as_ref(), &content)
-                {
-                    chunk_count += chunks.len();
-                    for chunk in chunks {
-                        indexed_chunks.push(IndexedChunk {
-                            file: chunk.file_path.clone(),
-                            symbol: chunk.symbol.clone(),
-                            start_line: chunk.start_line,
-                            end_line: chunk.end_line,
-                            content: chunk.content.clone(),
-                        });
-                        code_chunks.push(chunk);
-                    }
+            {
+                chunk_count += chunks.len();
+                for chunk in chunks {
+                    indexed_chunks.push(IndexedChunk {
+                        file: chunk.file_path.clone(),
+                        symbol: chunk.symbol.clone(),
+                        start_line: chunk.start_line,
+                        end_line: chunk.end_line,
+                        content: chunk.content.clone(),
+                    });
+                    code_chunks.push(chunk);
                 }
             }
         }
@@ -688,29 +899,42 @@ async fn run_index_action(cwd: &Path, action: &str, repo: &Path) -> anyhow::Resu
             chunks_extracted: chunk_count,
             indexed_at_unix: unix_now(),
             chunks: indexed_chunks,
+            embedding_template_version: None,
+            embedding_model: None,
         };
-        projects::save_project_index(&cwd, &indexed)?;
         persist_tantivy_index(&cwd, &project_path, &code_chunks)?;
+        projects::save_project_index(&cwd, &indexed)?;
 
         Ok(IndexActionOutput {
             project_path,
             files_scanned: files.len(),
             chunks_extracted: chunk_count,
-            code_chunks,
+            code_chunks: Arc::new(code_chunks),
         })
     })
     .await??;
 
-    persist_qdrant_vectors(&output).await?;
+    if let Some(template_version) =
+        persist_qdrant_vectors(&state_cwd, engine, &output, previous_model.as_deref()).await?
+    {
+        projects::record_embedding_metadata(
+            &state_cwd,
+            &output.project_path,
+            &engine.corpus_model_id(&output.project_path),
+            &template_version,
+        )?;
+    }
     Ok((output.files_scanned, output.chunks_extracted))
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct IndexActionOutput {
     project_path: String,
     files_scanned: usize,
     chunks_extracted: usize,
-    code_chunks: Vec<CodeChunk>,
+    // Holds the text of every chunk for the whole run: embedding and upserts are batched,
+    // but the scan result itself is still proportional to the project size.
+    code_chunks: Arc<Vec<CodeChunk>>,
 }
 
 fn persist_tantivy_index(
@@ -728,40 +952,226 @@ fn persist_tantivy_index(
     Ok(())
 }
 
-async fn persist_qdrant_vectors(output: &IndexActionOutput) -> anyhow::Result<()> {
+async fn persist_qdrant_vectors(
+    cwd: &Path,
+    engine: &Arc<EmbeddingEngine>,
+    output: &IndexActionOutput,
+    previous_model: Option<&str>,
+) -> anyhow::Result<Option<String>> {
     if output.code_chunks.is_empty() {
-        return Ok(());
+        return Ok(None);
     }
     let Some(client) = qdrant_client_from_env()? else {
-        return Ok(());
+        return Ok(None);
     };
+    let project_path = output.project_path.as_str();
+    if migrations_in_flight()
+        .lock()
+        .expect("migrations in flight")
+        .contains(project_path)
+    {
+        tracing::info!(
+            project = project_path,
+            "vector collection migration already running, skipping re-embed"
+        );
+        return Ok(None);
+    }
 
-    let texts = output
-        .code_chunks
-        .iter()
-        .map(|chunk| chunk.content.clone())
-        .collect::<Vec<_>>();
-    let embedding_cfg = EmbeddingConfig::default();
-    let engine = EmbeddingEngine::new(embedding_cfg.clone());
-    let vectors = engine.embed_batch(&texts)?;
-    if vectors.is_empty() {
-        return Ok(());
+    let mut state = projects::load_vector_collection_state(cwd, project_path);
+    let active = vector_store(engine, state.collection.clone());
+    let status = active.open_collection(&client).await?;
+    let legacy_model_changed = matches!(
+        &status,
+        CollectionStatus::Existing(found) if found.model_id.is_empty()
+    ) && previous_model
+        .is_some_and(|model| base_model_id(model) != engine.model_id());
+    if status.is_compatible(&active.metadata()) && !legacy_model_changed {
+        if let Some(stale) = state.migration.take() {
+            let _ = client.delete_collection(stale.target).await;
+            projects::save_vector_collection_state(cwd, project_path, &state)?;
+        }
+        return reembed_collection(cwd, engine, output, &client, &active)
+            .await
+            .map(Some);
     }
 
-    let mut cfg = VectorSearchConfig {
-        collection: projects::project_vector_collection(&output.project_path),
-        ..VectorSearchConfig::default()
+    let migration = match state.migration.clone() {
+        Some(pending) if pending.model_id == engine.model_id() => pending,
+        pending => {
+            if let Some(stale) = pending {
+                let _ = client.delete_collection(stale.target).await;
+            }
+            state.begin_migration(project_path, engine.model_id(), unix_now())
+        }
     };
-    cfg.vector_dim = vectors[0].len();
-    cfg.quantization = to_vector_quantization_mode(embedding_cfg.quantization);
-    let store = QdrantVectorStore::new(cfg);
-    store.ensure_collection(&client).await?;
-    store
-        .upsert_chunks(&client, &output.code_chunks, &vectors)
-        .await?;
+    let target = vector_store(engine, migration.target.clone());
+    target.ensure_collection(&client).await?;
+    projects::save_vector_collection_state(cwd, project_path, &state)?;
+    tracing::info!(
+        project = project_path,
+        from = active.collection(),
+        to = target.collection(),
+        found = %match &status {
+            CollectionStatus::Existing(found) => found.to_string(),
+            CollectionStatus::Created => "new collection".to_string(),
+        },
+        expected = %target.metadata(),
+        "migrating vector collection; searches stay lexical until it completes"
+    );
+
+    migrations_in_flight()
+        .lock()
+        .expect("migrations in flight")
+        .insert(project_path.to_string());
+    let (cwd, engine, output) = (cwd.to_path_buf(), engine.clone(), output.clone());
+    tokio::spawn(async move {
+        let result = migrate_vector_collection(&cwd, &engine, &output, &client, &target).await;
+        migrations_in_flight()
+            .lock()
+            .expect("migrations in flight")
+            .remove(&output.project_path);
+        if let Err(err) = result {
+            tracing::warn!(
+                project = output.project_path,
+                collection = target.collection(),
+                error = %err,
+                "vector collection migration failed; reindex to resume it"
+            );
+        }
+    });
+    Ok(None)
+}
+
+async fn migrate_vector_collection(
+    cwd: &Path,
+    engine: &Arc<EmbeddingEngine>,
+    output: &IndexActionOutput,
+    client: &Qdrant,
+    target: &QdrantVectorStore,
+) -> anyhow::Result<()> {
+    let template_version = reembed_collection(cwd, engine, output, client, target).await?;
+    let mut state = projects::load_vector_collection_state(cwd, &output.project_path);
+    if state
+        .migration
+        .as_ref()
+        .is_none_or(|migration| migration.target != target.collection())
+    {
+        anyhow::bail!("migration to {} was superseded", target.collection());
+    }
+    let previous = state.finish_migration();
+    projects::save_vector_collection_state(cwd, &output.project_path, &state)?;
+    projects::record_embedding_metadata(
+        cwd,
+        &output.project_path,
+        &engine.corpus_model_id(&output.project_path),
+        &template_version,
+    )?;
+    if let Some(previous) = previous
+        && let Err(err) = client.delete_collection(previous.clone()).await
+    {
+        tracing::warn!(
+            project = output.project_path,
+            collection = previous,
+            error = %err,
+            "failed to drop vector collection after migration"
+        );
+    }
+    tracing::info!(
+        project = output.project_path,
+        collection = target.collection(),
+        "vector collection migration complete"
+    );
     Ok(())
 }
 
+async fn reembed_collection(
+    cwd: &Path,
+    engine: &Arc<EmbeddingEngine>,
+    output: &IndexActionOutput,
+    client: &Qdrant,
+    store: &QdrantVectorStore,
+) -> anyhow::Result<String> {
+    let embedding_cfg = EmbeddingConfig::default();
+    let template = EmbeddingTemplate::from_env().with_root(&output.project_path);
+    let options = ReindexOptions::new(
+        embedding_cfg.batch_size,
+        cwd.join(".codivex")
+            .join("reindex")
+            .join(format!("{}.json", store.collection())),
+    );
+    let checkpoint_for = |engine: &EmbeddingEngine| {
+        ReindexCheckpoint::new(
+            &output.project_path,
+            &engine.corpus_model_id(&output.project_path),
+            template.version(),
+        )
+    };
+    let mut checkpoint = checkpoint_for(engine).resume(&options.checkpoint_path);
+
+    let committed = checkpoint.committed_chunks(&output.code_chunks);
+    if committed > 0 {
+        tracing::info!(
+            project = output.project_path,
+            committed,
+            total = output.code_chunks.len(),
+            "resuming interrupted reindex"
+        );
+    } else {
+        let corpus = output
+            .code_chunks
+            .iter()
+            .map(|chunk| chunk.content.clone())
+            .collect::<Vec<_>>();
+        engine.fit_corpus(&output.project_path, &corpus);
+        // Refitting changes the corpus model id, so the checkpoint is keyed on the new one.
+        checkpoint = checkpoint_for(engine).resume(&options.checkpoint_path);
+    }
+    store.ensure_collection(client).await?;
+
+    let embed = {
+        let engine = engine.clone();
+        let project = output.project_path.clone();
+        move |texts: &[String]| engine.embed_documents(&project, texts)
+    };
+    let result = stream_chunk_vectors(
+        output.code_chunks.clone(),
+        template.clone(),
+        checkpoint,
+        &options,
+        embed,
+        QdrantBatchSink { store, client },
+        |progress| {
+            tracing::info!(
+                project = output.project_path,
+                collection = store.collection(),
+                committed = progress.committed_chunks,
+                total = progress.total_chunks,
+                batches = progress.batches,
+                "reindex progress"
+            );
+        },
+    )
+    .await;
+    engine.flush_cache();
+    result?;
+    Ok(template.version().to_string())
+}
+
+fn vector_store(engine: &EmbeddingEngine, collection: String) -> QdrantVectorStore {
+    QdrantVectorStore::new(VectorSearchConfig {
+        collection,
+        vector_dim: engine.vector_dim(),
+        quantization: to_vector_quantization_mode(EmbeddingConfig::default().quantization),
+        model_id: engine.model_id().to_string(),
+        ..VectorSearchConfig::default()
+    })
+}
+
+fn migrations_in_flight() -> &'static Mutex<HashSet<String>> {
+    static IN_FLIGHT: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    IN_FLIGHT.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
 fn qdrant_client_from_env() -> anyhow::Result<Option<Qdrant>> {
     let url = std::env::var("QDRANT_URL").ok();
     let Some(url) = url.filter(|v| !v.trim().is_empty()) else {
@@ -791,6 +1201,7 @@ async fn api_agent_test(State(state): State<UiState>) -> Json<AgentTestReport> {
                 query: "iso_to_date".to_string(),
                 top_k: 5,
                 repo_filter: scope.clone(),
+                ..SearchCodeParams::default()
             })
             .unwrap_or_else(|_| json!({})),
         },
@@ -810,6 +1221,7 @@ async fn api_agent_test(State(state): State<UiState>) -> Json<AgentTestReport> {
                 query: "convert iso string to date".to_string(),
                 top_k: 5,
                 repo_filter: scope,
+                ..SearchCodeParams::default()
             })
             .unwrap_or_else(|_| json!({})),
         },
@@ -915,6 +1327,7 @@ async fn port_diagnostics(State(state): State<UiState>) -> Json<UiDiagnostics> {
         mcp_port: state.ports.mcp_port,
         metrics_port: state.ports.metrics_port,
         pid: state.pid,
+        embedding: state.embedder.readiness(),
     })
 }
 
@@ -942,6 +1355,8 @@ mod tests {
         http::{Request, StatusCode},
     };
     use common::ports::RuntimePorts;
+    use embeddings::{EmbeddingConfig, EmbeddingEngine};
+    use std::sync::Arc;
     use tower::ServiceExt;
 
     #[tokio::test]
@@ -955,6 +1370,7 @@ mod tests {
             pid: 1,
             cwd: std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from(".")),
             http: reqwest::Client::new(),
+            embedder: Arc::new(EmbeddingEngine::new(EmbeddingConfig::default())),
         });
         let req = Request::builder()
             .method("GET")
@@ -968,4 +1384,42 @@ mod tests {
         assert!(text.contains("Codivex Admin"));
         assert!(text.contains("Project Indexing"));
     }
+
+    #[tokio::test]
+    async fn pins_api_adds_and_lists_for_selected_project() {
+        let cwd = std::env::temp_dir().join(format!("codivex-ui-pins-{}", std::process::id()));
+        let repo = cwd.join("repo");
+        std::fs::create_dir_all(&repo).expect("repo");
+        let repo = repo.display().to_string();
+        common::projects::write_selected_project(&cwd, &repo).expect("select");
+        for pin in common::projects::load_pins(&cwd, &repo) {
+            let _ = common::projects::remove_pin(&cwd, &repo, &pin.id);
+        }
+        let app = build_router(UiState {
+            ports: RuntimePorts {
+                mcp_port: 38080,
+                ui_port: 38181,
+                metrics_port: None,
+            },
+            pid: 1,
+            cwd: cwd.clone(),
+            http: reqwest::Client::new(),
+            embedder: Arc::new(EmbeddingEngine::new(EmbeddingConfig::default())),
+        });
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/pins")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"query_pattern":"build router","file":"src/app.rs","start_line":3,"end_line":9}"#,
+            ))
+            .expect("request");
+        let res = app.oneshot(req).await.expect("response");
+        let body = to_bytes(res.into_body(), usize::MAX).await.expect("body");
+        let payload: serde_json::Value = serde_json::from_slice(&body).expect("json");
+        assert_eq!(payload["pins"][0]["file"], "src/app.rs");
+        assert_eq!(payload["pins"][0]["end_line"], 9);
+        assert_eq!(common::projects::load_pins(&cwd, &repo).len(), 1);
+        let _ = std::fs::remove_dir_all(&cwd);
+    }
 }
@@ -65,6 +65,30 @@ pub fn AdminPage(mcp_endpoint: String, ui_endpoint: String) -> Element {
                 }
             }
 
+            section {
+                style: panel_style(),
+                h2 { "Pinned Results" }
+                div { style: "display:flex; gap:8px; flex-wrap:wrap;",
+                    input { id: "pin-query", r#type: "text", placeholder: "Query pattern, e.g. where is auth checked", style: input_style() }
+                    input { id: "pin-file", r#type: "text", placeholder: "File, e.g. src/auth.rs", style: "min-width:220px;padding:8px;border:1px solid #bbb;border-radius:6px;" }
+                    input { id: "pin-symbol", r#type: "text", placeholder: "Symbol (optional)", style: "width:160px;padding:8px;border:1px solid #bbb;border-radius:6px;" }
+                    input { id: "pin-lines", r#type: "text", placeholder: "Lines 10-40", style: "width:100px;padding:8px;border:1px solid #bbb;border-radius:6px;" }
+                    button { id: "btn-add-pin", style: button_style(), "Pin" }
+                }
+                p { id: "pin-status", "Pins: loading" }
+                table { style: "width:100%; border-collapse:collapse;",
+                    thead {
+                        tr {
+                            th { style: th_style(), "Id" }
+                            th { style: th_style(), "Pattern" }
+                            th { style: th_style(), "Target" }
+                            th { style: th_style(), "" }
+                        }
+                    }
+                    tbody { id: "pins-tbody" }
+                }
+            }
+
             section {
                 style: panel_style(),
                 h2 { "Live Health" }